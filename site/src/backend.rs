@@ -303,6 +303,17 @@ impl SysBackend for WebBackend {
     fn trash(&self, path: &str) -> Result<(), String> {
         self.delete(path)
     }
+    fn create_dir(&self, _path: &str) -> Result<(), String> {
+        Ok(())
+    }
+    fn rename(&self, from: &str, to: &str) -> Result<(), String> {
+        let contents = self.file_read_all(from.as_ref())?;
+        self.delete(from)?;
+        self.file_write_all(to.as_ref(), &contents)
+    }
+    fn file_size(&self, path: &str) -> Result<u64, String> {
+        self.file(path.as_ref(), |contents| contents.len() as u64)
+    }
     fn play_audio(&self, wav_bytes: Vec<u8>) -> Result<(), String> {
         (self.stdout.lock().unwrap()).push(OutputItem::Audio(wav_bytes));
         Ok(())
@@ -406,6 +406,8 @@ impl Allowed {
                 ("gifs", &[PrimClass::Sys(SysOpClass::Gifs)]),
                 ("audio", &[PrimClass::Sys(SysOpClass::Audio)]),
                 ("tcp", &[PrimClass::Sys(SysOpClass::Tcp)]),
+                ("udp", &[PrimClass::Sys(SysOpClass::Udp)]),
+                ("time", &[PrimClass::Sys(SysOpClass::Time)]),
                 ("env", &[PrimClass::Sys(SysOpClass::Env)]),
                 ("command", &[PrimClass::Sys(SysOpClass::Command)]),
                 ("filesystem", &[PrimClass::Sys(SysOpClass::Filesystem)]),
@@ -545,6 +547,8 @@ impl Allowed {
                         SysOpClass::Images => ("System - Images".into_view(), "Work with static images"),
                         SysOpClass::Gifs => ("System - GIFs".into_view(), "Work with animated GIFs"),
                         SysOpClass::Tcp => ("System - TCP".into_view(), "Work with TCP sockets"),
+                        SysOpClass::Udp => ("System - UDP".into_view(), "Work with UDP sockets"),
+                        SysOpClass::Time => ("System - Time".into_view(), "Work with dates and times"),
                         SysOpClass::Ffi => ("System - FFI".into_view(), "Foreign function interface"),
                         SysOpClass::Misc => ("System - Misc".into_view(), ""),
                     }
@@ -412,9 +412,12 @@ fn prim_docs() {
     for prim in Primitive::non_deprecated() {
         for line in &prim.doc().lines {
             if let PrimDocLine::Example(ex) = line {
-                if ["&sl", "&tcpc", "&tlsc", "&ast", "&clset"]
-                    .iter()
-                    .any(|prim| ex.input().contains(prim))
+                if [
+                    "&sl", "&tcpc", "&tlsc", "&ast", "&clset", "&udpb", "&httpr", "&httpw",
+                    "&httpc", "&runp",
+                ]
+                .iter()
+                .any(|prim| ex.input().contains(prim))
                 {
                     continue;
                 }
@@ -1724,6 +1724,8 @@ fn TutorialModules() -> impl IntoView {
         <p>"On the site, code is pulled from a "<code>"lib.ua"</code>" file at the root of the repository. Loading other files is not supported."</p>
         <p>"To use Git modules in the "<A href="/docs/install">"native interpreter"</A>", you must have Git installed. The repository is added as a Git submodule and the "<code>"lib.ua"</code>" file is loaded as the module's contents."</p>
         <p>"The native interpreter also supports adding an additional "<code>"branch: <branch-name>"</code>" specifier after the URL."</p>
+        <p>"To pin a Git module to a tag or commit, declare it in a "<code>"uiua.toml"</code>" manifest next to your code. "<code>"uiua init"</code>" creates one. The commit each dependency resolves to is recorded in "<code>"uiua.lock"</code>", so the same code is used on every machine."</p>
+        <Editor example="# uiua.toml\n# [dependencies]\n# example = { git = \"github.com/uiua-lang/example-module\", tag = \"v1.0\" }"/>
         <p>"You can find a curated list of Uiua modules "<a href="https://github.com/uiua-lang/uiua-modules">"here"</a>"."</p>
     }
 }
@@ -1749,6 +1751,8 @@ fn TutorialTesting() -> impl IntoView {
         <p>"If the result does not match the expectation, that incorrect result will be thrown."</p>
         <Editor example="---\n⍤⟜≍: 4 +2 2 # Passes\n---"/>
         <Editor example="---\n⍤⟜≍: [2 3 5] +1 [1 2 3]\n--- # ↓↓↓↓↓↓↓"/> // Should fail
+        <p>"A comment on the opening "<code>"---"</code>" gives a test scope a name. "<code>"uiua test --filter <pattern>"</code>" only runs the test scopes whose names contain the pattern."</p>
+        <Editor example="--- # addition\n⍤⟜≍: 4 +2 2\n---"/>
 
         <Hd id="run-modes">"Run Modes"</Hd>
         <p>"Whether tests will run or not depends on how you run the code."</p>
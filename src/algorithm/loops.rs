@@ -111,6 +111,7 @@ fn repeat_impl(f: Function, n: f64, env: &mut Uiua) -> UiuaResult {
         let mut prev = env.pop(1)?;
         env.push(prev.clone());
         loop {
+            env.respect_execution_limit()?;
             env.call(f.clone())?;
             let next = env.pop("converging function result")?;
             let converged = next == prev;
@@ -138,6 +139,7 @@ fn repeat_impl(f: Function, n: f64, env: &mut Uiua) -> UiuaResult {
             }
         }
         for _ in 0..n {
+            env.respect_execution_limit()?;
             env.call(f.clone())?;
         }
     }
@@ -177,6 +179,7 @@ pub fn do_(env: &mut Uiua) -> UiuaResult {
         _ => {}
     }
     loop {
+        env.respect_execution_limit()?;
         // Make sure there are enough values
         if env.stack().len() < copy_count {
             // Pop until it fails
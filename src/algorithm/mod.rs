@@ -13,8 +13,9 @@ use std::{
 use tinyvec::TinyVec;
 
 use crate::{
-    Array, ArrayValue, CodeSpan, ExactDoubleIterator, Function, Inputs, PersistentMeta, Shape,
-    Signature, Span, TempStack, Uiua, UiuaError, UiuaErrorKind, UiuaResult, Value,
+    cowslice::would_exceed_memory_limit, Array, ArrayValue, CodeSpan, ExactDoubleIterator,
+    Function, Inputs, PersistentMeta, Shape, Signature, Span, TempStack, Uiua, UiuaError,
+    UiuaErrorKind, UiuaResult, Value,
 };
 
 mod dyadic;
@@ -100,7 +101,8 @@ pub(crate) fn validate_size_impl(
     } else {
         4096
     };
-    if size > (max_mega * 1024usize.pow(2)) as f64 {
+    // Exceeding the memory limit is reported by the runtime
+    if size > (max_mega * 1024usize.pow(2)) as f64 || would_exceed_memory_limit(size as usize) {
         return Err(SizeError(elements));
     }
     Ok(elements as usize)
@@ -564,6 +566,9 @@ pub fn try_(env: &mut Uiua) -> UiuaResult {
     }
     let backup = env.clone_stack_top(f_sig.args.min(handler_sig.args))?;
     if let Err(mut err) = env.call_clean_stack(f) {
+        if let UiuaErrorKind::Interrupted(..) = err.kind {
+            return Err(err);
+        }
         if err.is_case {
             err.is_case = false;
             return Err(err);
@@ -5,9 +5,9 @@ use ecow::{eco_vec, EcoString, EcoVec};
 use serde::*;
 
 use crate::{
-    is_ident_char, CodeSpan, DynamicFunction, FuncSlice, Function, Ident, ImplPrimitive, InputSrc,
-    Instr, IntoInputSrc, LocalName, Primitive, Signature, Span, StackSwizzle, TempStack, Uiua,
-    UiuaResult, Value,
+    check::instrs_signature, is_ident_char, CodeSpan, DynamicFunction, FuncSlice, Function, Ident,
+    ImplPrimitive, InputSrc, Instr, IntoInputSrc, LocalName, Primitive, Signature, Span,
+    StackSwizzle, TempStack, TestScope, Uiua, UiuaResult, Value,
 };
 
 /// A compiled Uiua assembly
@@ -16,6 +16,8 @@ pub struct Assembly {
     pub(crate) instrs: EcoVec<Instr>,
     /// The sections of the instructions that are top-level expressions
     pub(crate) top_slices: Vec<FuncSlice>,
+    /// The test scopes among the top-level expressions
+    pub(crate) test_scopes: Vec<TestScope>,
     /// A list of global bindings
     pub bindings: EcoVec<BindingInfo>,
     pub(crate) spans: EcoVec<Span>,
@@ -30,6 +32,7 @@ impl Default for Assembly {
         Self {
             instrs: EcoVec::new(),
             top_slices: Vec::new(),
+            test_scopes: Vec::new(),
             spans: eco_vec![Span::Builtin],
             bindings: EcoVec::new(),
             dynamic_functions: EcoVec::new(),
@@ -103,6 +106,17 @@ impl Assembly {
     /// Make top-level expressions not run
     pub fn remove_top_level(&mut self) {
         self.top_slices.clear();
+        self.test_scopes.clear();
+    }
+    /// Infer the signature of the top-level expressions
+    ///
+    /// Returns an error message if the signature cannot be inferred
+    pub fn top_level_signature(&self) -> Result<Signature, String> {
+        let instrs: Vec<Instr> = (self.top_slices.iter())
+            .flat_map(|&slice| self.instrs(slice))
+            .cloned()
+            .collect();
+        instrs_signature(&instrs).map_err(|e| e.message)
     }
     /// Parse a `.uasm` file into an assembly
     pub fn from_uasm(src: &str) -> Result<Self, String> {
@@ -200,6 +214,7 @@ impl Assembly {
         Ok(Self {
             instrs,
             top_slices,
+            test_scopes: Vec::new(),
             bindings,
             spans,
             inputs: Inputs {
@@ -23,6 +23,22 @@ pub enum Item {
     TestScope(Sp<Vec<Item>>),
 }
 
+/// Get the name of a test scope
+///
+/// This is the text of a comment on the same line as the opening `---`.
+pub(crate) fn test_scope_name(scope: &Sp<Vec<Item>>) -> Option<Sp<String>> {
+    let Some(Item::Words(lines)) = scope.value.first() else {
+        return None;
+    };
+    let word = (lines.first()?.iter()).find(|word| !matches!(word.value, Word::Spaces))?;
+    match &word.value {
+        Word::Comment(name) if word.span.start.line == scope.span.start.line => {
+            Some(word.span.clone().sp(name.clone()))
+        }
+        _ => None,
+    }
+}
+
 /// A binding
 #[derive(Debug, Clone)]
 pub struct Binding {
@@ -4,13 +4,13 @@ mod modifier;
 use std::{
     cell::RefCell,
     collections::{hash_map::DefaultHasher, BTreeSet, HashMap, HashSet},
-    env::current_dir,
+    env::{self, current_dir},
     fmt, fs,
     hash::{Hash, Hasher},
     iter::repeat,
     mem::{replace, take},
     panic::{catch_unwind, AssertUnwindSafe},
-    path::{Path, PathBuf},
+    path::{Component, Path, PathBuf},
     rc::Rc,
     sync::Arc,
 };
@@ -28,11 +28,13 @@ use crate::{
     ident_modifier_args,
     lex::{CodeSpan, Sp, Span},
     lsp::{CodeMeta, SigDecl},
+    manifest::{git_module_path, normalize_git_url, project_dir},
     optimize::{optimize_instrs, optimize_instrs_mut},
     parse::{count_placeholders, parse, split_words, unsplit_words},
-    Array, Assembly, BindingKind, Boxed, Diagnostic, DiagnosticKind, DocComment, Ident,
-    ImplPrimitive, InputSrc, IntoInputSrc, IntoSysBackend, Primitive, RunMode, SemanticComment,
-    SysBackend, Uiua, UiuaError, UiuaErrorKind, UiuaResult, Value, CONSTANTS, EXAMPLE_UA, VERSION,
+    Array, Assembly, BindingInfo, BindingKind, Boxed, Diagnostic, DiagnosticKind, DocComment,
+    Ident, ImplPrimitive, InputSrc, IntoInputSrc, IntoSysBackend, Primitive, RunMode,
+    SemanticComment, SysBackend, TestScope, TypedFunction, Uiua, UiuaError, UiuaErrorKind,
+    UiuaResult, Value, CONSTANTS, EXAMPLE_UA, VERSION,
 };
 
 /// The Uiua compiler
@@ -56,6 +58,12 @@ pub struct Compiler {
     current_imports: Vec<PathBuf>,
     /// The bindings of imported files
     imports: HashMap<PathBuf, Import>,
+    /// The paths of git modules by url
+    git_imports: IndexMap<(String, Option<String>), PathBuf>,
+    /// A local directory to load git modules from instead of fetching them
+    module_root: Option<PathBuf>,
+    /// Directories to search for imports that are not relative to the importing file
+    search_paths: Vec<PathBuf>,
     /// Unexpanded stack macros
     stack_macros: HashMap<usize, StackMacro>,
     /// Unexpanded array macros
@@ -93,6 +101,9 @@ impl Default for Compiler {
             mode: RunMode::All,
             current_imports: Vec::new(),
             imports: HashMap::new(),
+            git_imports: IndexMap::new(),
+            module_root: None,
+            search_paths: Vec::new(),
             stack_macros: HashMap::new(),
             array_macros: HashMap::new(),
             macro_depth: 0,
@@ -108,6 +119,18 @@ impl Default for Compiler {
     }
 }
 
+/// The environment variable that lists directories to search for imports
+pub const SEARCH_PATH_VAR: &str = "UIUA_PATH";
+
+/// Get the import search directories listed in the `UIUA_PATH` environment variable
+///
+/// Directories are separated like in `PATH`.
+pub fn env_search_paths() -> Vec<PathBuf> {
+    env::var_os(SEARCH_PATH_VAR)
+        .map(|paths| env::split_paths(&paths).collect())
+        .unwrap_or_default()
+}
+
 /// An imported module
 #[derive(Clone)]
 pub struct Import {
@@ -219,6 +242,8 @@ pub enum PreEvalMode {
 
 const MAX_PRE_EVAL_ELEMS: usize = 1000;
 const MAX_PRE_EVAL_RANK: usize = 4;
+/// Expressions that allocate more than this are evaluated at runtime instead
+const MAX_PRE_EVAL_BYTES: usize = 1 << 24;
 
 impl PreEvalMode {
     fn matches_instrs(&self, instrs: &[Instr], asm: &Assembly) -> bool {
@@ -259,7 +284,11 @@ impl Compiler {
     }
     /// Set the compiler's assembly
     pub fn with_assembly(self, asm: Assembly) -> Self {
-        Self { asm, ..self }
+        Self {
+            next_global: asm.bindings.len(),
+            asm,
+            ..self
+        }
     }
     /// Get a reference to the assembly
     pub fn assembly(&self) -> &Assembly {
@@ -281,6 +310,29 @@ impl Compiler {
     pub fn finish(&mut self) -> Assembly {
         take(&mut self.asm)
     }
+    /// Get the names bound in the current scope along with their binding info
+    pub fn bindings(&self) -> impl Iterator<Item = (&Ident, &BindingInfo)> {
+        (self.scope.names.iter())
+            .filter_map(|(name, local)| Some((name, self.asm.bindings.get(local.index)?)))
+    }
+    /// Get the names bound in the current scope along with their global indices
+    pub fn binding_indices(&self) -> impl Iterator<Item = (&Ident, usize)> {
+        (self.scope.names.iter()).map(|(name, local)| (name, local.index))
+    }
+    /// Bind a name in the current scope to an existing global binding
+    ///
+    /// Returns `false` if there is no global binding at the index
+    pub fn bind_existing(&mut self, name: impl Into<Ident>, index: usize) -> bool {
+        let Some(binding) = self.asm.bindings.get(index) else {
+            return false;
+        };
+        let local = LocalName {
+            index,
+            public: binding.public,
+        };
+        self.scope.names.insert(name.into(), local);
+        true
+    }
     /// Set whether to evaluate `comptime`
     pub fn comptime(&mut self, comptime: bool) -> &mut Self {
         self.comptime = comptime;
@@ -305,6 +357,31 @@ impl Compiler {
         self.mode = mode;
         self
     }
+    /// Set a local directory to load git modules from instead of fetching them
+    ///
+    /// Modules are expected at `<root>/<host>/<owner>/<repo>/lib.ua`, the layout that `uiua vendor` creates.
+    pub fn module_root(&mut self, root: Option<PathBuf>) -> &mut Self {
+        self.module_root = root;
+        self
+    }
+    /// Add directories to search for imports that are not found relative to the importing file
+    ///
+    /// Directories are searched in the order they are added.
+    /// A module `name` can be either `name.ua` or `name/lib.ua` in a search directory.
+    pub fn search_paths<P: Into<PathBuf>>(
+        &mut self,
+        paths: impl IntoIterator<Item = P>,
+    ) -> &mut Self {
+        self.search_paths.extend(paths.into_iter().map(Into::into));
+        self
+    }
+    /// Get the urls of the git modules that have been imported along with the paths of their `lib.ua` files
+    ///
+    /// The branch is included if the import specified one.
+    pub fn git_imports(&self) -> impl Iterator<Item = (&str, Option<&str>, &Path)> {
+        (self.git_imports.iter())
+            .map(|((url, branch), path)| (url.as_str(), branch.as_deref(), path.as_path()))
+    }
     /// Get the backend
     pub fn backend(&self) -> Arc<dyn SysBackend> {
         self.macro_env.rt.backend.clone()
@@ -368,6 +445,7 @@ impl Compiler {
     fn load_impl(&mut self, input: &str, src: InputSrc) -> UiuaResult<&mut Self> {
         let instrs_start = self.asm.instrs.len();
         let top_slices_start = self.asm.top_slices.len();
+        let test_scopes_start = self.asm.test_scopes.len();
         let (items, errors, diagnostics) = parse(input, src.clone(), &mut self.asm.inputs);
         if self.print_diagnostics {
             for diagnostic in diagnostics {
@@ -406,6 +484,7 @@ impl Compiler {
             Err(e) | Ok(Err(e)) => {
                 self.asm.instrs.truncate(instrs_start);
                 self.asm.top_slices.truncate(top_slices_start);
+                self.asm.test_scopes.truncate(test_scopes_start);
                 self.errors.push(e);
             }
             _ => {}
@@ -495,7 +574,17 @@ code:
         let mut lines = match item {
             Item::TestScope(items) => {
                 prev_comment.take();
+                let name = test_scope_name(&items);
+                let start = self.asm.top_slices.len();
                 self.in_scope(ScopeKind::Test, |env| env.items(items.value, true))?;
+                let end = self.asm.top_slices.len();
+                (self.asm.test_scopes).push(TestScope {
+                    name: name
+                        .map(|name| name.value.trim().to_string())
+                        .filter(|name| !name.is_empty()),
+                    span: items.span,
+                    slices: start..end,
+                });
                 return Ok(());
             }
             Item::Words(lines) => lines,
@@ -567,8 +656,16 @@ code:
                     // Try to evaluate at comptime
                     // This can be done when there are at least as many push instructions
                     // preceding the current line as there are arguments to the line
+                    // and they are still part of the top level
+                    let mut top_level_before = 0;
+                    for slice in self.asm.top_slices.iter().rev() {
+                        if slice.start + slice.len != instr_count_before - top_level_before {
+                            break;
+                        }
+                        top_level_before += slice.len;
+                    }
                     if !instrs.is_empty()
-                        && instr_count_before >= sig.args
+                        && top_level_before >= sig.args
                         && (self.asm.instrs.iter().take(instr_count_before).rev())
                             .take(sig.args)
                             .all(|instr| matches!(instr, Instr::Push(_)))
@@ -719,22 +816,32 @@ code:
                 branch = Some(b.trim());
             }
             // Git import
-            let mut url = url.trim().trim_end_matches(".git").to_string();
-            if ![".com", ".net", ".org", ".io", ".dev"]
-                .iter()
-                .any(|s| url.contains(s))
-            {
-                if !url.starts_with('/') {
-                    url = format!("/{url}");
+            let url = normalize_git_url(url);
+            let path = if let Some(root) = &self.module_root {
+                let path = git_module_path(&url, branch)
+                    .map(|path| root.join(path).join("lib.ua"))
+                    .map_err(|e| self.fatal_error(span.clone(), e))?;
+                if !path.exists() {
+                    return Err(self.fatal_error(
+                        span.clone(),
+                        format!(
+                            "Module {url} was not found in {}. \
+                            Run `uiua vendor` to copy it there.",
+                            root.display()
+                        ),
+                    ));
                 }
-                url = format!("github.com{url}");
-            }
-            if !(url.starts_with("https://") || url.starts_with("http://")) {
-                url = format!("https://{url}");
-            }
-            self.backend()
-                .load_git_module(&url, branch)
-                .map_err(|e| self.fatal_error(span.clone(), e))?
+                path
+            } else {
+                // The project is the directory of the file being compiled
+                let project =
+                    project_dir(self.current_imports.first().map(PathBuf::as_path)).to_path_buf();
+                self.backend()
+                    .load_project_git_module(&url, branch, &project)
+                    .map_err(|e| self.fatal_error(span.clone(), e))?
+            };
+            (self.git_imports).insert((url, branch.map(Into::into)), path.clone());
+            path
         } else {
             // Normal import
             self.resolve_import_path(Path::new(path_str))
@@ -787,6 +894,24 @@ code:
         if !target.exists() && target.extension().is_none() {
             target = target.with_extension("ua");
         }
+        // Bare imports that are not next to the importing file are looked up in the search paths
+        let is_bare =
+            (path.components().next()).is_some_and(|comp| matches!(comp, Component::Normal(_)));
+        if !target.exists() && is_bare {
+            let found = self.search_paths.iter().find_map(|dir| {
+                let target = dir.join(path);
+                [
+                    target.clone(),
+                    target.with_extension("ua"),
+                    target.join("lib.ua"),
+                ]
+                .into_iter()
+                .find(|target| target.is_file())
+            });
+            if let Some(found) = found {
+                target = found;
+            }
+        }
         let base = Path::new(".");
         if let (Ok(canon_target), Ok(canon_base)) = (target.canonicalize(), base.canonicalize()) {
             pathdiff::diff_paths(canon_target, canon_base).unwrap_or(target)
@@ -2107,6 +2232,13 @@ code:
             eco_vec![Instr::Dynamic(DynamicFunction { index, signature })],
         )
     }
+    /// Create a function from a Rust function with typed arguments and output
+    ///
+    /// The signature is derived from the function's type.
+    /// See [`TypedFunction`] for details.
+    pub fn create_typed_function<Args, F: TypedFunction<Args>>(&mut self, f: F) -> Function {
+        self.create_function(F::signature(), move |env| f.call_typed(env))
+    }
     /// Bind a function in the current scope
     ///
     /// # Errors
@@ -2136,6 +2268,18 @@ code:
         let function = self.create_function(signature, f);
         self.bind_function(name, function)
     }
+    /// Create and bind a Rust function with typed arguments and output in the current scope
+    ///
+    /// # Errors
+    /// Returns an error in the binding name is not valid
+    pub fn create_bind_typed_function<Args>(
+        &mut self,
+        name: impl Into<EcoString>,
+        f: impl TypedFunction<Args>,
+    ) -> UiuaResult {
+        let function = self.create_typed_function(f);
+        self.bind_function(name, function)
+    }
     #[must_use]
     fn pre_eval_instrs(&mut self, instrs: EcoVec<Instr>) -> (EcoVec<Instr>, Vec<UiuaError>) {
         let mut errors = Vec::new();
@@ -2233,7 +2377,8 @@ code:
             } else {
                 Uiua::with_safe_sys()
             }
-            .with_execution_limit(Duration::from_millis(40));
+            .with_execution_limit(Duration::from_millis(40))
+            .with_memory_limit(MAX_PRE_EVAL_BYTES);
             match env.run_asm(asm) {
                 Ok(()) => {
                     let stack = env.take_stack();
@@ -2247,7 +2392,12 @@ code:
                     cache.borrow_mut().insert(instrs, res.clone());
                     Ok(res)
                 }
-                Err(e) if matches!(e.kind, UiuaErrorKind::Timeout(..)) => {
+                Err(e)
+                    if matches!(
+                        e.kind,
+                        UiuaErrorKind::Timeout(..) | UiuaErrorKind::MemoryLimit(..)
+                    ) =>
+                {
                     cache.borrow_mut().insert(instrs, None);
                     Ok(None)
                 }
@@ -0,0 +1,419 @@
+//! Conversions between Rust types and Uiua values
+
+use crate::{Array, Boxed, Signature, Uiua, UiuaResult, Value};
+
+/// A type that can be created from a Uiua [`Value`]
+pub trait FromValue: Sized {
+    /// Convert a value into this type
+    ///
+    /// Returns an error if the value has the wrong type or shape.
+    fn from_value(value: Value, env: &Uiua) -> UiuaResult<Self>;
+}
+
+/// A type that can be turned into a Uiua [`Value`]
+pub trait IntoValue {
+    /// Whether lists of this type should always be boxed
+    ///
+    /// This is `true` for types like strings whose values can have different lengths.
+    const BOX_ROWS: bool = false;
+    /// Convert this into a value
+    fn into_value(self) -> Value;
+    /// The value of an empty list of this type
+    ///
+    /// By default, this is an empty list of numbers,
+    /// or an empty list of boxes if [`IntoValue::BOX_ROWS`] is `true`.
+    fn empty_list() -> Value
+    where
+        Self: Sized,
+    {
+        if Self::BOX_ROWS {
+            Array::<Boxed>::default().into()
+        } else {
+            Array::<f64>::default().into()
+        }
+    }
+}
+
+impl FromValue for Value {
+    fn from_value(value: Value, _: &Uiua) -> UiuaResult<Self> {
+        Ok(value)
+    }
+}
+
+impl IntoValue for Value {
+    fn into_value(self) -> Value {
+        self
+    }
+}
+
+impl FromValue for f64 {
+    fn from_value(value: Value, env: &Uiua) -> UiuaResult<Self> {
+        value.as_num(env, "")
+    }
+}
+
+impl IntoValue for f64 {
+    fn into_value(self) -> Value {
+        self.into()
+    }
+}
+
+impl FromValue for f32 {
+    fn from_value(value: Value, env: &Uiua) -> UiuaResult<Self> {
+        value.as_num(env, "").map(|n| n as f32)
+    }
+}
+
+impl IntoValue for f32 {
+    fn into_value(self) -> Value {
+        (self as f64).into()
+    }
+}
+
+macro_rules! int_conversions {
+    ($($ty:ty),* => $as:ident) => {
+        $(
+            impl FromValue for $ty {
+                fn from_value(value: Value, env: &Uiua) -> UiuaResult<Self> {
+                    let n = value.$as(env, "")?;
+                    <$ty>::try_from(n).map_err(|_| {
+                        env.error(format!(
+                            "Expected value to fit in {}, but it is {n}",
+                            stringify!($ty)
+                        ))
+                    })
+                }
+            }
+
+            impl IntoValue for $ty {
+                fn into_value(self) -> Value {
+                    (self as f64).into()
+                }
+            }
+        )*
+    };
+}
+
+int_conversions!(isize, i64, i32, i16, i8 => as_int);
+int_conversions!(usize, u64, u32, u16 => as_nat);
+
+impl FromValue for u8 {
+    fn from_value(value: Value, env: &Uiua) -> UiuaResult<Self> {
+        let n = value.as_nat(env, "")?;
+        u8::try_from(n)
+            .map_err(|_| env.error(format!("Expected value to fit in u8, but it is {n}")))
+    }
+}
+
+impl IntoValue for u8 {
+    fn into_value(self) -> Value {
+        self.into()
+    }
+    fn empty_list() -> Value {
+        Array::<u8>::default().into()
+    }
+}
+
+impl FromValue for bool {
+    fn from_value(value: Value, env: &Uiua) -> UiuaResult<Self> {
+        value.as_bool(env, "")
+    }
+}
+
+impl IntoValue for bool {
+    fn into_value(self) -> Value {
+        self.into()
+    }
+    fn empty_list() -> Value {
+        Array::<u8>::default().into()
+    }
+}
+
+impl FromValue for char {
+    fn from_value(value: Value, env: &Uiua) -> UiuaResult<Self> {
+        match value {
+            Value::Char(arr) if arr.rank() == 0 => Ok(arr.data[0]),
+            Value::Char(arr) => Err(env.error(format!(
+                "Expected value to be a character, but its rank is {}",
+                arr.rank()
+            ))),
+            value => Err(env.error(format!(
+                "Expected value to be a character, but its type is {}",
+                value.type_name()
+            ))),
+        }
+    }
+}
+
+impl IntoValue for char {
+    fn into_value(self) -> Value {
+        self.into()
+    }
+    fn empty_list() -> Value {
+        Array::<char>::default().into()
+    }
+}
+
+impl FromValue for String {
+    fn from_value(value: Value, env: &Uiua) -> UiuaResult<Self> {
+        value.as_string(env, "")
+    }
+}
+
+impl IntoValue for String {
+    const BOX_ROWS: bool = true;
+    fn into_value(self) -> Value {
+        self.into()
+    }
+}
+
+impl IntoValue for &str {
+    const BOX_ROWS: bool = true;
+    fn into_value(self) -> Value {
+        self.into()
+    }
+}
+
+/// Vecs are converted to and from the rows of an array
+///
+/// Nested vecs become multidimensional arrays if their rows all have the same shape.
+/// Otherwise, the rows are boxed.
+impl<T: FromValue> FromValue for Vec<T> {
+    fn from_value(value: Value, env: &Uiua) -> UiuaResult<Self> {
+        if value.rank() == 0 {
+            return Err(env.error(format!(
+                "Expected value to be a list, but it is a scalar {}",
+                value.type_name()
+            )));
+        }
+        (value.into_rows())
+            .map(|row| T::from_value(row.unboxed(), env))
+            .collect()
+    }
+}
+
+impl<T: IntoValue> IntoValue for Vec<T> {
+    fn into_value(self) -> Value {
+        if self.is_empty() {
+            return T::empty_list();
+        }
+        rows_into_value(self.into_iter().map(T::into_value).collect(), T::BOX_ROWS)
+    }
+}
+
+/// Combine row values into an array, boxing them if they cannot be combined
+fn rows_into_value(rows: Vec<Value>, box_rows: bool) -> Value {
+    let uniform = rows
+        .windows(2)
+        .all(|w| w[0].shape() == w[1].shape() && w[0].type_name() == w[1].type_name());
+    if uniform && !box_rows {
+        Value::from_row_values_infallible(rows)
+    } else {
+        rows.into_iter().map(Boxed).collect()
+    }
+}
+
+macro_rules! tuple_conversions {
+    ($($len:literal => ($($name:ident),*)),* $(,)?) => {
+        $(
+            /// Tuples are converted to and from lists with one row for each item
+            impl<$($name: FromValue),*> FromValue for ($($name,)*) {
+                fn from_value(value: Value, env: &Uiua) -> UiuaResult<Self> {
+                    if value.rank() == 0 || value.row_count() != $len {
+                        return Err(env.error(format!(
+                            "Expected value to be a list of {} items, but its shape is {}",
+                            $len,
+                            value.shape()
+                        )));
+                    }
+                    let mut rows = value.into_rows();
+                    Ok(($($name::from_value(rows.next().unwrap().unboxed(), env)?,)*))
+                }
+            }
+
+            impl<$($name: IntoValue),*> IntoValue for ($($name,)*) {
+                #[allow(non_snake_case)]
+                fn into_value(self) -> Value {
+                    let ($($name,)*) = self;
+                    let box_rows = false $(|| $name::BOX_ROWS)*;
+                    rows_into_value(vec![$($name.into_value()),*], box_rows)
+                }
+            }
+        )*
+    };
+}
+
+tuple_conversions!(
+    1 => (A),
+    2 => (A, B),
+    3 => (A, B, C),
+    4 => (A, B, C, D),
+    5 => (A, B, C, D, E),
+    6 => (A, B, C, D, E, F),
+);
+
+/// The result of a Rust function that is called from Uiua
+///
+/// This is implemented for all [`IntoValue`] types, which produce a single output,
+/// for `()`, which produces no outputs,
+/// and for [`UiuaResult`]s of those, whose errors are thrown in Uiua.
+pub trait FunctionOutput {
+    /// The number of values the output pushes onto the stack
+    const OUTPUTS: usize;
+    /// Push the output onto the stack
+    fn push_output(self, env: &mut Uiua) -> UiuaResult;
+}
+
+impl<T: IntoValue> FunctionOutput for T {
+    const OUTPUTS: usize = 1;
+    fn push_output(self, env: &mut Uiua) -> UiuaResult {
+        env.push(self.into_value());
+        Ok(())
+    }
+}
+
+impl FunctionOutput for () {
+    const OUTPUTS: usize = 0;
+    fn push_output(self, _: &mut Uiua) -> UiuaResult {
+        Ok(())
+    }
+}
+
+impl<T: FunctionOutput> FunctionOutput for UiuaResult<T> {
+    const OUTPUTS: usize = T::OUTPUTS;
+    fn push_output(self, env: &mut Uiua) -> UiuaResult {
+        self?.push_output(env)
+    }
+}
+
+/// A Rust function whose arguments and output are converted to and from Uiua values
+///
+/// This is implemented for [`Fn`]s of up to 6 [`FromValue`] arguments
+/// that return a [`FunctionOutput`].
+/// The first argument is taken from the top of the stack.
+pub trait TypedFunction<Args>: Send + Sync + 'static {
+    /// The function's signature
+    fn signature() -> Signature;
+    /// Pop the function's arguments, call it, and push its output
+    fn call_typed(&self, env: &mut Uiua) -> UiuaResult;
+}
+
+macro_rules! typed_function {
+    ($($len:literal => ($($name:ident $arg:literal),*)),* $(,)?) => {
+        $(
+            impl<Func, Out, $($name),*> TypedFunction<($($name,)*)> for Func
+            where
+                Func: Fn($($name),*) -> Out + Send + Sync + 'static,
+                Out: FunctionOutput,
+                $($name: FromValue,)*
+            {
+                fn signature() -> Signature {
+                    Signature::new($len, Out::OUTPUTS)
+                }
+                #[allow(non_snake_case)]
+                fn call_typed(&self, env: &mut Uiua) -> UiuaResult {
+                    $(
+                        let $name = env.pop($arg)?;
+                        let $name = $name::from_value($name, env)?;
+                    )*
+                    self($($name),*).push_output(env)
+                }
+            }
+        )*
+    };
+}
+
+typed_function!(
+    0 => (),
+    1 => (A 1),
+    2 => (A 1, B 2),
+    3 => (A 1, B 2, C 3),
+    4 => (A 1, B 2, C 3, D 4),
+    5 => (A 1, B 2, C 3, D 4, E 5),
+    6 => (A 1, B 2, C 3, D 4, E 5, F 6),
+);
+
+#[cfg(test)]
+pub(crate) mod test {
+    use std::fmt;
+
+    use super::*;
+
+    /// Check that converting to a [`Value`] and back gives the original, returning the [`Value`]
+    pub(crate) fn check_round_trip<T: PartialEq + fmt::Debug>(
+        x: &T,
+        into: impl FnOnce(&T) -> Value,
+        from: impl FnOnce(Value) -> T,
+    ) -> Value {
+        let value = into(x);
+        assert_eq!(&from(value.clone()), x);
+        value
+    }
+
+    fn round_trip<T: IntoValue + FromValue + Clone + PartialEq + fmt::Debug>(x: T) -> Value {
+        let env = Uiua::with_safe_sys();
+        check_round_trip(
+            &x,
+            |x| x.clone().into_value(),
+            |value| T::from_value(value, &env).unwrap(),
+        )
+    }
+
+    #[test]
+    fn scalars() {
+        assert_eq!(round_trip(1.5).type_name(), "number");
+        assert_eq!(round_trip(-3i32).type_name(), "number");
+        assert_eq!(round_trip(200u8).type_name(), "number");
+        assert_eq!(round_trip(true), Value::from(1u8));
+        assert_eq!(round_trip('x').type_name(), "character");
+        assert_eq!(round_trip(String::from("hi")), Value::from("hi"));
+    }
+
+    #[test]
+    fn lists() {
+        assert_eq!(round_trip(vec![1.0, 2.0]).shape().dims(), [2]);
+        assert_eq!(
+            round_trip(vec![vec![1, 2], vec![3, 4]]).shape().dims(),
+            [2, 2]
+        );
+        let ragged = round_trip(vec![vec![1], vec![2, 3]]);
+        assert_eq!(
+            (ragged.type_name(), ragged.shape().dims()),
+            ("box", &[2][..])
+        );
+        let strings = round_trip(vec![String::from("a"), String::from("bc")]);
+        assert_eq!(
+            (strings.type_name(), strings.shape().dims()),
+            ("box", &[2][..])
+        );
+        let tuple = round_trip((1.0, String::from("a")));
+        assert_eq!((tuple.type_name(), tuple.shape().dims()), ("box", &[2][..]));
+        assert_eq!(round_trip((1, 2, 3)).shape().dims(), [3]);
+    }
+
+    #[test]
+    fn empty_lists() {
+        let empty = |value: Value| (value.type_name(), value.shape().dims().to_vec());
+        assert_eq!(empty(round_trip(Vec::<f64>::new())), ("number", vec![0]));
+        assert_eq!(empty(round_trip(Vec::<u8>::new())), ("number", vec![0]));
+        assert_eq!(
+            empty(round_trip(Vec::<char>::new())),
+            ("character", vec![0])
+        );
+        assert_eq!(empty(round_trip(Vec::<String>::new())), ("box", vec![0]));
+        assert_eq!(
+            empty(round_trip(Vec::<Vec<f64>>::new())),
+            ("number", vec![0])
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let env = Uiua::with_safe_sys();
+        assert!(u8::from_value(300.into(), &env).is_err());
+        assert!(i32::from_value(1.5.into(), &env).is_err());
+        assert!(Vec::<f64>::from_value(1.into(), &env).is_err());
+        assert!(<(f64, f64)>::from_value(vec![1.0].into_value(), &env).is_err());
+        assert!(char::from_value("ab".into(), &env).is_err());
+    }
+}
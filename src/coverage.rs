@@ -0,0 +1,292 @@
+//! Code coverage
+
+use std::{collections::BTreeMap, fmt::Write, mem::take, sync::Arc};
+
+use parking_lot::Mutex;
+use serde::Serialize;
+
+use crate::{Assembly, BindingKind, CodeSpan, FuncSlice, InputSrc, Instr, Loc, Uiua};
+
+/// Execution counts by instruction index
+///
+/// Each runtime counts into its own buffer without locking.
+/// The counts are merged into the shared totals when the runtime is dropped or reset.
+#[derive(Debug, Default)]
+pub(crate) struct Coverage {
+    hits: Vec<usize>,
+    merged: Arc<Mutex<Vec<usize>>>,
+}
+
+impl Coverage {
+    pub(crate) fn hit(&mut self, instr: usize) {
+        if instr >= self.hits.len() {
+            self.hits.resize(instr + 1, 0);
+        }
+        self.hits[instr] += 1;
+    }
+    fn merge(&mut self) {
+        let hits = take(&mut self.hits);
+        if hits.is_empty() {
+            return;
+        }
+        let mut merged = self.merged.lock();
+        add_hits(&mut merged, &hits);
+    }
+    /// Get the totals, including the counts of this runtime that are not merged yet
+    fn totals(&self) -> Vec<usize> {
+        let mut totals = self.merged.lock().clone();
+        add_hits(&mut totals, &self.hits);
+        totals
+    }
+}
+
+fn add_hits(totals: &mut Vec<usize>, hits: &[usize]) {
+    if totals.len() < hits.len() {
+        totals.resize(hits.len(), 0);
+    }
+    for (total, hits) in totals.iter_mut().zip(hits) {
+        *total += hits;
+    }
+}
+
+/// A clone counts into a new buffer that is merged into the same totals
+impl Clone for Coverage {
+    fn clone(&self) -> Self {
+        Coverage {
+            hits: Vec::new(),
+            merged: self.merged.clone(),
+        }
+    }
+}
+
+impl Drop for Coverage {
+    fn drop(&mut self) {
+        self.merge();
+    }
+}
+
+/// How many times the code at a span was executed
+#[derive(Debug, Clone)]
+pub struct SpanCoverage {
+    /// The span of the code
+    pub span: CodeSpan,
+    /// The number of times the code ran
+    pub hits: usize,
+}
+
+/// A record of which code ran
+#[derive(Debug, Clone, Default)]
+pub struct CoverageReport {
+    /// Coverage for each span that has instructions, in source order
+    pub spans: Vec<SpanCoverage>,
+}
+
+impl Uiua {
+    /// Record which code runs so that a [`CoverageReport`] can be generated
+    pub fn with_coverage(mut self) -> Self {
+        self.rt.coverage = Some(Coverage::default());
+        self
+    }
+    /// Get the coverage of the current assembly
+    ///
+    /// Returns `None` if coverage was not enabled with [`Uiua::with_coverage`]
+    pub fn coverage(&self) -> Option<CoverageReport> {
+        let hits = self.rt.coverage.as_ref()?.totals();
+        Some(CoverageReport::new(&self.asm, &hits))
+    }
+}
+
+impl CoverageReport {
+    fn new(asm: &Assembly, hits: &[usize]) -> Self {
+        let mut spans: BTreeMap<CodeSpan, usize> = BTreeMap::new();
+        for (i, span) in instr_spans(asm).into_iter().enumerate() {
+            let Some(span) = span else {
+                continue;
+            };
+            if let InputSrc::Macro(_) = span.src {
+                continue;
+            }
+            // A single word may compile to several instructions
+            let count = spans.entry(span).or_default();
+            *count = (*count).max(hits.get(i).copied().unwrap_or(0));
+        }
+        CoverageReport {
+            spans: (spans.into_iter())
+                .map(|(span, hits)| SpanCoverage { span, hits })
+                .collect(),
+        }
+    }
+    /// Get the number of spans that ran at least once
+    pub fn covered(&self) -> usize {
+        self.spans.iter().filter(|span| span.hits > 0).count()
+    }
+    /// Get the execution count of each line of each file
+    ///
+    /// A line's count is the highest count of the spans that start on it.
+    pub fn lines(&self) -> BTreeMap<InputSrc, BTreeMap<u16, usize>> {
+        let mut files: BTreeMap<InputSrc, BTreeMap<u16, usize>> = BTreeMap::new();
+        for span in &self.spans {
+            let lines = files.entry(span.span.src.clone()).or_default();
+            let hits = lines.entry(span.span.start.line).or_default();
+            *hits = (*hits).max(span.hits);
+        }
+        files
+    }
+    /// Format the report in the lcov tracefile format
+    ///
+    /// Only code from files is included
+    pub fn to_lcov(&self) -> String {
+        let mut lcov = String::new();
+        for (src, lines) in self.lines() {
+            let InputSrc::File(path) = src else {
+                continue;
+            };
+            _ = writeln!(lcov, "TN:");
+            _ = writeln!(lcov, "SF:{}", path.display());
+            for (line, hits) in &lines {
+                _ = writeln!(lcov, "DA:{line},{hits}");
+            }
+            _ = writeln!(lcov, "LF:{}", lines.len());
+            _ = writeln!(
+                lcov,
+                "LH:{}",
+                lines.values().filter(|&&hits| hits > 0).count()
+            );
+            _ = writeln!(lcov, "end_of_record");
+        }
+        lcov
+    }
+    /// Format the report as JSON
+    ///
+    /// Spans are grouped by file, and lines and columns are 1-indexed
+    pub fn to_json(&self) -> String {
+        let mut files: BTreeMap<String, Vec<&SpanCoverage>> = BTreeMap::new();
+        for span in &self.spans {
+            let file = match &span.span.src {
+                InputSrc::File(path) => path.display().to_string(),
+                InputSrc::Str(i) => format!("<string {i}>"),
+                InputSrc::Macro(_) => continue,
+            };
+            files.entry(file).or_default().push(span);
+        }
+        let report = serde_json::json!({
+            "spans": self.spans.len(),
+            "covered": self.covered(),
+            "files": files,
+        });
+        serde_json::to_string_pretty(&report).unwrap()
+    }
+}
+
+/// Get the code span that each instruction's execution count applies to
+///
+/// Instructions without spans, like pushes of constants and calls of bindings,
+/// use the span of the nearest instruction in the same function that has one.
+fn instr_spans(asm: &Assembly) -> Vec<Option<CodeSpan>> {
+    let code_span = |instr: &Instr| {
+        let index = instr.span_index()?;
+        asm.spans.get(index)?.clone().code()
+    };
+    let mut spans: Vec<_> = asm.instrs.iter().map(code_span).collect();
+    let mut slices: Vec<FuncSlice> = asm.top_slices.clone();
+    for binding in &asm.bindings {
+        if let BindingKind::Func(f) = &binding.kind {
+            slices.push(f.slice);
+        }
+    }
+    for instr in &asm.instrs {
+        if let Instr::PushFunc(f) = instr {
+            slices.push(f.slice);
+        }
+    }
+    for slice in slices {
+        let range = slice.start..slice.end().min(asm.instrs.len());
+        let own: Vec<_> = asm.instrs[range.clone()].iter().map(code_span).collect();
+        let mut nearest = own.iter().flatten().next().cloned();
+        for (i, own) in range.zip(own) {
+            if own.is_some() {
+                nearest = own;
+            } else if spans[i].is_none() {
+                spans[i].clone_from(&nearest);
+            }
+        }
+    }
+    spans
+}
+
+impl Serialize for SpanCoverage {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct Pos {
+            line: u16,
+            column: u16,
+        }
+        #[derive(Serialize)]
+        struct Rep {
+            start: Pos,
+            end: Pos,
+            hits: usize,
+        }
+        let pos = |loc: Loc| Pos {
+            line: loc.line,
+            column: loc.col,
+        };
+        Rep {
+            start: pos(self.span.start),
+            end: pos(self.span.end),
+            hits: self.hits,
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Compiler, PreEvalMode};
+
+    fn compile(code: &str) -> Assembly {
+        let mut comp = Compiler::new();
+        (comp.pre_eval_mode(PreEvalMode::Lazy))
+            .load_str(code)
+            .unwrap();
+        comp.finish()
+    }
+
+    fn line_hits(env: &Uiua) -> BTreeMap<u16, usize> {
+        let report = env.coverage().unwrap();
+        report.lines().into_values().next().unwrap_or_default()
+    }
+
+    #[test]
+    fn counts_lines() {
+        let mut env = Uiua::with_safe_sys().with_coverage();
+        env.run_asm(compile("F ← +1\n≡F ⇡3")).unwrap();
+        assert_eq!(line_hits(&env), [(1, 3), (2, 1)].into());
+    }
+
+    #[test]
+    fn merges_other_runtimes() {
+        let mut env = Uiua::with_safe_sys().with_coverage();
+        env.run_asm(compile("F ← +⚂\n≡(wait spawn F) ⇡3")).unwrap();
+        assert_eq!(line_hits(&env), [(1, 3), (2, 3)].into());
+        // Runtimes cloned for parallel test scopes
+        let mut clone = env.clone();
+        clone
+            .run_asm(compile("F ← +⚂\n≡(wait spawn F) ⇡3"))
+            .unwrap();
+        drop(clone);
+        assert_eq!(line_hits(&env), [(1, 6), (2, 6)].into());
+    }
+
+    #[test]
+    fn spanless_instructions() {
+        let asm = compile("F ← ⊂1 ⚂\nF");
+        let spans = instr_spans(&asm);
+        let (i, _) = (asm.instrs.iter().enumerate())
+            .find(|(_, instr)| matches!(instr, Instr::Push(_)))
+            .unwrap();
+        assert!(asm.instrs[i].span_index().is_none());
+        assert_eq!(spans[i].as_ref().unwrap().start.line, 1);
+    }
+}
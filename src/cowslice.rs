@@ -2,11 +2,17 @@
 
 use std::{
     borrow::Borrow,
+    cell::{Cell, RefCell},
     cmp::Ordering,
     fmt,
     hash::{Hash, Hasher},
+    mem::{size_of, take},
     ops::{Bound, Deref, RangeBounds},
-    ptr,
+    panic, ptr,
+    sync::{
+        atomic::{self, AtomicBool, AtomicIsize, AtomicU64},
+        Arc,
+    },
 };
 
 use serde::*;
@@ -26,6 +32,186 @@ macro_rules! cowslice {
 pub(crate) use cowslice;
 use ecow::EcoVec;
 
+/// Counts the bytes in array buffers allocated while a runtime runs
+///
+/// The count is the net change since the last [`MemoryCounter::reset`].
+/// Each reset starts a new epoch, and freeing a buffer only subtracts it
+/// if it was counted in the current epoch, so the count never goes below zero.
+///
+/// Each thread adds to the count in batches of up to [`BATCH_BYTES`]
+/// so that small allocations do not contend on the shared count.
+#[derive(Debug)]
+pub(crate) struct MemoryCounter {
+    limit: usize,
+    delta: AtomicIsize,
+    exceeded: AtomicBool,
+    epoch: AtomicU64,
+}
+
+/// The next epoch to give a [`MemoryCounter`]
+///
+/// Epochs are never reused, and `0` means a buffer was not counted.
+static NEXT_EPOCH: AtomicU64 = AtomicU64::new(1);
+
+fn new_epoch() -> u64 {
+    NEXT_EPOCH.fetch_add(1, atomic::Ordering::Relaxed)
+}
+
+impl MemoryCounter {
+    pub fn new(limit: usize) -> Self {
+        MemoryCounter {
+            limit,
+            delta: AtomicIsize::new(0),
+            exceeded: AtomicBool::new(false),
+            epoch: AtomicU64::new(new_epoch()),
+        }
+    }
+    /// Start counting from zero
+    ///
+    /// Buffers counted before this are not subtracted when they are freed.
+    pub fn reset(&self) {
+        self.delta.store(0, atomic::Ordering::Relaxed);
+        self.exceeded.store(false, atomic::Ordering::Relaxed);
+        self.epoch.store(new_epoch(), atomic::Ordering::Relaxed);
+    }
+    /// Get the number of bytes allocated since the last reset
+    pub fn usage(&self) -> usize {
+        self.delta.load(atomic::Ordering::Relaxed).max(0) as usize
+    }
+    /// Check if the limit has been exceeded, and forget any past excess
+    pub fn take_exceeded(&self) -> bool {
+        flush_count();
+        self.exceeded.swap(false, atomic::Ordering::Relaxed) || self.usage() > self.limit
+    }
+    /// Check if allocating some more bytes would exceed the limit
+    ///
+    /// If it would, the limit is marked as exceeded.
+    fn would_exceed(&self, bytes: usize) -> bool {
+        let bytes = isize::try_from(bytes).unwrap_or(isize::MAX);
+        let delta = self.delta.load(atomic::Ordering::Relaxed);
+        let exceeded = delta.saturating_add(bytes) > self.limit.try_into().unwrap_or(isize::MAX);
+        if exceeded {
+            self.exceeded.store(true, atomic::Ordering::Relaxed);
+        }
+        exceeded
+    }
+    fn add(&self, bytes: isize) {
+        let delta = self
+            .delta
+            .fetch_add(bytes, atomic::Ordering::Relaxed)
+            .saturating_add(bytes);
+        if delta > 0 && delta as usize > self.limit {
+            self.exceeded.store(true, atomic::Ordering::Relaxed);
+        }
+    }
+}
+
+/// The most bytes a thread counts before adding them to its [`MemoryCounter`]
+const BATCH_BYTES: usize = 1 << 16;
+
+thread_local! {
+    /// The memory counter of the runtime running on this thread
+    static COUNTER: RefCell<Option<Arc<MemoryCounter>>> = const { RefCell::new(None) };
+    /// Bytes that have not been added to the counter yet
+    static PENDING: Cell<isize> = const { Cell::new(0) };
+}
+
+/// Set the memory counter for this thread, returning the previous one
+pub(crate) fn set_memory_counter(
+    counter: Option<Arc<MemoryCounter>>,
+) -> Option<Arc<MemoryCounter>> {
+    flush_count();
+    COUNTER.replace(counter)
+}
+
+/// Check if allocations on this thread are counted
+///
+/// Arrays can be dropped while thread locals are being destroyed,
+/// in which case nothing is counted.
+fn counting() -> bool {
+    COUNTER
+        .try_with(|counter| counter.borrow().is_some())
+        .unwrap_or(false)
+}
+
+/// Get the epoch of this thread's counter, or `0` if allocations are not counted
+fn current_epoch() -> u64 {
+    COUNTER
+        .try_with(|counter| {
+            (counter.borrow().as_ref())
+                .map_or(0, |counter| counter.epoch.load(atomic::Ordering::Relaxed))
+        })
+        .unwrap_or(0)
+}
+
+fn count(bytes: isize) {
+    if bytes != 0 {
+        let pending = PENDING.get().saturating_add(bytes);
+        PENDING.set(pending);
+        if pending.unsigned_abs() >= BATCH_BYTES {
+            flush_count();
+        }
+    }
+}
+
+/// Add this thread's pending bytes to its counter
+fn flush_count() {
+    let pending = PENDING.replace(0);
+    if pending != 0 {
+        _ = COUNTER.try_with(|counter| {
+            if let Some(counter) = &*counter.borrow() {
+                counter.add(pending)
+            }
+        });
+    }
+}
+
+/// Check if allocating some bytes on this thread would exceed its memory limit
+///
+/// If it would, the limit is marked as exceeded, so the runtime reports it.
+pub(crate) fn would_exceed_memory_limit(bytes: usize) -> bool {
+    if bytes < BATCH_BYTES || !counting() {
+        return false;
+    }
+    flush_count();
+    COUNTER
+        .try_with(|counter| {
+            (counter.borrow().as_ref()).is_some_and(|counter| counter.would_exceed(bytes))
+        })
+        .unwrap_or(false)
+}
+
+/// The panic payload used to stop an allocation that would exceed the memory limit
+///
+/// The runtime turns this into a [`crate::UiuaErrorKind::MemoryLimit`] error.
+#[derive(Debug)]
+pub(crate) struct MemoryLimitExceeded;
+
+/// Unwind before allocating if the allocation would exceed this thread's memory limit
+///
+/// This does not run the panic hook.
+fn reserve<T>(capacity: usize) {
+    if would_exceed_memory_limit(capacity.saturating_mul(size_of::<T>())) {
+        panic::resume_unwind(Box::new(MemoryLimitExceeded));
+    }
+}
+
+/// Count a new buffer, returning the epoch it was counted in
+fn track<T>(capacity: usize) -> u64 {
+    let epoch = current_epoch();
+    if epoch != 0 {
+        count((capacity * size_of::<T>()) as isize);
+    }
+    epoch
+}
+
+/// Stop counting a buffer that is being freed
+fn untrack<T>(capacity: usize, epoch: u64) {
+    if epoch != 0 && epoch == current_epoch() {
+        count(-((capacity * size_of::<T>()) as isize));
+    }
+}
+
 /// The backing buffer for Uiua's arrays' data
 ///
 /// `CowSlice`s are reference-counted buffers that also have associated start and end indices.
@@ -34,6 +220,8 @@ pub struct CowSlice<T> {
     data: EcoVec<T>,
     start: usize,
     end: usize,
+    /// The epoch in which the buffer was counted, or `0` if it was not
+    epoch: u64,
 }
 
 impl<T> CowSlice<T> {
@@ -42,10 +230,14 @@ impl<T> CowSlice<T> {
     }
     #[inline]
     pub fn with_capacity(capacity: usize) -> Self {
+        reserve::<T>(capacity);
+        let data = EcoVec::with_capacity(capacity);
+        let epoch = track::<T>(data.capacity());
         Self {
-            data: EcoVec::with_capacity(capacity),
+            data,
             start: 0,
             end: 0,
+            epoch,
         }
     }
     #[inline]
@@ -67,13 +259,18 @@ impl<T> CowSlice<T> {
     }
 }
 
+impl<T> Drop for CowSlice<T> {
+    fn drop(&mut self) {
+        if self.data.is_unique() {
+            untrack::<T>(self.data.capacity(), self.epoch);
+        }
+    }
+}
+
 impl<T: Clone> CowSlice<T> {
     pub fn from_elem(elem: T, len: usize) -> Self {
-        Self {
-            data: EcoVec::from_elem(elem, len),
-            start: 0,
-            end: len,
-        }
+        reserve::<T>(len);
+        EcoVec::from_elem(elem, len).into()
     }
     pub fn truncate(&mut self, len: usize) {
         if self.is_unique() {
@@ -83,8 +280,10 @@ impl<T: Clone> CowSlice<T> {
     }
     pub fn as_mut_slice(&mut self) -> &mut [T] {
         if !self.data.is_unique() {
+            reserve::<T>(self.len());
             let mut new_data = EcoVec::with_capacity(self.len());
             new_data.extend_from_slice(&*self);
+            self.epoch = track::<T>(new_data.capacity());
             self.data = new_data;
             self.start = 0;
             self.end = self.data.len();
@@ -115,6 +314,7 @@ impl<T: Clone> CowSlice<T> {
             data: self.data.clone(),
             start,
             end,
+            epoch: self.epoch,
         }
     }
     /// Get an iterator over slices with the given size
@@ -137,6 +337,7 @@ impl<T: Clone> CowSlice<T> {
                 data: self.data.clone(),
                 start,
                 end: start + size,
+                epoch: self.epoch,
             }
         })
     }
@@ -146,7 +347,10 @@ impl<T: Clone> CowSlice<T> {
         F: FnOnce(&mut EcoVec<T>) -> R,
     {
         if self.data.is_unique() && self.start == 0 && self.end == self.data.len() {
+            let capacity = self.data.capacity();
             let res = f(&mut self.data);
+            untrack::<T>(capacity, self.epoch);
+            self.epoch = track::<T>(self.data.capacity());
             self.end = self.data.len();
             res
         } else {
@@ -162,7 +366,10 @@ impl<T: Clone> CowSlice<T> {
         F: FnOnce(&mut EcoVec<T>) -> R,
     {
         if self.data.is_unique() && self.end == self.data.len() {
+            let capacity = self.data.capacity();
             let res = f(&mut self.data);
+            untrack::<T>(capacity, self.epoch);
+            self.epoch = track::<T>(self.data.capacity());
             self.end = self.data.len();
             res
         } else {
@@ -178,6 +385,7 @@ impl<T: Clone> CowSlice<T> {
             self.modify(|vec| vec.clear());
         } else {
             self.data = EcoVec::new();
+            self.epoch = 0;
         }
         self.start = 0;
         self.end = 0;
@@ -257,12 +465,59 @@ fn cow_slice_modify() {
     assert_eq!(sub, [2, 3, 5]);
 }
 
+#[test]
+fn cow_slice_memory_tracking() {
+    let existing: CowSlice<f64> = (0..50).map(|i| i as f64).collect();
+    let counter = Arc::new(MemoryCounter::new(BATCH_BYTES * 2));
+    let prev = set_memory_counter(Some(counter.clone()));
+    let usage = || {
+        flush_count();
+        counter.usage()
+    };
+    let slice: CowSlice<f64> = (0..100).map(|i| i as f64).collect();
+    assert!(usage() >= 100 * size_of::<f64>());
+    let sub = slice.slice(10..20);
+    drop(slice);
+    assert!(usage() >= 100 * size_of::<f64>());
+    drop(sub);
+    assert_eq!(usage(), 0);
+    // Freeing arrays that were not counted does not make room
+    let shared = EcoVec::from_elem(0.0f64, 50);
+    drop(CowSlice::from(shared.clone()));
+    drop(existing);
+    drop(shared);
+    let delta = || {
+        flush_count();
+        counter.delta.load(atomic::Ordering::Relaxed)
+    };
+    assert_eq!(delta(), 0);
+    let res = panic::catch_unwind(|| CowSlice::from_elem(0u8, BATCH_BYTES * 2 + 1));
+    assert!(res.unwrap_err().is::<MemoryLimitExceeded>());
+    assert!(counter.take_exceeded());
+    // Nor does freeing arrays counted before a reset
+    let before = CowSlice::from_elem(0u8, BATCH_BYTES);
+    counter.reset();
+    drop(before);
+    assert_eq!(delta(), 0);
+    // Allocations over the limit are caught even if they are freed right away
+    drop(CowSlice::from(EcoVec::from_elem(0u8, BATCH_BYTES * 3)));
+    assert!(counter.take_exceeded());
+    assert!(!counter.take_exceeded());
+    // Allocations known to be over the limit are stopped before they happen
+    let res = panic::catch_unwind(|| CowSlice::from_elem(0u8, usize::MAX / 2));
+    assert!(res.unwrap_err().is::<MemoryLimitExceeded>());
+    assert!(counter.take_exceeded());
+    assert_eq!(usage(), 0);
+    set_memory_counter(prev);
+}
+
 impl<T> Default for CowSlice<T> {
     fn default() -> Self {
         Self {
             data: EcoVec::new(),
             start: 0,
             end: 0,
+            epoch: 0,
         }
     }
 }
@@ -273,6 +528,7 @@ impl<T: Clone> Clone for CowSlice<T> {
             data: self.data.clone(),
             start: self.start,
             end: self.end,
+            epoch: self.epoch,
         }
     }
 }
@@ -299,7 +555,9 @@ fn cow_slice_deref_mut() {
 impl<T: Clone> From<CowSlice<T>> for Vec<T> {
     fn from(mut slice: CowSlice<T>) -> Self {
         if slice.data.is_unique() && slice.start == 0 && slice.end == slice.data.len() {
-            slice.data.into_iter().collect()
+            untrack::<T>(slice.data.capacity(), slice.epoch);
+            slice.epoch = 0;
+            take(&mut slice.data).into_iter().collect()
         } else {
             slice.to_vec()
         }
@@ -307,32 +565,31 @@ impl<T: Clone> From<CowSlice<T>> for Vec<T> {
 }
 
 impl<T: Clone> From<EcoVec<T>> for CowSlice<T> {
-    fn from(data: EcoVec<T>) -> Self {
+    fn from(mut data: EcoVec<T>) -> Self {
+        // Shared buffers may already be owned by something that was not counted
+        let epoch = if data.is_unique() {
+            track::<T>(data.capacity())
+        } else {
+            0
+        };
         Self {
             start: 0,
             end: data.len(),
             data,
+            epoch,
         }
     }
 }
 
 impl<'a, T: Clone> From<&'a [T]> for CowSlice<T> {
     fn from(slice: &'a [T]) -> Self {
-        Self {
-            start: 0,
-            end: slice.len(),
-            data: slice.into(),
-        }
+        EcoVec::from(slice).into()
     }
 }
 
 impl<T: Clone, const N: usize> From<[T; N]> for CowSlice<T> {
     fn from(array: [T; N]) -> Self {
-        Self {
-            start: 0,
-            end: N,
-            data: array.into(),
-        }
+        EcoVec::from(array).into()
     }
 }
 
@@ -401,11 +658,12 @@ impl<T: Hash> Hash for CowSlice<T> {
 impl<T: Clone> IntoIterator for CowSlice<T> {
     type Item = T;
     type IntoIter = CowSliceIntoIter<T>;
-    fn into_iter(self) -> Self::IntoIter {
+    fn into_iter(mut self) -> Self::IntoIter {
         CowSliceIntoIter {
-            data: self.data,
+            data: take(&mut self.data),
             start: self.start,
             end: self.end,
+            epoch: take(&mut self.epoch),
         }
     }
 }
@@ -415,6 +673,7 @@ pub struct CowSliceIntoIter<T> {
     data: EcoVec<T>,
     start: usize,
     end: usize,
+    epoch: u64,
 }
 
 impl<T: Clone> Iterator for CowSliceIntoIter<T> {
@@ -436,6 +695,14 @@ impl<T: Clone> Iterator for CowSliceIntoIter<T> {
 
 impl<T: Clone> ExactSizeIterator for CowSliceIntoIter<T> {}
 
+impl<T> Drop for CowSliceIntoIter<T> {
+    fn drop(&mut self) {
+        if self.data.is_unique() {
+            untrack::<T>(self.data.capacity(), self.epoch);
+        }
+    }
+}
+
 impl<'a, T> IntoIterator for &'a CowSlice<T> {
     type Item = &'a T;
     type IntoIter = <&'a [T] as IntoIterator>::IntoIter;
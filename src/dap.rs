@@ -0,0 +1,778 @@
+//! Uiua's Debug Adapter Protocol (DAP) implementation
+//!
+//! The adapter communicates over stdin and stdout. It can be started with `uiua dap`.
+
+use std::{
+    io::{self, BufRead, Write},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+};
+
+use crossbeam_channel::{Receiver, Sender};
+use parking_lot::Mutex;
+use serde_json::{json, Value as Json};
+
+use crate::{
+    grid_fmt::GridFmt,
+    sys_native::{set_output_enabled, set_output_hook, OutputStream},
+    Breakpoint, CodeSpan, Compiler, Debugger, InputSrc, Instr, InterruptHandle, NativeSys,
+    PreEvalMode, RunMode, StepMode, Stepper, TempStack, Uiua, UiuaResult, Value,
+};
+
+/// The id of the only thread exposed to the client
+const THREAD_ID: u64 = 1;
+
+/// Variable references for the fixed scopes
+const STACK_REF: usize = 1;
+const UNDER_REF: usize = 2;
+const INLINE_REF: usize = 3;
+const BINDINGS_REF: usize = 4;
+
+/// The largest message body that will be read
+const MAX_MESSAGE_LEN: usize = 16 << 20;
+
+/// Run the debug adapter
+pub fn run_debug_adapter() {
+    // Stdin and stdout are used for the protocol, so the program may not use them
+    set_output_enabled(false);
+    let out = Arc::new(Output::new(io::stdout()));
+    set_output_hook(Some({
+        let out = out.clone();
+        Arc::new(move |stream, s| {
+            let category = match stream {
+                OutputStream::Stdout => "stdout",
+                OutputStream::Stderr => "stderr",
+                OutputStream::Trace => "console",
+            };
+            out.event("output", json!({ "category": category, "output": s }));
+        })
+    }));
+    let mut adapter = Adapter::new(out);
+    let stdin = io::stdin();
+    let mut stdin = stdin.lock();
+    while let Some(message) = read_message(&mut stdin) {
+        if !adapter.handle(message) {
+            break;
+        }
+    }
+    set_output_hook(None);
+}
+
+fn read_message(reader: &mut impl BufRead) -> Option<Json> {
+    let mut len = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).ok()? == 0 {
+            return None;
+        }
+        let line = line.trim();
+        if line.is_empty() {
+            break;
+        }
+        if let Some(n) = line.strip_prefix("Content-Length:") {
+            len = n.trim().parse().ok();
+        }
+    }
+    let len = len.filter(|&len| len <= MAX_MESSAGE_LEN)?;
+    let mut buffer = vec![0; len];
+    reader.read_exact(&mut buffer).ok()?;
+    serde_json::from_slice(&buffer).ok()
+}
+
+/// Writes protocol messages
+struct Output {
+    writer: Mutex<(u64, Box<dyn Write + Send>)>,
+}
+
+impl Output {
+    fn new(writer: impl Write + Send + 'static) -> Self {
+        Output {
+            writer: Mutex::new((0, Box::new(writer))),
+        }
+    }
+    fn send(&self, mut message: Json) {
+        let (seq, writer) = &mut *self.writer.lock();
+        *seq += 1;
+        message["seq"] = json!(*seq);
+        let body = message.to_string();
+        _ = write!(writer, "Content-Length: {}\r\n\r\n{body}", body.len());
+        _ = writer.flush();
+    }
+    fn event(&self, event: &str, body: Json) {
+        self.send(json!({ "type": "event", "event": event, "body": body }));
+    }
+    fn respond(&self, request: &Json, body: Json) {
+        self.send(json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+            "success": true,
+            "body": body,
+        }));
+    }
+    fn respond_error(&self, request: &Json, message: impl Into<String>) {
+        self.send(json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+            "success": false,
+            "message": message.into(),
+        }));
+    }
+}
+
+/// A command sent to the paused program
+enum Command {
+    Continue,
+    StepInto,
+    StepOver,
+    StepOut,
+    Evaluate(String, Sender<Result<Vec<Value>, String>>),
+    Stop,
+}
+
+/// State shared between the adapter and the program thread
+struct Session {
+    stepper: Mutex<Stepper>,
+    paused: Mutex<Option<Paused>>,
+    pause_requested: AtomicBool,
+    entered: AtomicBool,
+    stopped: AtomicBool,
+    interrupt: Mutex<Option<InterruptHandle>>,
+    send: Sender<Command>,
+    recv: Receiver<Command>,
+}
+
+/// A snapshot of the program's state while it is paused
+struct Paused {
+    /// Frame names and locations, innermost first
+    frames: Vec<(String, Option<CodeSpan>)>,
+    stack: Vec<Value>,
+    under: Vec<Value>,
+    inline: Vec<Value>,
+    bindings: Vec<(String, Value)>,
+    /// Values whose rows can be expanded, offset by `BINDINGS_REF + 1`
+    expandable: Vec<Vec<(String, Value)>>,
+}
+
+impl Paused {
+    fn new(env: &Uiua, span: Option<&CodeSpan>) -> Self {
+        let mut frames: Vec<_> = (env.call_stack().into_iter().rev())
+            .map(|frame| {
+                let span = frame.span.unwrap_or(frame.call_span);
+                (frame.id.to_string(), span.code())
+            })
+            .collect();
+        if let (Some(frame), Some(span)) = (frames.first_mut(), span) {
+            frame.1 = Some(span.clone());
+        }
+        let mut bindings: Vec<_> = (env.bound_values().into_iter())
+            .map(|(name, value)| (name.to_string(), value))
+            .collect();
+        bindings.sort_by(|a, b| a.0.cmp(&b.0));
+        Paused {
+            frames,
+            stack: env.stack().to_vec(),
+            under: env.temp_stack(TempStack::Under).to_vec(),
+            inline: env.temp_stack(TempStack::Inline).to_vec(),
+            bindings,
+            expandable: Vec::new(),
+        }
+    }
+    /// Render a list of variables, registering expandable ones
+    fn variables(&mut self, values: Vec<(String, Value)>) -> Vec<Json> {
+        let mut vars = Vec::with_capacity(values.len());
+        for (name, value) in values {
+            let reference =
+                if value.rank() >= 2 || value.rank() == 1 && matches!(value, Value::Box(_)) {
+                    let rows = (value.rows().enumerate())
+                        .map(|(i, row)| (format!("[{i}]"), row))
+                        .collect();
+                    self.expandable.push(rows);
+                    BINDINGS_REF + self.expandable.len()
+                } else {
+                    0
+                };
+            vars.push(json!({
+                "name": name,
+                "value": value.grid_string(false),
+                "type": if value.rank() == 0 {
+                    value.type_name().to_string()
+                } else {
+                    format!("{} {}", value.shape(), value.type_name())
+                },
+                "variablesReference": reference,
+            }));
+        }
+        vars
+    }
+}
+
+fn stack_entries(values: &[Value]) -> Vec<(String, Value)> {
+    // The top of the stack is listed first
+    (values.iter().rev().enumerate())
+        .map(|(i, value)| (i.to_string(), value.clone()))
+        .collect()
+}
+
+struct DapDebugger {
+    session: Arc<Session>,
+    out: Arc<Output>,
+    compiler: Compiler,
+}
+
+impl Debugger for DapDebugger {
+    fn before_instr(&self, env: &Uiua, _instr: &Instr, span: Option<&CodeSpan>) -> UiuaResult {
+        if self.session.stopped.load(Ordering::Relaxed) {
+            return Err(env.error("Debugging stopped"));
+        }
+        let reason = {
+            let mut stepper = self.session.stepper.lock();
+            if !stepper.should_pause(env, span) {
+                return Ok(());
+            }
+            if !self.session.entered.swap(true, Ordering::Relaxed) {
+                "entry"
+            } else if self.session.pause_requested.swap(false, Ordering::Relaxed) {
+                "pause"
+            } else if stepper.mode == StepMode::Continue {
+                "breakpoint"
+            } else {
+                "step"
+            }
+        };
+        *self.session.paused.lock() = Some(Paused::new(env, span));
+        self.out.event(
+            "stopped",
+            json!({ "reason": reason, "threadId": THREAD_ID, "allThreadsStopped": true }),
+        );
+        let res = loop {
+            let Ok(command) = self.session.recv.recv() else {
+                break Err(env.error("Debugging stopped"));
+            };
+            let mut stepper = self.session.stepper.lock();
+            match command {
+                Command::Continue => stepper.resume(),
+                Command::StepInto => stepper.step_into(),
+                Command::StepOver => stepper.step_over(env),
+                Command::StepOut => stepper.step_out(env),
+                Command::Evaluate(code, reply) => {
+                    let res = env.eval_snapshot(&self.compiler, &code);
+                    _ = reply.send(res.map_err(|e| e.report().color(false).to_string()));
+                    continue;
+                }
+                Command::Stop => break Err(env.error("Debugging stopped")),
+            }
+            break Ok(());
+        };
+        *self.session.paused.lock() = None;
+        res
+    }
+}
+
+struct Adapter {
+    out: Arc<Output>,
+    session: Arc<Session>,
+    program: Option<PathBuf>,
+    args: Vec<String>,
+    stop_on_entry: bool,
+}
+
+impl Adapter {
+    fn new(out: Arc<Output>) -> Self {
+        let (send, recv) = crossbeam_channel::unbounded();
+        Adapter {
+            out,
+            session: Arc::new(Session {
+                stepper: Mutex::new(Stepper::new()),
+                paused: Mutex::new(None),
+                pause_requested: AtomicBool::new(false),
+                entered: AtomicBool::new(false),
+                stopped: AtomicBool::new(false),
+                interrupt: Mutex::new(None),
+                send,
+                recv,
+            }),
+            program: None,
+            args: Vec::new(),
+            stop_on_entry: false,
+        }
+    }
+    /// Handle a request
+    ///
+    /// Returns `false` if the adapter should shut down
+    fn handle(&mut self, req: Json) -> bool {
+        let args = &req["arguments"];
+        match req["command"].as_str().unwrap_or("") {
+            "initialize" => {
+                self.out.respond(
+                    &req,
+                    json!({
+                        "supportsConfigurationDoneRequest": true,
+                        "supportsEvaluateForHovers": true,
+                        "supportsTerminateRequest": true,
+                    }),
+                );
+                self.out.event("initialized", json!({}));
+            }
+            "launch" => {
+                let Some(program) = args["program"].as_str() else {
+                    self.out.respond_error(&req, "No program specified");
+                    return true;
+                };
+                self.program = Some(program.into());
+                self.args = (args["args"].as_array().into_iter().flatten())
+                    .filter_map(|arg| arg.as_str().map(Into::into))
+                    .collect();
+                self.stop_on_entry = args["stopOnEntry"].as_bool().unwrap_or(false);
+                self.out.respond(&req, json!({}));
+            }
+            "setBreakpoints" => {
+                let Some(path) = args["source"]["path"].as_str() else {
+                    self.out.respond_error(&req, "No source path specified");
+                    return true;
+                };
+                let lines: Vec<u64> = (args["breakpoints"].as_array().into_iter().flatten())
+                    .filter_map(|bp| bp["line"].as_u64())
+                    .collect();
+                let mut stepper = self.session.stepper.lock();
+                let file_bp = Breakpoint::file(path, 0);
+                stepper.breakpoints.retain(|bp| bp.path != file_bp.path);
+                let breakpoints: Vec<_> = (lines.into_iter())
+                    .map(|line| match u16::try_from(line) {
+                        Ok(line) => {
+                            stepper.add_breakpoint(Breakpoint { line, ..file_bp.clone() });
+                            json!({ "verified": true, "line": line })
+                        }
+                        Err(_) => json!({
+                            "verified": false,
+                            "line": line,
+                            "message": "Line is out of range",
+                        }),
+                    })
+                    .collect();
+                self.out.respond(&req, json!({ "breakpoints": breakpoints }));
+            }
+            "setExceptionBreakpoints" | "setFunctionBreakpoints" => {
+                self.out.respond(&req, json!({ "breakpoints": [] }))
+            }
+            "configurationDone" => {
+                self.out.respond(&req, json!({}));
+                self.start();
+            }
+            "threads" => self.out.respond(
+                &req,
+                json!({ "threads": [{ "id": THREAD_ID, "name": "main" }] }),
+            ),
+            "stackTrace" => {
+                let paused = self.session.paused.lock();
+                let Some(paused) = &*paused else {
+                    self.out.respond_error(&req, "The program is not paused");
+                    return true;
+                };
+                let frames: Vec<_> = (paused.frames.iter().enumerate())
+                    .map(|(i, (name, span))| stack_frame(i, name, span.as_ref()))
+                    .collect();
+                let total = frames.len();
+                self.out.respond(
+                    &req,
+                    json!({ "stackFrames": frames, "totalFrames": total }),
+                );
+            }
+            "scopes" => self.out.respond(
+                &req,
+                json!({ "scopes": [
+                    { "name": "Stack", "variablesReference": STACK_REF, "expensive": false },
+                    { "name": "Under Stack", "variablesReference": UNDER_REF, "expensive": false },
+                    { "name": "Inline Stack", "variablesReference": INLINE_REF, "expensive": false },
+                    { "name": "Bindings", "variablesReference": BINDINGS_REF, "expensive": false },
+                ]}),
+            ),
+            "variables" => {
+                let mut paused = self.session.paused.lock();
+                let Some(paused) = &mut *paused else {
+                    self.out.respond_error(&req, "The program is not paused");
+                    return true;
+                };
+                let values = match args["variablesReference"].as_u64().unwrap_or(0) as usize {
+                    STACK_REF => stack_entries(&paused.stack),
+                    UNDER_REF => stack_entries(&paused.under),
+                    INLINE_REF => stack_entries(&paused.inline),
+                    BINDINGS_REF => paused.bindings.clone(),
+                    i => (paused.expandable.get(i.wrapping_sub(BINDINGS_REF + 1)))
+                        .cloned()
+                        .unwrap_or_default(),
+                };
+                let variables = paused.variables(values);
+                self.out.respond(&req, json!({ "variables": variables }));
+            }
+            command @ ("continue" | "next" | "stepIn" | "stepOut") => {
+                // Taking the snapshot makes sure only one command resumes each pause
+                if self.session.paused.lock().take().is_none() {
+                    self.out.respond_error(&req, "The program is not paused");
+                    return true;
+                }
+                let command = match command {
+                    "continue" => Command::Continue,
+                    "next" => Command::StepOver,
+                    "stepIn" => Command::StepInto,
+                    _ => Command::StepOut,
+                };
+                _ = self.session.send.send(command);
+                self.out.respond(&req, json!({ "allThreadsContinued": true }));
+            }
+            "pause" => {
+                self.session.pause_requested.store(true, Ordering::Relaxed);
+                self.session.stepper.lock().step_into();
+                self.out.respond(&req, json!({}));
+            }
+            "evaluate" => {
+                let code = args["expression"].as_str().unwrap_or("").to_string();
+                if self.session.paused.lock().is_none() {
+                    self.out.respond_error(&req, "The program is not paused");
+                    return true;
+                }
+                let (send, recv) = crossbeam_channel::bounded(1);
+                _ = self.session.send.send(Command::Evaluate(code, send));
+                // Wait on another thread so that a disconnect can still stop the evaluation
+                let (out, session) = (self.out.clone(), self.session.clone());
+                thread::spawn(move || match recv.recv() {
+                    Ok(Ok(stack)) => {
+                        let result = (stack.last())
+                            .map_or_else(|| "(empty)".into(), |value| value.grid_string(false));
+                        let mut paused = session.paused.lock();
+                        let reference = if let (Some(paused), true) = (&mut *paused, stack.len() > 1)
+                        {
+                            paused.expandable.push(stack_entries(&stack));
+                            BINDINGS_REF + paused.expandable.len()
+                        } else {
+                            0
+                        };
+                        out.respond(
+                            &req,
+                            json!({ "result": result, "variablesReference": reference }),
+                        );
+                    }
+                    Ok(Err(message)) => out.respond_error(&req, message),
+                    Err(_) => out.respond_error(&req, "The program is not running"),
+                });
+            }
+            "disconnect" | "terminate" => {
+                self.stop();
+                self.out.respond(&req, json!({}));
+                return false;
+            }
+            command => self
+                .out
+                .respond_error(&req, format!("Unsupported request `{command}`")),
+        }
+        true
+    }
+    /// Stop the program whether it is paused or running
+    fn stop(&self) {
+        self.session.stopped.store(true, Ordering::Relaxed);
+        if let Some(interrupt) = &*self.session.interrupt.lock() {
+            interrupt.interrupt();
+        }
+        _ = self.session.send.send(Command::Stop);
+    }
+    /// Compile and start running the program
+    fn start(&mut self) {
+        let Some(program) = self.program.clone() else {
+            self.out.event("terminated", json!({}));
+            return;
+        };
+        if !self.stop_on_entry {
+            self.session.stepper.lock().resume();
+            self.session.entered.store(true, Ordering::Relaxed);
+        }
+        let mut compiler = Compiler::with_backend(NativeSys);
+        compiler
+            .mode(RunMode::Normal)
+            .pre_eval_mode(PreEvalMode::Lazy);
+        let res = compiler.load_file(&program).map(drop);
+        for diag in compiler.take_diagnostics() {
+            let output = format!("{}\n", diag.report().color(false));
+            (self.out).event("output", json!({ "category": "console", "output": output }));
+        }
+        if let Err(e) = res {
+            let output = format!("{}\n", e.report().color(false));
+            (self.out).event("output", json!({ "category": "stderr", "output": output }));
+            self.out.event("terminated", json!({}));
+            return;
+        }
+        let asm = compiler.assembly().clone();
+        let debugger = DapDebugger {
+            session: self.session.clone(),
+            out: self.out.clone(),
+            compiler,
+        };
+        let mut env = Uiua::with_native_sys()
+            .with_file_path(&program)
+            .with_args(self.args.clone())
+            .with_debugger(debugger);
+        *self.session.interrupt.lock() = Some(env.interrupt_handle());
+        let out = self.out.clone();
+        thread::spawn(move || {
+            let exit_code = match env.run_asm(asm) {
+                Ok(()) => {
+                    let mut output = String::new();
+                    for value in env.take_stack() {
+                        output.push_str(&value.show());
+                        output.push('\n');
+                    }
+                    out.event("output", json!({ "category": "stdout", "output": output }));
+                    0
+                }
+                Err(e) => {
+                    let output = format!("{}\n", e.report().color(false));
+                    out.event("output", json!({ "category": "stderr", "output": output }));
+                    1
+                }
+            };
+            out.event("exited", json!({ "exitCode": exit_code }));
+            out.event("terminated", json!({}));
+        });
+    }
+}
+
+fn stack_frame(id: usize, name: &str, span: Option<&CodeSpan>) -> Json {
+    let Some(span) = span else {
+        return json!({ "id": id, "name": name, "line": 0, "column": 0 });
+    };
+    let mut frame = json!({
+        "id": id,
+        "name": name,
+        "line": span.start.line,
+        "column": span.start.col,
+        "endLine": span.end.line,
+        "endColumn": span.end.col,
+    });
+    if let InputSrc::File(path) = &span.src {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        frame["source"] = json!({
+            "name": path.file_name().map(|name| name.to_string_lossy()),
+            "path": path,
+        });
+    }
+    frame
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Buffer {
+        fn messages(&self) -> Vec<Json> {
+            let bytes = self.0.lock().clone();
+            let mut reader = bytes.as_slice();
+            std::iter::from_fn(|| read_message(&mut reader)).collect()
+        }
+        /// Wait for an event to be sent
+        fn wait_for(&self, event: &str) -> Vec<Json> {
+            let start = Instant::now();
+            loop {
+                let messages = self.messages();
+                if messages.iter().any(|m| m["event"] == event) {
+                    return messages;
+                }
+                assert!(
+                    start.elapsed() < Duration::from_secs(10),
+                    "no {event} event in {messages:#?}"
+                );
+                thread::sleep(Duration::from_millis(10));
+            }
+        }
+        /// Wait for the response to a request
+        fn wait_for_response(&self, seq: u64) -> Json {
+            let start = Instant::now();
+            loop {
+                let messages = self.messages();
+                if let Some(res) =
+                    (messages.iter()).find(|m| m["type"] == "response" && m["request_seq"] == seq)
+                {
+                    return res.clone();
+                }
+                assert!(
+                    start.elapsed() < Duration::from_secs(10),
+                    "no response to {seq} in {messages:#?}"
+                );
+                thread::sleep(Duration::from_millis(10));
+            }
+        }
+    }
+
+    fn adapter() -> (Adapter, Buffer) {
+        let buffer = Buffer::default();
+        (Adapter::new(Arc::new(Output::new(buffer.clone()))), buffer)
+    }
+
+    fn request(seq: u64, command: &str, arguments: Json) -> Json {
+        json!({ "seq": seq, "type": "request", "command": command, "arguments": arguments })
+    }
+
+    fn response(messages: &[Json], seq: u64) -> &Json {
+        (messages.iter())
+            .find(|m| m["type"] == "response" && m["request_seq"] == seq)
+            .unwrap_or_else(|| panic!("no response to {seq} in {messages:#?}"))
+    }
+
+    #[test]
+    fn read_messages() {
+        let input = "Content-Length: 13\r\n\r\n{\"seq\":1}    \
+            Content-Type: application/json\r\nContent-Length: 9\r\n\r\n{\"seq\":2}";
+        let mut reader = input.as_bytes();
+        assert_eq!(read_message(&mut reader), Some(json!({ "seq": 1 })));
+        assert_eq!(read_message(&mut reader), Some(json!({ "seq": 2 })));
+        assert_eq!(read_message(&mut reader), None);
+
+        // Missing length
+        assert_eq!(read_message(&mut "\r\n{}".as_bytes()), None);
+        // Truncated body
+        assert_eq!(
+            read_message(&mut "Content-Length: 10\r\n\r\n{}".as_bytes()),
+            None
+        );
+        // Too long
+        assert_eq!(
+            read_message(&mut "Content-Length: 99999999999\r\n\r\n{}".as_bytes()),
+            None
+        );
+        // Invalid JSON
+        assert_eq!(
+            read_message(&mut "Content-Length: 2\r\n\r\n{{".as_bytes()),
+            None
+        );
+    }
+
+    #[test]
+    fn dispatch() {
+        let (mut adapter, buffer) = adapter();
+        assert!(adapter.handle(request(1, "initialize", json!({}))));
+        assert!(adapter.handle(request(2, "continue", json!({}))));
+        assert!(adapter.handle(request(3, "stackTrace", json!({}))));
+        assert!(adapter.handle(request(4, "frobnicate", json!({}))));
+        assert!(adapter.handle(request(5, "launch", json!({}))));
+        assert!(adapter.handle(request(
+            6,
+            "setBreakpoints",
+            json!({
+                "source": { "path": "dap_test_does_not_exist.ua" },
+                "breakpoints": [{ "line": 3 }, { "line": 70000 }],
+            })
+        )));
+        assert!(!adapter.handle(request(7, "disconnect", json!({}))));
+
+        let messages = buffer.messages();
+        let seqs: Vec<_> = messages
+            .iter()
+            .map(|m| m["seq"].as_u64().unwrap())
+            .collect();
+        assert_eq!(seqs, (1..=seqs.len() as u64).collect::<Vec<_>>());
+        assert_eq!(response(&messages, 1)["success"], true);
+        assert!(messages.iter().any(|m| m["event"] == "initialized"));
+        for seq in [2, 3, 4, 5] {
+            assert_eq!(response(&messages, seq)["success"], false);
+        }
+        let breakpoints = &response(&messages, 6)["body"]["breakpoints"];
+        assert_eq!(breakpoints[0]["verified"], true);
+        assert_eq!(breakpoints[1]["verified"], false);
+        assert_eq!(adapter.session.stepper.lock().breakpoints.len(), 1);
+        assert_eq!(response(&messages, 7)["success"], true);
+    }
+
+    fn launch(name: &str, code: &str, stop_on_entry: bool) -> (Adapter, Buffer, PathBuf) {
+        let path = std::env::temp_dir().join(format!("uiua_dap_test_{name}.ua"));
+        std::fs::write(&path, code).unwrap();
+        let (mut adapter, buffer) = adapter();
+        adapter.handle(request(1, "initialize", json!({})));
+        adapter.handle(request(
+            2,
+            "launch",
+            json!({ "program": path, "stopOnEntry": stop_on_entry }),
+        ));
+        adapter.handle(request(3, "configurationDone", json!({})));
+        (adapter, buffer, path)
+    }
+
+    #[test]
+    fn pause_inspect_and_step() {
+        let (mut adapter, buffer, path) = launch("step", "X ← [1 2 3]\n⚂\n+1 ⚂\n", true);
+        buffer.wait_for("stopped");
+        adapter.handle(request(4, "next", json!({})));
+        // A second resume of the same pause is rejected
+        adapter.handle(request(5, "next", json!({})));
+        let messages = buffer.messages();
+        assert_eq!(response(&messages, 4)["success"], true);
+        assert_eq!(response(&messages, 5)["success"], false);
+
+        let start = Instant::now();
+        while adapter.session.paused.lock().is_none() {
+            assert!(start.elapsed() < Duration::from_secs(10));
+            thread::sleep(Duration::from_millis(10));
+        }
+        adapter.handle(request(6, "stackTrace", json!({})));
+        adapter.handle(request(
+            7,
+            "variables",
+            json!({ "variablesReference": BINDINGS_REF }),
+        ));
+        adapter.handle(request(8, "evaluate", json!({ "expression": "⇡2_2" })));
+        adapter.handle(request(9, "continue", json!({})));
+        let messages = buffer.wait_for("terminated");
+        let frames = &response(&messages, 6)["body"]["stackFrames"];
+        assert!(frames[0]["line"].as_u64().unwrap() > 0);
+        let variables = response(&messages, 7)["body"]["variables"]
+            .as_array()
+            .unwrap();
+        let x = variables.iter().find(|v| v["name"] == "X").unwrap();
+        assert_eq!(x["value"], "[1 2 3]");
+        let result = &response(&messages, 8)["body"]["result"];
+        assert!(result.as_str().unwrap().contains('╭'), "{result}");
+        let exited = messages.iter().find(|m| m["event"] == "exited").unwrap();
+        assert_eq!(exited["body"]["exitCode"], 0);
+        _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn disconnect_while_running() {
+        let (mut adapter, buffer, path) = launch("running", "⍥(+1)∞ 0\n", false);
+        thread::sleep(Duration::from_millis(50));
+        assert!(!adapter.handle(request(4, "disconnect", json!({}))));
+        let messages = buffer.wait_for("terminated");
+        let exited = messages.iter().find(|m| m["event"] == "exited").unwrap();
+        assert_eq!(exited["body"]["exitCode"], 1);
+        _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn disconnect_while_evaluating() {
+        let (mut adapter, buffer, path) = launch("evaluating", "1\n", true);
+        buffer.wait_for("stopped");
+        assert!(adapter.handle(request(4, "evaluate", json!({ "expression": "⍥(+1)∞ 0" }))));
+        thread::sleep(Duration::from_millis(50));
+        assert!(!adapter.handle(request(5, "disconnect", json!({}))));
+        buffer.wait_for("terminated");
+        assert_eq!(buffer.wait_for_response(4)["success"], false);
+        _ = std::fs::remove_file(path);
+    }
+}
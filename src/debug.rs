@@ -0,0 +1,312 @@
+//! Step debugging support for the Uiua runtime
+
+use std::path::{Path, PathBuf};
+
+use crate::{CodeSpan, Compiler, InputSrc, Instr, Uiua, UiuaResult, Value};
+
+/// A hook that is called by the runtime before each instruction is executed
+///
+/// Set one with [`Uiua::with_debugger`].
+pub trait Debugger: Send + Sync + 'static {
+    /// Called before an instruction is executed
+    ///
+    /// Returning an error aborts execution with that error.
+    fn before_instr(&self, env: &Uiua, instr: &Instr, span: Option<&CodeSpan>) -> UiuaResult;
+}
+
+/// How execution should proceed after a pause
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StepMode {
+    /// Run until a breakpoint is hit
+    Continue,
+    /// Pause at the very next instruction
+    #[default]
+    Into,
+    /// Pause at the next instruction that is not inside a deeper function call
+    Over {
+        /// The call stack depth to return to
+        depth: usize,
+    },
+    /// Pause at the next instruction after the current function returns
+    Out {
+        /// The call stack depth to leave
+        depth: usize,
+    },
+}
+
+/// A line breakpoint
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Breakpoint {
+    /// The file the breakpoint is in
+    ///
+    /// If `None`, the breakpoint applies to every input source
+    pub path: Option<PathBuf>,
+    /// The 1-indexed line of the breakpoint
+    pub line: u16,
+}
+
+impl Breakpoint {
+    /// Create a breakpoint on a line of any source
+    pub fn line(line: u16) -> Self {
+        Breakpoint { path: None, line }
+    }
+    /// Create a breakpoint on a line of a file
+    pub fn file(path: impl Into<PathBuf>, line: u16) -> Self {
+        let path = path.into();
+        let path = path.canonicalize().unwrap_or(path);
+        Breakpoint {
+            path: Some(path),
+            line,
+        }
+    }
+    /// Check if the breakpoint applies to a span
+    pub fn matches(&self, span: &CodeSpan) -> bool {
+        if !(span.start.line..=span.end.line).contains(&self.line) {
+            return false;
+        }
+        match (&self.path, &span.src) {
+            (None, _) => true,
+            (Some(bp_path), InputSrc::File(path)) => same_path(bp_path, path),
+            (Some(_), _) => false,
+        }
+    }
+}
+
+fn same_path(a: &Path, b: &Path) -> bool {
+    a == b || b.canonicalize().is_ok_and(|b| a == b)
+}
+
+/// Tracks breakpoints and stepping state
+///
+/// This contains the logic for deciding when a [`Debugger`] should pause.
+/// It does not do any IO itself.
+#[derive(Debug, Clone, Default)]
+pub struct Stepper {
+    /// The current step mode
+    pub mode: StepMode,
+    /// The set breakpoints
+    pub breakpoints: Vec<Breakpoint>,
+    last_break: Option<(InputSrc, u16)>,
+}
+
+impl Stepper {
+    /// Create a new stepper that pauses at the first instruction
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Add a breakpoint
+    ///
+    /// Returns `false` if the breakpoint was already set
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) -> bool {
+        if self.breakpoints.contains(&breakpoint) {
+            return false;
+        }
+        self.breakpoints.push(breakpoint);
+        true
+    }
+    /// Remove a breakpoint
+    ///
+    /// Only a breakpoint with the same path and line is removed.
+    /// Returns `false` if the breakpoint was not set
+    pub fn remove_breakpoint(&mut self, breakpoint: &Breakpoint) -> bool {
+        let len = self.breakpoints.len();
+        self.breakpoints.retain(|bp| bp != breakpoint);
+        self.breakpoints.len() != len
+    }
+    /// Continue until the next breakpoint
+    pub fn resume(&mut self) {
+        self.mode = StepMode::Continue;
+    }
+    /// Step to the next instruction
+    pub fn step_into(&mut self) {
+        self.mode = StepMode::Into;
+    }
+    /// Step to the next instruction at the current call depth
+    pub fn step_over(&mut self, env: &Uiua) {
+        self.mode = StepMode::Over {
+            depth: env.call_depth(),
+        };
+    }
+    /// Step until the current function returns
+    pub fn step_out(&mut self, env: &Uiua) {
+        self.mode = StepMode::Out {
+            depth: env.call_depth(),
+        };
+    }
+    /// Decide whether execution should pause before an instruction
+    pub fn should_pause(&mut self, env: &Uiua, span: Option<&CodeSpan>) -> bool {
+        self.should_pause_at(env.call_depth(), span)
+    }
+    fn should_pause_at(&mut self, depth: usize, span: Option<&CodeSpan>) -> bool {
+        let pause = match self.mode {
+            StepMode::Into => true,
+            StepMode::Over { depth: target } => depth <= target,
+            StepMode::Out { depth: target } => depth < target,
+            StepMode::Continue => span.is_some_and(|span| {
+                let loc = (span.src.clone(), span.start.line);
+                self.last_break.as_ref() != Some(&loc)
+                    && self.breakpoints.iter().any(|bp| bp.matches(span))
+            }),
+        };
+        // Remember the line of the last pause until execution leaves it,
+        // so that a breakpoint does not trigger on every instruction of its line
+        if let Some(span) = span {
+            let here = (span.src.clone(), span.start.line);
+            if pause {
+                self.last_break = Some(here);
+            } else if self.last_break.as_ref() != Some(&here) {
+                self.last_break = None;
+            }
+        }
+        pause
+    }
+}
+
+impl Uiua {
+    /// Evaluate some code against a copy of the current stack
+    ///
+    /// This is meant to be used by a [`Debugger`] while execution is paused.
+    /// The compiler should be the one that compiled the running assembly, so that bindings can be resolved.
+    /// The runtime itself is not modified.
+    ///
+    /// Returns the resulting stack
+    pub fn eval_snapshot(&self, compiler: &Compiler, code: &str) -> UiuaResult<Vec<Value>> {
+        let mut comp = compiler.clone();
+        *comp.assembly_mut() = self.asm.clone();
+        comp.assembly_mut().remove_top_level();
+        comp.load_str(code)?;
+        let mut env = self.clone();
+        env.rt.debugger = None;
+        env.run_asm(comp.finish())?;
+        Ok(env.take_stack())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lex::Loc;
+
+    fn span(src: InputSrc, line: u16, end_line: u16) -> CodeSpan {
+        let loc = |line| Loc {
+            line,
+            col: 1,
+            byte_pos: 0,
+            char_pos: 0,
+        };
+        CodeSpan {
+            src,
+            start: loc(line),
+            end: loc(end_line),
+        }
+    }
+
+    #[test]
+    fn breakpoint_matches() {
+        let file = Path::new("debug_test_does_not_exist.ua");
+        let other = Path::new("other_does_not_exist.ua");
+        let in_file = span(InputSrc::File(file.into()), 3, 3);
+        let multiline = span(InputSrc::File(file.into()), 2, 4);
+        let in_str = span(InputSrc::Str(0), 3, 3);
+
+        assert!(Breakpoint::line(3).matches(&in_file));
+        assert!(Breakpoint::line(3).matches(&in_str));
+        assert!(Breakpoint::line(3).matches(&multiline));
+        assert!(!Breakpoint::line(5).matches(&multiline));
+        assert!(!Breakpoint::line(2).matches(&in_file));
+
+        assert!(Breakpoint::file(file, 3).matches(&in_file));
+        assert!(!Breakpoint::file(file, 3).matches(&in_str));
+        assert!(!Breakpoint::file(other, 3).matches(&in_file));
+    }
+
+    #[test]
+    fn breakpoint_pauses_once_per_line() {
+        let mut stepper = Stepper::new();
+        stepper.add_breakpoint(Breakpoint::line(2));
+        stepper.resume();
+        let line1 = span(InputSrc::Str(0), 1, 1);
+        let line2 = span(InputSrc::Str(0), 2, 2);
+        assert!(!stepper.should_pause_at(0, Some(&line1)));
+        assert!(stepper.should_pause_at(0, Some(&line2)));
+        // Later instructions on the same line do not pause again
+        assert!(!stepper.should_pause_at(0, Some(&line2)));
+        assert!(!stepper.should_pause_at(0, None));
+        // Coming back to the line does
+        assert!(!stepper.should_pause_at(0, Some(&line1)));
+        assert!(stepper.should_pause_at(0, Some(&line2)));
+        assert!(!stepper.add_breakpoint(Breakpoint::line(2)));
+        stepper.add_breakpoint(Breakpoint::file("a.ua", 2));
+        assert!(stepper.remove_breakpoint(&Breakpoint::line(2)));
+        assert!(!stepper.remove_breakpoint(&Breakpoint::line(2)));
+        assert!(!stepper.remove_breakpoint(&Breakpoint::file("b.ua", 2)));
+        assert!(stepper.remove_breakpoint(&Breakpoint::file("a.ua", 2)));
+        assert!(!stepper.should_pause_at(0, Some(&line1)));
+        assert!(!stepper.should_pause_at(0, Some(&line2)));
+    }
+
+    #[test]
+    fn step_modes() {
+        let mut stepper = Stepper::new();
+        // Into pauses everywhere
+        assert!(stepper.should_pause_at(0, None));
+        assert!(stepper.should_pause_at(2, None));
+
+        // Over skips deeper calls
+        stepper.mode = StepMode::Over { depth: 1 };
+        assert!(!stepper.should_pause_at(2, None));
+        assert!(!stepper.should_pause_at(3, None));
+        assert!(stepper.should_pause_at(1, None));
+        assert!(stepper.should_pause_at(0, None));
+
+        // Out waits for the current function to return
+        stepper.mode = StepMode::Out { depth: 2 };
+        assert!(!stepper.should_pause_at(3, None));
+        assert!(!stepper.should_pause_at(2, None));
+        assert!(stepper.should_pause_at(1, None));
+
+        // Continue only pauses at breakpoints
+        stepper.resume();
+        assert!(!stepper.should_pause_at(0, None));
+        assert!(!stepper.should_pause_at(5, Some(&span(InputSrc::Str(0), 1, 1))));
+    }
+
+    struct Recorder {
+        step_over: bool,
+        state: std::sync::Mutex<(Stepper, Vec<u16>)>,
+    }
+
+    impl Debugger for std::sync::Arc<Recorder> {
+        fn before_instr(&self, env: &Uiua, _: &Instr, span: Option<&CodeSpan>) -> UiuaResult {
+            let (stepper, lines) = &mut *self.state.lock().unwrap();
+            if stepper.should_pause(env, span) {
+                lines.extend(span.map(|span| span.start.line));
+                if self.step_over {
+                    stepper.step_over(env);
+                }
+            }
+            Ok(())
+        }
+    }
+
+    fn paused_lines(code: &str, step_over: bool) -> Vec<u16> {
+        let recorder = std::sync::Arc::new(Recorder {
+            step_over,
+            state: Default::default(),
+        });
+        let mut env = Uiua::with_safe_sys().with_debugger(recorder.clone());
+        env.run_str(code).unwrap();
+        let lines = recorder.state.lock().unwrap().1.clone();
+        lines
+    }
+
+    #[test]
+    fn step_through_runtime() {
+        let code = "F ← ×2 +1\n∵F [⚂ ⚂]\n-1";
+        let into = paused_lines(code, false);
+        let over = paused_lines(code, true);
+        assert!(into.contains(&1), "{into:?}");
+        assert!(!over.contains(&1), "{over:?}");
+        assert!(over.contains(&2) && over.contains(&3), "{over:?}");
+    }
+}
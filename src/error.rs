@@ -43,6 +43,10 @@ pub enum UiuaErrorKind {
     Throw(Box<Value>, Span, Box<Inputs>),
     /// Maximum execution time exceeded
     Timeout(Span, Box<Inputs>),
+    /// Maximum memory usage exceeded
+    MemoryLimit(Span, Box<Inputs>),
+    /// The program was interrupted with an [`InterruptHandle`](crate::InterruptHandle)
+    Interrupted(Span, Box<Inputs>),
     /// The compiler panicked
     CompilerPanic(String),
 }
@@ -97,6 +101,8 @@ impl fmt::Display for UiuaError {
             UiuaErrorKind::Run(error, _) => write!(f, "{error}"),
             UiuaErrorKind::Throw(value, span, _) => write!(f, "{span}: {value}"),
             UiuaErrorKind::Timeout(..) => write!(f, "Maximum execution time exceeded"),
+            UiuaErrorKind::MemoryLimit(..) => write!(f, "Maximum memory usage exceeded"),
+            UiuaErrorKind::Interrupted(..) => write!(f, "Program interrupted"),
             UiuaErrorKind::CompilerPanic(message) => message.fmt(f),
         }
     }
@@ -217,6 +223,14 @@ impl UiuaError {
                 inputs,
                 [("Maximum execution time exceeded", span.clone())],
             ),
+            UiuaErrorKind::MemoryLimit(span, inputs) => Report::new_multi(
+                kind,
+                inputs,
+                [("Maximum memory usage exceeded", span.clone())],
+            ),
+            UiuaErrorKind::Interrupted(span, inputs) => {
+                Report::new_multi(kind, inputs, [("Program interrupted", span.clone())])
+            }
             UiuaErrorKind::CompilerPanic(message) => Report::new(kind, message),
             UiuaErrorKind::Load(..) | UiuaErrorKind::Format(..) => {
                 Report::new(kind, self.to_string())
@@ -228,7 +242,9 @@ impl UiuaError {
             UiuaErrorKind::Parse(_, inputs)
             | UiuaErrorKind::Run(_, inputs)
             | UiuaErrorKind::Throw(_, _, inputs)
-            | UiuaErrorKind::Timeout(_, inputs) => inputs,
+            | UiuaErrorKind::Timeout(_, inputs)
+            | UiuaErrorKind::MemoryLimit(_, inputs)
+            | UiuaErrorKind::Interrupted(_, inputs) => inputs,
             _ => &default_inputs,
         };
         for (info, span) in &self.infos {
@@ -7,6 +7,7 @@ use std::{
     fmt::Display,
     fs,
     iter::repeat,
+    mem::take,
     path::{Path, PathBuf},
 };
 
@@ -343,11 +344,68 @@ pub struct FormatOutput {
     pub output: String,
     /// A map from the original code spans to the formatted code spans
     pub glyph_map: Vec<(CodeSpan, (Loc, Loc))>,
+    /// The original spans of output comments and their freshly computed text
+    pub output_comments: Vec<(CodeSpan, String)>,
     /// The inputs that were formatted
     pub inputs: Inputs,
 }
 
+/// An output comment whose recorded values differ from freshly computed ones
+#[derive(Debug, Clone)]
+pub struct OutputCommentMismatch {
+    /// The span of the output comment in the original code
+    pub span: CodeSpan,
+    /// The lines of the comment as written
+    pub expected: Vec<String>,
+    /// The lines of the comment with fresh values
+    pub actual: Vec<String>,
+}
+
 impl FormatOutput {
+    /// Get the output comments whose text in the original code does not match their fresh values
+    ///
+    /// Differences in indentation and trailing whitespace are ignored.
+    pub fn output_comment_mismatches(&self) -> Vec<OutputCommentMismatch> {
+        fn comment_lines(s: &str) -> Vec<String> {
+            s.lines().map(|line| line.trim().into()).collect()
+        }
+        (self.output_comments.iter())
+            .filter_map(|(span, fresh)| {
+                let expected = span.as_str(&self.inputs, comment_lines);
+                let actual = comment_lines(fresh);
+                (expected != actual).then(|| OutputCommentMismatch {
+                    span: span.clone(),
+                    expected,
+                    actual,
+                })
+            })
+            .collect()
+    }
+    /// Replace the output comments in the original code with their fresh text
+    ///
+    /// Unlike [`FormatOutput::output`], the rest of the code is left as it was written.
+    pub fn update_output_comments(&self, input: &str) -> String {
+        let mut comments: Vec<_> = self.output_comments.iter().collect();
+        comments.sort_by_key(|(span, _)| span.start.byte_pos);
+        let mut output = String::with_capacity(input.len());
+        let mut end = 0;
+        for (span, fresh) in comments {
+            let start = span.start.byte_pos as usize;
+            output.push_str(&input[end..start]);
+            let indent = input[..start].rsplit('\n').next().unwrap_or_default();
+            let indent = indent.chars().count();
+            for (i, line) in fresh.lines().enumerate() {
+                if i > 0 {
+                    output.push('\n');
+                    output.extend(repeat(' ').take(indent));
+                }
+                output.push_str(line.trim());
+            }
+            end = span.end.byte_pos as usize;
+        }
+        output.push_str(&input[end..]);
+        output
+    }
     /// Map a cursor position in unfomatted code to glyph start/end positions in formatted code
     pub fn map_char_pos(&self, pos: u32) -> (u32, u32) {
         let mut pairs = self.glyph_map.iter().cloned();
@@ -389,19 +447,65 @@ pub fn format<P: AsRef<Path>>(
     path: P,
     config: &FormatConfig,
 ) -> UiuaResult<FormatOutput> {
-    format_impl(input, path.as_ref().into(), config)
+    format_impl(input, path.as_ref().into(), config, None)
 }
 
 /// Format Uiua code without a path
 pub fn format_str(input: &str, config: &FormatConfig) -> UiuaResult<FormatOutput> {
-    format_impl(input, InputSrc::Str(0), config)
+    format_impl(input, InputSrc::Str(0), config, None)
 }
 
-fn format_impl(input: &str, src: InputSrc, config: &FormatConfig) -> UiuaResult<FormatOutput> {
+/// Format Uiua code, running it on the given runtime to fill in output comments
+///
+/// [`format`] runs the code with a [`SafeSys`] backend and a short execution limit.
+/// This uses the runtime's backend and limits instead.
+/// The compiler's module root and search paths are used for imports.
+///
+/// The path is used for error reporting
+pub fn format_with_runtime<P: AsRef<Path>>(
+    input: &str,
+    path: P,
+    config: &FormatConfig,
+    env: &mut Uiua,
+    compiler: &mut Compiler,
+) -> UiuaResult<FormatOutput> {
+    let src = InputSrc::from(path.as_ref());
+    #[cfg(feature = "native_sys")]
+    let enabled = crate::sys_native::set_output_enabled(false);
+    let res = (compiler.print_diagnostics(false))
+        .mode(RunMode::All)
+        .pre_eval_mode(PreEvalMode::Lazy)
+        .load_str_src(input, src.clone())
+        .map(drop)
+        .and_then(|()| env.run_compiler(compiler));
+    #[cfg(feature = "native_sys")]
+    crate::sys_native::set_output_enabled(enabled);
+    let values = output_comment_values(take(&mut env.rt.output_comments), res);
+    format_impl(input, src, config, Some(values))
+}
+
+/// Get the values for output comments from a run, showing any error in the next comment
+fn output_comment_values(
+    mut values: HashMap<usize, Vec<Vec<Value>>>,
+    res: UiuaResult,
+) -> HashMap<usize, Vec<Vec<Value>>> {
+    if let Err(e) = res {
+        let next = (0..).take_while(|i| values.contains_key(i)).count();
+        values.insert(next, vec![vec![e.to_string().into()]]);
+    }
+    values
+}
+
+fn format_impl(
+    input: &str,
+    src: InputSrc,
+    config: &FormatConfig,
+    output_comments: Option<HashMap<usize, Vec<Vec<Value>>>>,
+) -> UiuaResult<FormatOutput> {
     let mut inputs = Inputs::default();
     let (items, errors, _) = parse(input, src.clone(), &mut inputs);
     if errors.is_empty() {
-        let (output, glyph_map) = Formatter {
+        let (output, glyph_map, output_comments) = Formatter {
             src,
             config,
             inputs: &inputs,
@@ -409,12 +513,14 @@ fn format_impl(input: &str, src: InputSrc, config: &FormatConfig) -> UiuaResult<
             glyph_map: Vec::new(),
             end_of_line_comments: Vec::new(),
             prev_import_function: None,
-            output_comments: None,
+            output_comments,
+            output_comment_texts: Vec::new(),
         }
         .format_top_items(&items);
         Ok(FormatOutput {
             output,
             glyph_map,
+            output_comments,
             inputs,
         })
     } else {
@@ -452,6 +558,7 @@ pub(crate) fn format_words(words: &[Sp<Word>], inputs: &Inputs) -> String {
         end_of_line_comments: Vec::new(),
         prev_import_function: None,
         output_comments: None,
+        output_comment_texts: Vec::new(),
     };
     formatter.format_words(words, true, 0, true);
     formatter.output
@@ -467,6 +574,7 @@ pub(crate) fn format_word(word: &Sp<Word>, inputs: &Inputs) -> String {
         end_of_line_comments: Vec::new(),
         prev_import_function: None,
         output_comments: None,
+        output_comment_texts: Vec::new(),
     };
     formatter.format_word(word, 0, true);
     formatter.output
@@ -481,12 +589,13 @@ struct Formatter<'a> {
     end_of_line_comments: Vec<(usize, String)>,
     prev_import_function: Option<Ident>,
     output_comments: Option<HashMap<usize, Vec<Vec<Value>>>>,
+    output_comment_texts: Vec<(CodeSpan, String)>,
 }
 
 type GlyphMap = Vec<(CodeSpan, (Loc, Loc))>;
 
 impl<'a> Formatter<'a> {
-    fn format_top_items(mut self, items: &[Item]) -> (String, GlyphMap) {
+    fn format_top_items(mut self, items: &[Item]) -> (String, GlyphMap, Vec<(CodeSpan, String)>) {
         self.format_items(items);
         let mut output = self.output;
         while output.ends_with('\n') {
@@ -495,7 +604,7 @@ impl<'a> Formatter<'a> {
         if self.config.trailing_newline && !output.trim().is_empty() {
             output.push('\n');
         }
-        (output, self.glyph_map)
+        (output, self.glyph_map, self.output_comment_texts)
     }
     fn format_items(&mut self, items: &[Item]) {
         for item in items {
@@ -575,11 +684,27 @@ impl<'a> Formatter<'a> {
     }
     fn format_item(&mut self, item: &Item) {
         match item {
-            Item::TestScope(items) => {
+            Item::TestScope(scope) => {
                 self.prev_import_function = None;
                 self.output.push_str("---");
+                // Keep the scope's name on the opening line
+                let mut items = Cow::Borrowed(&scope.value);
+                if let Some(name) = test_scope_name(scope) {
+                    self.output.push_str(" #");
+                    if !name.value.starts_with(' ') && self.config.comment_space_after_hash {
+                        self.output.push(' ');
+                    }
+                    self.output.push_str(name.value.trim_end());
+                    let items = items.to_mut();
+                    if let Item::Words(lines) = &mut items[0] {
+                        lines.remove(0);
+                        if lines.is_empty() {
+                            items.remove(0);
+                        }
+                    }
+                }
                 self.output.push('\n');
-                self.format_items(&items.value);
+                self.format_items(&items);
                 self.output.push_str("---");
             }
             Item::Words(lines) => {
@@ -1122,6 +1247,7 @@ impl<'a> Formatter<'a> {
                     s.push_str(&line);
                 }
                 self.push(&word.span, &s);
+                (self.output_comment_texts).push((word.span.clone(), s));
             }
         }
     }
@@ -1222,12 +1348,7 @@ impl<'a> Formatter<'a> {
             #[cfg(feature = "native_sys")]
             crate::sys_native::set_output_enabled(enabled);
 
-            let mut values = env.rt.output_comments;
-            if let Err(e) = res {
-                let next = (0..).take_while(|i| values.contains_key(i)).count();
-                values.insert(next, vec![vec![e.to_string().into()]]);
-            }
-            values
+            output_comment_values(env.rt.output_comments, res.map(drop))
         });
         values.remove(&index).unwrap_or_default()
     }
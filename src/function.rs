@@ -247,6 +247,29 @@ impl Instr {
     pub(crate) fn is_code(&self) -> bool {
         !matches!(self, Self::NoInline)
     }
+    /// Get the index of the instruction's span, if it has one
+    pub(crate) fn span_index(&self) -> Option<usize> {
+        match self {
+            Instr::BindGlobal { span, .. }
+            | Instr::EndArray { span, .. }
+            | Instr::Prim(_, span)
+            | Instr::ImplPrim(_, span)
+            | Instr::Call(span)
+            | Instr::CallRecursive(span)
+            | Instr::Recur(span)
+            | Instr::Switch { span, .. }
+            | Instr::Format { span, .. }
+            | Instr::MatchFormatPattern { span, .. }
+            | Instr::StackSwizzle(_, span)
+            | Instr::Label { span, .. }
+            | Instr::Unpack { span, .. }
+            | Instr::TouchStack { span, .. }
+            | Instr::PushTemp { span, .. }
+            | Instr::PopTemp { span, .. }
+            | Instr::CopyToTemp { span, .. } => Some(*span),
+            _ => None,
+        }
+    }
 }
 
 pub(crate) struct FmtInstrs<'a>(pub &'a [Instr], pub &'a Assembly);
@@ -84,6 +84,26 @@ let res = uiua.pop_num().unwrap();
 assert_eq!(res, 5.0);
 ```
 
+Rust functions with arguments and outputs that implement [`FromValue`] and [`IntoValue`] can be bound with [`Compiler::create_bind_typed_function`]. The signature is derived from the function's type.
+```rust
+use uiua::*;
+
+let mut comp = Compiler::new();
+comp.create_bind_typed_function("Repeat", |s: String, n: usize| s.repeat(n)).unwrap();
+comp.create_bind_typed_function("Sums", |rows: Vec<Vec<f64>>| {
+    rows.into_iter().map(|row| row.iter().sum()).collect::<Vec<f64>>()
+}).unwrap();
+comp.load_str("Repeat \"ab\" 3\nSums [1_2 3_4]").unwrap();
+let asm = comp.finish();
+
+let mut uiua = Uiua::with_native_sys();
+uiua.run_asm(asm).unwrap();
+let sums = uiua.pop_nums().unwrap();
+assert_eq!(sums, [3.0, 7.0]);
+let repeated = uiua.pop_string().unwrap();
+assert_eq!(repeated, "ababab");
+```
+
 Bindings can be retrieved with [`Uiua::bound_values`] or [`Uiua::bound_functions`].
 ```rust
 use uiua::*;
@@ -109,6 +129,100 @@ let res = uiua.pop_int().unwrap();
 assert_eq!(res, 5);
 ```
 
+Functions can also be called with arguments on their own stack with [`Uiua::call_with`] or [`Uiua::call_as`].
+```rust
+use uiua::*;
+
+let mut uiua = Uiua::with_native_sys();
+uiua.run_str("F ← ⊂⊃+-").unwrap();
+let f = uiua.bound_functions().remove("F").unwrap();
+
+let outputs = uiua.call_with(&f, [5.into(), 3.into()]).unwrap();
+assert_eq!(outputs.len(), 1);
+let res: Vec<f64> = uiua.call_as(&f, [5.into(), 3.into()]).unwrap();
+assert_eq!(res, [8.0, -2.0]);
+assert!(uiua.call_with(&f, [1.into()]).is_err());
+
+uiua.run_str("G ← ⊃+-").unwrap();
+let g = uiua.bound_functions().remove("G").unwrap();
+let (sum, diff): (f64, f64) = uiua.call_as(&g, [5.into(), 3.into()]).unwrap();
+assert_eq!((sum, diff), (8.0, -2.0));
+```
+
+Rust types that implement [`serde::Serialize`] and [`serde::Deserialize`] can be converted to and from [`Value`]s with [`Value::from_serialize`] and [`Value::deserialize_into`]. Structs become map arrays.
+```rust
+use uiua::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Config {
+    name: String,
+    sizes: Vec<u32>,
+    mode: Option<Mode>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+enum Mode {
+    Fast,
+    Limit(f64),
+}
+
+let config = Config {
+    name: "test".into(),
+    sizes: vec![1, 2, 3],
+    mode: Some(Mode::Limit(0.5)),
+};
+
+let mut uiua = Uiua::with_native_sys();
+uiua.push(Value::from_serialize(&config).unwrap());
+uiua.run_str("°□get \"sizes\"").unwrap();
+assert_eq!(uiua.pop_nums().unwrap(), [1.0, 2.0, 3.0]);
+
+let value = Value::from_serialize(&config).unwrap();
+assert_eq!(value.deserialize_into::<Config>().unwrap(), config);
+```
+
+To run untrusted code, wrap a backend in a [`PolicySys`], which only allows the [`SysOpClass`]es you choose. It can also restrict file access to a directory and limit how much is read and written.
+```rust
+use uiua::*;
+
+let policy = PolicySys::new(SafeSys::new())
+    .allow(SysOpClass::StdIO)
+    .with_output_limit(1 << 20);
+let mut uiua = Uiua::with_backend(policy);
+uiua.run_str("&p \"Hello!\"").unwrap();
+let error = uiua.run_str("&fras \"secret.txt\"").err().unwrap();
+assert!(error.to_string().contains("Filesystem operations are not allowed"));
+```
+
+A running program can be stopped from another thread with an [`InterruptHandle`].
+```rust
+use uiua::*;
+use std::{thread, time::Duration};
+
+let mut uiua = Uiua::with_native_sys();
+let handle = uiua.interrupt_handle();
+thread::spawn(move || {
+    thread::sleep(Duration::from_millis(10));
+    handle.interrupt();
+});
+let error = uiua.run_str("⍢(+1)1 0").err().unwrap();
+assert!(matches!(error.kind, UiuaErrorKind::Interrupted(..)));
+```
+
+[`MemSys`] is a backend with an in-memory filesystem, which is useful for testing code that does file IO.
+```rust
+use uiua::*;
+
+let sys = MemSys::new()
+    .with_file("input.txt", "1 2 3")
+    .with_stdin("4\n");
+let mut uiua = Uiua::with_backend(sys);
+uiua.run_str("&fwa \"output.txt\" ⊂: &sc &fras \"input.txt\"").unwrap();
+let sys = uiua.downcast_backend::<MemSys>().unwrap();
+assert_eq!(sys.file("output.txt").unwrap(), b"1 2 34");
+```
+
 You can format Uiua code with the [`mod@format`] module.
 ```rust
 use uiua::format::*;
@@ -133,6 +247,7 @@ The `uiua` crate has the following noteable feature flags:
 - `invoke`: Enables the `&invk` system function
 - `trash`: Enables the `&ftr` system function
 - `raw_mode`: Enables the `&raw` system function
+- `dap`: Enables the Debug Adapter Protocol server in the [`dap`] module
 */
 
 #![allow(clippy::single_match, clippy::needless_range_loop)]
@@ -146,7 +261,12 @@ mod boxed;
 mod check;
 mod compile;
 mod complex;
+mod convert;
+mod coverage;
 mod cowslice;
+#[cfg(feature = "dap")]
+pub mod dap;
+mod debug;
 mod error;
 mod ffi;
 pub mod format;
@@ -154,19 +274,29 @@ mod function;
 mod grid_fmt;
 mod lex;
 pub mod lsp;
+mod manifest;
 mod optimize;
 mod parse;
 mod primitive;
 #[doc(hidden)]
 pub mod profile;
+mod profiler;
 mod run;
+mod serde_value;
 mod shape;
 #[cfg(feature = "stand")]
 #[doc(hidden)]
 pub mod stand;
 mod sys;
+#[cfg(feature = "http")]
+mod sys_http;
+mod sys_mem;
 #[cfg(feature = "native_sys")]
 mod sys_native;
+mod sys_policy;
+#[cfg(feature = "chrono")]
+mod sys_time;
+mod testing;
 mod value;
 
 #[allow(unused_imports)]
@@ -175,17 +305,26 @@ pub use self::{
     assembly::*,
     boxed::*,
     compile::*,
+    convert::*,
+    coverage::*,
+    debug::*,
     error::*,
     ffi::*,
     function::*,
     lex::is_ident_char,
     lex::*,
     lsp::{spans, SpanKind},
+    manifest::*,
     parse::{ident_modifier_args, parse, ParseError},
     primitive::*,
+    profiler::*,
     run::*,
+    serde_value::*,
     shape::*,
     sys::*,
+    sys_mem::*,
+    sys_policy::*,
+    testing::*,
     value::*,
 };
 
@@ -291,6 +430,157 @@ mod tests {
         }
     }
 
+    #[test]
+    fn comptime_eval_uses_top_level_pushes() {
+        use super::*;
+        // Lines at the top level are folded into their results
+        let mut comp = Compiler::new();
+        comp.load_str("1 2\n+").unwrap();
+        let asm = comp.finish();
+        assert!(!(asm.instrs.iter()).any(|instr| matches!(instr, Instr::Prim(Primitive::Add, _))));
+        // Pushes from lines that were already run and removed are not folded
+        let mut env = Uiua::with_safe_sys();
+        let mut comp = Compiler::new();
+        for line in ["1 2", "+", "×2"] {
+            comp.load_str(line).unwrap();
+            env.run_asm(comp.finish()).unwrap();
+            let mut asm = env.take_asm();
+            asm.remove_top_level();
+            *comp.assembly_mut() = asm;
+        }
+        assert_eq!(env.take_stack(), [Value::from(6)]);
+    }
+
+    #[test]
+    fn test_scope_names_stay_on_opening_line() {
+        use super::*;
+        let config = format::FormatConfig::default().with_trailing_newline(false);
+        let input = "---#  name\n⍤\"a\" 1\n---\n---\n# not a name\n---";
+        let formatted = format::format_str(input, &config).unwrap().output;
+        assert_eq!(
+            formatted,
+            "--- #  name\n⍤\"a\" 1\n---\n---\n# not a name\n---"
+        );
+        assert_eq!(
+            format::format_str(&formatted, &config).unwrap().output,
+            formatted
+        );
+    }
+
+    #[test]
+    fn output_comments_update_in_place() {
+        use super::*;
+        let input = "X  ←  5\n+1 X\n## 7\n  [1 2\n   3]\n  ## old\n";
+        let mut env = Uiua::with_safe_sys();
+        let mut comp = Compiler::new();
+        let config = format::FormatConfig::default();
+        let formatted =
+            format::format_with_runtime(input, "test.ua", &config, &mut env, &mut comp).unwrap();
+        let mismatches = formatted.output_comment_mismatches();
+        assert_eq!(mismatches.len(), 2);
+        assert_eq!(mismatches[0].expected, ["## 7"]);
+        assert_eq!(mismatches[0].actual, ["## 6"]);
+        let updated = formatted.update_output_comments(input);
+        assert_eq!(
+            updated,
+            "X  ←  5\n+1 X\n## 6\n  [1 2\n   3]\n  ## [1 2 3]\n"
+        );
+    }
+
+    #[test]
+    fn memory_limit() {
+        use super::*;
+        let mut env = Uiua::with_safe_sys().with_memory_limit(1 << 20);
+        let Err(err) = env.run_str("⇡1e6") else {
+            panic!("expected a memory limit error");
+        };
+        assert!(matches!(err.kind, UiuaErrorKind::MemoryLimit(..)), "{err}");
+        // Limits are not remembered after an error is caught or a run ends
+        env.run_str("⍣(⇡1e6)0 ⇡+1000⌊⚂").unwrap();
+        assert!(env.memory_usage().unwrap() >= 1000 * size_of::<f64>());
+        // Freed arrays are not counted
+        env.run_str("⍥(◌⇡1e4)1000").unwrap();
+        assert!(env.memory_usage().unwrap() < 1 << 20);
+        assert_eq!(Uiua::with_safe_sys().memory_usage(), None);
+    }
+
+    #[test]
+    fn memory_limit_checked_before_allocating() {
+        use super::*;
+        // Each of these would allocate hundreds of megabytes
+        for code in [
+            "⇡1e8",
+            "↯1e8 0",
+            "▽1e8 [1]",
+            "wait spawn(⇡1e8)",
+            "⊞+⇡1e4⇡1e4",
+        ] {
+            let mut env = Uiua::with_safe_sys().with_memory_limit(1 << 20);
+            let Err(err) = env.run_str(code) else {
+                panic!("expected a memory limit error for {code}");
+            };
+            assert!(
+                matches!(err.kind, UiuaErrorKind::MemoryLimit(..)),
+                "{code}: {err}"
+            );
+            assert!(env.memory_usage().unwrap() < 1 << 20, "{code}");
+        }
+    }
+
+    #[test]
+    fn call_with_limits() {
+        use super::*;
+        use std::time::Duration;
+        let mut env = Uiua::with_safe_sys()
+            .with_execution_limit(Duration::from_millis(500))
+            .with_memory_limit(1 << 20);
+        env.run_str("F ← ⇡").unwrap();
+        let f = env.bound_functions().remove("F").unwrap();
+        // Each call gets the whole budget
+        let mut kept = Vec::new();
+        for _ in 0..3 {
+            kept.push(env.call_with(&f, [1e5.into()]).unwrap());
+            std::thread::sleep(Duration::from_millis(300));
+        }
+        let Err(err) = env.call_with(&f, [1e6.into()]) else {
+            panic!("expected a memory limit error");
+        };
+        assert!(matches!(err.kind, UiuaErrorKind::MemoryLimit(..)), "{err}");
+    }
+
+    #[test]
+    fn interrupt() {
+        use super::*;
+        use std::{thread, time::Duration};
+        let mut env = Uiua::with_native_sys();
+        let handle = env.interrupt_handle();
+        let interrupter = handle.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            interrupter.interrupt();
+        });
+        // Interrupts are not caught, and they stop sleeps and spawned threads
+        let Err(err) = env.run_str("⍣(wait spawn(1 &sl 100))0") else {
+            panic!("expected an interrupt");
+        };
+        assert!(matches!(err.kind, UiuaErrorKind::Interrupted(..)), "{err}");
+        // Interrupts stay until reset
+        assert!(env.run_str("1").is_err());
+        handle.reset();
+        env.run_str("1").unwrap();
+        // Interrupts stop waiting for datagrams
+        let interrupter = handle.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            interrupter.interrupt();
+        });
+        let Err(err) = env.run_str("&udpr 100 &udpb \"127.0.0.1:0\"") else {
+            panic!("expected an interrupt");
+        };
+        assert!(matches!(err.kind, UiuaErrorKind::Interrupted(..)), "{err}");
+        handle.reset();
+    }
+
     #[test]
     fn no_dbgs() {
         fn recurse_dirs(dir: &std::path::Path, f: &impl Fn(&std::path::Path)) {
@@ -2,11 +2,16 @@
 compile_error!("To compile the uiua interpreter binary, you must enable the `binary` feature flag");
 
 use std::{
-    env, fmt, fs,
+    borrow::Cow,
+    env,
+    ffi::OsString,
+    fmt, fs,
     io::{self, stderr, stdin, BufRead, Write},
+    mem::take,
     path::{Path, PathBuf},
     process::{exit, Child, Command, Stdio},
-    sync::mpsc::channel,
+    str::FromStr,
+    sync::{mpsc::channel, Arc},
     thread::sleep,
     time::Duration,
 };
@@ -17,12 +22,27 @@ use instant::Instant;
 use notify::{EventKind, RecursiveMode, Watcher};
 use once_cell::sync::Lazy;
 use parking_lot::Mutex;
-use rustyline::{error::ReadlineError, DefaultEditor};
+use rustyline::{
+    completion::{Completer, Pair},
+    config::CompletionType,
+    error::ReadlineError,
+    highlight::Highlighter,
+    hint::{Hint, Hinter},
+    history::DefaultHistory,
+    validate::Validator,
+    Cmd, ConditionalEventHandler, Context, Editor, Event, EventContext, EventHandler, Helper,
+    KeyEvent, RepeatCount,
+};
+use serde::{Deserialize, Serialize};
 use uiua::{
-    format::{format_file, format_str, FormatConfig, FormatConfigSource},
+    env_search_paths,
+    format::{format_file, format_str, format_with_runtime, FormatConfig, FormatConfigSource},
+    git_module_path, is_ident_char, json_test_report, junit_test_report,
     lsp::BindingDocsKind,
-    Assembly, Compiler, NativeSys, PrimClass, RunMode, SpanKind, Uiua, UiuaError, UiuaErrorKind,
-    UiuaResult, Value,
+    project_dir, Assembly, BindingKind, Breakpoint, CodeSpan, Compiler, Debugger, Ident, Instr,
+    Manifest, NativeSys, PreEvalMode, PrimClass, Primitive, Profile, RunMode, SpanKind, Stepper,
+    TempStack, TestCase, TestCaseKind, TestOptions, Uiua, UiuaError, UiuaErrorKind, UiuaResult,
+    Value, MANIFEST_FILE, VENDOR_DIR,
 };
 
 fn main() {
@@ -75,6 +95,16 @@ fn run() -> UiuaResult {
                 } else {
                     fs::write("main.ua", "\"Hello, World!\"").unwrap();
                 }
+                if Path::new(MANIFEST_FILE).exists() {
+                    eprintln!("File already exists: {MANIFEST_FILE}");
+                } else {
+                    let name: String = (env::current_dir().ok())
+                        .and_then(|dir| dir.file_name().map(|name| name.to_string_lossy().into()))
+                        .unwrap_or_else(|| "main".into());
+                    if let Err(e) = Manifest::new(name).save(Path::new(".")) {
+                        eprintln!("{e}");
+                    }
+                }
             }
             App::Fmt {
                 path,
@@ -115,7 +145,9 @@ fn run() -> UiuaResult {
                 no_color,
                 formatter_options,
                 time_instrs,
+                profile,
                 mode,
+                module_options,
                 #[cfg(feature = "audio")]
                 audio_options,
                 args,
@@ -137,7 +169,10 @@ fn run() -> UiuaResult {
                     .with_file_path(&path)
                     .with_args(args)
                     .time_instrs(time_instrs);
-                if path.extension().is_some_and(|ext| ext == "uasm") {
+                if profile.is_some() {
+                    rt = rt.with_profiler();
+                }
+                let res = if path.extension().is_some_and(|ext| ext == "uasm") {
                     let uasm = match fs::read_to_string(&path) {
                         Ok(json) => json,
                         Err(e) => {
@@ -152,7 +187,7 @@ fn run() -> UiuaResult {
                             return Ok(());
                         }
                     };
-                    rt.run_asm(assembly)?;
+                    rt.run_asm(assembly)
                 } else {
                     if !no_format {
                         let config = FormatConfig::from_source(
@@ -163,12 +198,25 @@ fn run() -> UiuaResult {
                     }
                     let mode = mode.unwrap_or(RunMode::Normal);
                     rt.compile_run(|comp| {
-                        comp.mode(mode).print_diagnostics(true).load_file(&path)
-                    })?;
+                        comp.mode(mode)
+                            .module_root(module_options.root(Some(&path)))
+                            .search_paths(module_options.search_paths())
+                            .print_diagnostics(true)
+                            .load_file(&path)
+                    })
+                    .map(drop)
+                };
+                if let Some((profile_path, profile)) = profile.zip(rt.profile()) {
+                    write_profile(&path, &profile_path, &profile);
                 }
+                res?;
                 print_stack(&rt.take_stack(), !no_color);
             }
-            App::Build { path, output } => {
+            App::Build {
+                path,
+                output,
+                module_options,
+            } => {
                 let path = if let Some(path) = path {
                     path
                 } else {
@@ -181,6 +229,8 @@ fn run() -> UiuaResult {
                     }
                 };
                 let assembly = Compiler::with_backend(NativeSys)
+                    .module_root(module_options.root(Some(&path)))
+                    .search_paths(module_options.search_paths())
                     .print_diagnostics(true)
                     .load_file(&path)?
                     .finish();
@@ -193,6 +243,7 @@ fn run() -> UiuaResult {
             App::Eval {
                 code,
                 no_color,
+                module_options,
                 #[cfg(feature = "audio")]
                 audio_options,
                 args,
@@ -202,14 +253,37 @@ fn run() -> UiuaResult {
                 let mut rt = Uiua::with_native_sys().with_args(args);
                 rt.compile_run(|comp| {
                     comp.mode(RunMode::Normal)
+                        .module_root(module_options.root(None))
+                        .search_paths(module_options.search_paths())
                         .print_diagnostics(true)
                         .load_str(&code)
                 })?;
                 print_stack(&rt.take_stack(), !no_color);
             }
+            App::Vendor { path } => {
+                let path = if let Some(path) = path {
+                    path
+                } else {
+                    match working_file_path() {
+                        Ok(path) => path,
+                        Err(e) => {
+                            eprintln!("{}", e);
+                            return Ok(());
+                        }
+                    }
+                };
+                vendor(&path)?;
+            }
             App::Test {
                 path,
+                format,
+                filter,
+                parallel,
+                coverage,
+                snapshot,
+                update,
                 formatter_options,
+                module_options,
                 args,
             } => {
                 let path = if let Some(path) = path {
@@ -225,16 +299,120 @@ fn run() -> UiuaResult {
                 };
                 let config =
                     FormatConfig::from_source(formatter_options.format_config_source, Some(&path))?;
-                format_file(&path, &config)?;
+                if snapshot {
+                    return check_snapshots(&path, &config, update, &module_options, args);
+                }
+                let formatted = format_file(&path, &config).map(drop);
                 let mut rt = Uiua::with_native_sys()
                     .with_file_path(&path)
                     .with_args(args);
-                rt.compile_run(|comp| {
-                    comp.mode(RunMode::Test)
-                        .print_diagnostics(true)
-                        .load_file(path)
-                })?;
-                println!("No failures!");
+                if coverage.is_some() {
+                    rt = rt.with_coverage();
+                }
+                let options = TestOptions { filter, parallel };
+                if let (TestFormat::Text, None, false, None) =
+                    (format, &options.filter, parallel, &coverage)
+                {
+                    formatted?;
+                    rt.compile_run(|comp| {
+                        comp.mode(RunMode::Test)
+                            .module_root(module_options.root(Some(&path)))
+                            .search_paths(module_options.search_paths())
+                            .print_diagnostics(true)
+                            .load_file(&path)
+                    })?;
+                    println!("No failures!");
+                    return Ok(());
+                }
+                // Lazy pre-evaluation keeps asserts from being folded away
+                let mut compiler = Compiler::with_backend(NativeSys);
+                let loaded = formatted.and_then(|()| {
+                    (compiler.mode(RunMode::Test))
+                        .module_root(module_options.root(Some(&path)))
+                        .search_paths(module_options.search_paths())
+                        .pre_eval_mode(PreEvalMode::Lazy)
+                        .print_diagnostics(matches!(format, TestFormat::Text))
+                        .load_file(&path)
+                        .map(drop)
+                });
+                let start = Instant::now();
+                let cases = match loaded {
+                    Ok(()) => rt.run_tests(compiler.finish(), &options),
+                    // Reports are still written so that CI sees the failure
+                    Err(e) if !matches!(format, TestFormat::Text) => {
+                        vec![TestCase::top_level(&e, Duration::ZERO)]
+                    }
+                    Err(e) => return Err(e),
+                };
+                let duration = start.elapsed();
+                if let Some((report_path, report)) = coverage.zip(rt.coverage()) {
+                    let report_string = if report_path.extension().is_some_and(|ext| ext == "json")
+                    {
+                        report.to_json()
+                    } else {
+                        report.to_lcov()
+                    };
+                    if let Err(e) = fs::write(&report_path, report_string) {
+                        eprintln!("Failed to write coverage report: {e}");
+                    }
+                    eprintln!(
+                        "Covered {} of {} spans",
+                        report.covered(),
+                        report.spans.len()
+                    );
+                }
+                match format {
+                    TestFormat::Text => print_test_text(&cases),
+                    TestFormat::Json => println!("{}", json_test_report(&path, &cases, duration)),
+                    TestFormat::Junit => print!("{}", junit_test_report(&path, &cases, duration)),
+                }
+                if cases.iter().any(|case| !case.passed()) {
+                    exit(1);
+                }
+            }
+            App::Debug {
+                path,
+                breakpoints,
+                module_options,
+                args,
+            } => {
+                let path = if let Some(path) = path {
+                    path
+                } else {
+                    match working_file_path() {
+                        Ok(path) => path,
+                        Err(e) => {
+                            eprintln!("{}", e);
+                            return Ok(());
+                        }
+                    }
+                };
+                let mut stepper = Stepper::new();
+                if !breakpoints.is_empty() {
+                    stepper.resume();
+                }
+                for line in breakpoints {
+                    stepper.add_breakpoint(Breakpoint::line(line));
+                }
+                let mut compiler = Compiler::with_backend(NativeSys);
+                compiler
+                    .mode(RunMode::Normal)
+                    .module_root(module_options.root(Some(&path)))
+                    .search_paths(module_options.search_paths())
+                    .pre_eval_mode(PreEvalMode::Lazy)
+                    .print_diagnostics(true)
+                    .load_file(&path)?;
+                let asm = compiler.assembly().clone();
+                let mut rt = Uiua::with_native_sys()
+                    .with_file_path(&path)
+                    .with_args(args)
+                    .with_debugger(CliDebugger {
+                        stepper: Mutex::new(stepper),
+                        compiler,
+                    });
+                eprintln!("Debugging {} (type `help` for commands)", path.display());
+                rt.run_asm(asm)?;
+                print_stack(&rt.take_stack(), true);
             }
             App::Watch {
                 no_format,
@@ -243,6 +421,7 @@ fn run() -> UiuaResult {
                 clear,
                 args,
                 stdin_file,
+                module_options,
             } => {
                 if let Err(e) = watch(
                     working_file_path().ok().as_deref(),
@@ -252,15 +431,20 @@ fn run() -> UiuaResult {
                     clear,
                     args,
                     stdin_file,
+                    &module_options,
                 ) {
                     eprintln!("Error watching file: {e}");
                 }
             }
             #[cfg(feature = "lsp")]
             App::Lsp => uiua::lsp::run_language_server(),
+            #[cfg(feature = "dap")]
+            App::Dap => uiua::dap::run_debug_adapter(),
             App::Repl {
                 file,
+                session,
                 formatter_options,
+                module_options,
                 #[cfg(feature = "audio")]
                 audio_options,
                 args,
@@ -274,12 +458,33 @@ fn run() -> UiuaResult {
                 setup_audio(audio_options);
                 let mut rt = Uiua::with_native_sys().with_args(args);
                 let mut compiler = Compiler::with_backend(NativeSys);
-                compiler.mode(RunMode::Normal).print_diagnostics(true);
+                (compiler.mode(RunMode::Normal))
+                    .module_root(module_options.root(None))
+                    .search_paths(module_options.search_paths())
+                    .print_diagnostics(true);
+                let mut lines = Vec::new();
+                if let Some(name) = &session {
+                    if let Err(e) = ReplSession::path(name) {
+                        eprintln!("{e}");
+                        exit(1);
+                    }
+                    let restored = ReplSession::load(name).and_then(|saved| {
+                        (saved.map(|saved| saved.restore(&mut rt, &mut compiler))).transpose()
+                    });
+                    match restored {
+                        Ok(Some(restored)) => {
+                            lines = restored;
+                            println!("Restored session {name}");
+                        }
+                        Ok(None) => {}
+                        Err(e) => eprintln!("Failed to restore session {name}: {e}"),
+                    }
+                }
                 if let Some(file) = file {
                     compiler.load_file(file)?;
                     rt.run_compiler(&mut compiler)?;
                 }
-                repl(rt, compiler, true, config);
+                repl(rt, compiler, lines, session, true, config);
             }
             App::Update { main, check } => update(main, check),
             #[cfg(feature = "stand")]
@@ -347,6 +552,7 @@ fn run() -> UiuaResult {
                     false,
                     Vec::new(),
                     None,
+                    &ModuleOptions::default(),
                 ),
                 Err(NoWorkingFile::MultipleFiles) => watch(
                     None,
@@ -356,6 +562,7 @@ fn run() -> UiuaResult {
                     false,
                     Vec::new(),
                     None,
+                    &ModuleOptions::default(),
                 ),
                 Err(nwf) => {
                     _ = e.print();
@@ -421,6 +628,7 @@ fn working_file_path() -> Result<PathBuf, NoWorkingFile> {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn watch(
     initial_path: Option<&Path>,
     format: bool,
@@ -429,6 +637,7 @@ fn watch(
     clear: bool,
     args: Vec<String>,
     stdin_file: Option<PathBuf>,
+    module_options: &ModuleOptions,
 ) -> io::Result<()> {
     let (send, recv) = channel();
     let mut watcher = notify::recommended_watcher(send).unwrap();
@@ -506,6 +715,7 @@ fn watch(
                                 #[cfg(feature = "audio")]
                                 &audio_port,
                             ])
+                            .args(module_options.to_args())
                             .args(&args)
                             .stdin(stdin_file.map_or_else(Stdio::inherit, Into::into))
                             .spawn()
@@ -572,10 +782,176 @@ fn watch(
     }
 }
 
+#[derive(Debug, Clone, Copy)]
+enum TestFormat {
+    Text,
+    Json,
+    Junit,
+}
+
+impl FromStr for TestFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(TestFormat::Text),
+            "json" => Ok(TestFormat::Json),
+            "junit" => Ok(TestFormat::Junit),
+            _ => Err(format!("unknown test format `{s}`")),
+        }
+    }
+}
+
+fn write_profile(path: &Path, profile_path: &Path, profile: &Profile) {
+    let contents = if profile_path.extension().is_some_and(|ext| ext == "json") {
+        profile.to_speedscope(&path.display().to_string())
+    } else {
+        profile.to_folded()
+    };
+    if let Err(e) = fs::write(profile_path, contents) {
+        eprintln!("Failed to write profile: {e}");
+        return;
+    }
+    let total = profile.total();
+    eprintln!("Total time: {total:.2}ms");
+    for (frame, time) in profile.self_times().into_iter().take(10) {
+        let percent = if total > 0.0 {
+            time / total * 100.0
+        } else {
+            0.0
+        };
+        eprintln!("{time:>10.2}ms {percent:>5.1}%  {frame}");
+    }
+}
+
+fn vendor(path: &Path) -> UiuaResult {
+    let mut compiler = Compiler::with_backend(NativeSys);
+    compiler
+        .pre_eval_mode(PreEvalMode::Lazy)
+        .print_diagnostics(true)
+        .load_file(path)?;
+    let vendor_dir = project_dir(Some(path)).join(VENDOR_DIR);
+    let mut count = 0;
+    for (url, branch, lib_path) in compiler.git_imports() {
+        let Some(module_dir) = lib_path.parent() else {
+            continue;
+        };
+        let vendored = match git_module_path(url, branch) {
+            Ok(module_path) => vendor_dir.join(module_path),
+            Err(e) => {
+                eprintln!("{e}");
+                continue;
+            }
+        };
+        if vendored.exists() {
+            fs::remove_dir_all(&vendored)
+                .map_err(|e| UiuaErrorKind::Load(vendored.clone(), e.into()))?;
+        }
+        copy_dir(module_dir, &vendored)
+            .map_err(|e| UiuaErrorKind::Load(module_dir.into(), e.into()))?;
+        println!("Vendored {url}");
+        count += 1;
+    }
+    let s = if count == 1 { "" } else { "s" };
+    println!("Vendored {count} module{s} into {}", vendor_dir.display());
+    Ok(())
+}
+
+/// Recursively copy a directory, skipping git metadata
+fn copy_dir(from: &Path, to: &Path) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        if entry.file_name() == ".git" {
+            continue;
+        }
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+fn check_snapshots(
+    path: &Path,
+    config: &FormatConfig,
+    update: bool,
+    module_options: &ModuleOptions,
+    args: Vec<String>,
+) -> UiuaResult {
+    let input =
+        fs::read_to_string(path).map_err(|e| UiuaErrorKind::Load(path.to_path_buf(), e.into()))?;
+    let mut rt = Uiua::with_native_sys().with_file_path(path).with_args(args);
+    let mut compiler = Compiler::with_backend(NativeSys);
+    (compiler.module_root(module_options.root(Some(path))))
+        .search_paths(module_options.search_paths());
+    let formatted = format_with_runtime(&input, path, config, &mut rt, &mut compiler)?;
+    let total = formatted.output_comments.len();
+    let mismatches = formatted.output_comment_mismatches();
+    if update {
+        // Only the output comments are rewritten
+        let updated = formatted.update_output_comments(&input);
+        if updated != input {
+            fs::write(path, updated)
+                .map_err(|e| UiuaErrorKind::Format(path.to_path_buf(), e.into()))?;
+        }
+        println!("Updated {} of {total} output comments", mismatches.len());
+        return Ok(());
+    }
+    for mismatch in &mismatches {
+        println!("{}", mismatch.span.to_string().bright_white().bold());
+        for line in &mismatch.expected {
+            println!("{}", format!("- {line}").red());
+        }
+        for line in &mismatch.actual {
+            println!("{}", format!("+ {line}").green());
+        }
+        println!();
+    }
+    if mismatches.is_empty() {
+        println!("All {total} output comments match");
+    } else {
+        println!(
+            "{} of {total} output comments do not match\n\
+            Run with --update to rewrite them",
+            mismatches.len()
+        );
+        exit(1);
+    }
+    Ok(())
+}
+
+fn print_test_text(cases: &[TestCase]) {
+    let scopes: Vec<_> = (cases.iter())
+        .filter(|case| case.kind == TestCaseKind::Scope)
+        .collect();
+    for case in &scopes {
+        let status = if case.passed() { "ok" } else { "FAILED" };
+        println!("{} ... {status}", case.name);
+    }
+    let failed: Vec<_> = (cases.iter())
+        .filter(|case| case.kind != TestCaseKind::Assert && !case.passed())
+        .collect();
+    // Failures are reported the same way as when running without test options
+    for case in &failed {
+        if let Some(report) = &case.report {
+            println!("{report}");
+        }
+    }
+    if failed.is_empty() {
+        println!("No failures!");
+    } else {
+        let passed = scopes.iter().filter(|case| case.passed()).count();
+        println!("{passed} passed, {} failed", failed.len());
+    }
+}
+
 #[derive(Parser)]
 #[clap(version)]
 enum App {
-    #[clap(about = "Initialize a new main.ua file")]
+    #[clap(about = "Initialize a new main.ua file and uiua.toml manifest")]
     Init,
     #[clap(about = "Format and run a file")]
     Run {
@@ -588,8 +964,16 @@ enum App {
         formatter_options: FormatterOptions,
         #[clap(long, help = "Emit the duration of each instruction's execution")]
         time_instrs: bool,
+        #[clap(
+            long,
+            help = "Write a profile to a file, as speedscope JSON if it ends in .json \
+                    or collapsed stacks otherwise"
+        )]
+        profile: Option<PathBuf>,
         #[clap(long, help = "Run the file in a specific mode")]
         mode: Option<RunMode>,
+        #[clap(flatten)]
+        module_options: ModuleOptions,
         #[cfg(feature = "audio")]
         #[clap(flatten)]
         audio_options: AudioOptions,
@@ -601,23 +985,71 @@ enum App {
         path: Option<PathBuf>,
         #[clap(short, long, help = "The path to the output file")]
         output: Option<PathBuf>,
+        #[clap(flatten)]
+        module_options: ModuleOptions,
     },
     #[clap(about = "Evaluate an expression and print its output")]
     Eval {
         code: String,
         #[clap(long, help = "Don't colorize stack output")]
         no_color: bool,
+        #[clap(flatten)]
+        module_options: ModuleOptions,
         #[cfg(feature = "audio")]
         #[clap(flatten)]
         audio_options: AudioOptions,
         #[clap(trailing_var_arg = true, help = "Arguments to pass to the program")]
         args: Vec<String>,
     },
+    #[clap(about = "Copy the git modules a file imports into the vendor directory")]
+    Vendor { path: Option<PathBuf> },
     #[clap(about = "Format and test a file")]
     Test {
         path: Option<PathBuf>,
+        #[clap(
+            long,
+            help = "The format of the test results: text, json, or junit",
+            default_value = "text"
+        )]
+        format: TestFormat,
+        #[clap(long, help = "Only run test scopes whose names contain this pattern")]
+        filter: Option<String>,
+        #[clap(long, help = "Run test scopes in parallel")]
+        parallel: bool,
+        #[clap(
+            long,
+            help = "Write a coverage report to a file, as JSON if it ends in .json or lcov otherwise"
+        )]
+        coverage: Option<PathBuf>,
+        #[clap(
+            long,
+            help = "Check that output comments match freshly computed values"
+        )]
+        snapshot: bool,
+        #[clap(
+            long,
+            requires = "snapshot",
+            help = "Rewrite output comments that do not match"
+        )]
+        update: bool,
         #[clap(flatten)]
         formatter_options: FormatterOptions,
+        #[clap(flatten)]
+        module_options: ModuleOptions,
+        #[clap(trailing_var_arg = true, help = "Arguments to pass to the program")]
+        args: Vec<String>,
+    },
+    #[clap(about = "Step through a file in the debugger")]
+    Debug {
+        path: Option<PathBuf>,
+        #[clap(
+            short,
+            long = "break",
+            help = "Set a breakpoint on a line and run until it is reached"
+        )]
+        breakpoints: Vec<u16>,
+        #[clap(flatten)]
+        module_options: ModuleOptions,
         #[clap(trailing_var_arg = true, help = "Arguments to pass to the program")]
         args: Vec<String>,
     },
@@ -633,6 +1065,8 @@ enum App {
         clear: bool,
         #[clap(long, help = "Read stdin from file")]
         stdin_file: Option<PathBuf>,
+        #[clap(flatten)]
+        module_options: ModuleOptions,
         #[clap(trailing_var_arg = true, help = "Arguments to pass to the program")]
         args: Vec<String>,
     },
@@ -647,12 +1081,22 @@ enum App {
     #[cfg(feature = "lsp")]
     #[clap(about = "Run the Language Server")]
     Lsp,
+    #[cfg(feature = "dap")]
+    #[clap(about = "Run the Debug Adapter")]
+    Dap,
     #[clap(about = "Run the Uiua interpreter in a REPL")]
     Repl {
         #[clap(help = "A Uiua file to run before the REPL starts")]
         file: Option<PathBuf>,
+        #[clap(
+            long,
+            help = "Save the stack and bindings under a name on exit, restoring them if it already exists"
+        )]
+        session: Option<String>,
         #[clap(flatten)]
         formatter_options: FormatterOptions,
+        #[clap(flatten)]
+        module_options: ModuleOptions,
         #[cfg(feature = "audio")]
         #[clap(flatten)]
         audio_options: AudioOptions,
@@ -676,6 +1120,59 @@ enum App {
     },
 }
 
+#[derive(clap::Args, Default)]
+struct ModuleOptions {
+    #[clap(
+        long,
+        conflicts_with = "module_root",
+        help = "Load git modules from the vendor directory instead of fetching them"
+    )]
+    offline: bool,
+    #[clap(
+        long,
+        help = "Load git modules from a local directory instead of fetching them"
+    )]
+    module_root: Option<PathBuf>,
+    #[clap(
+        short = 'I',
+        long = "search-path",
+        help = "A directory to search for imports, in addition to those in UIUA_PATH"
+    )]
+    search_paths: Vec<PathBuf>,
+}
+
+impl ModuleOptions {
+    /// Get the module root for a project with the given main file
+    ///
+    /// The vendor directory is in the project's directory.
+    fn root(&self, main: Option<&Path>) -> Option<PathBuf> {
+        (self.module_root.clone())
+            .or_else(|| (self.offline).then(|| project_dir(main).join(VENDOR_DIR)))
+    }
+    /// Get the arguments that pass these options to another command
+    fn to_args(&self) -> Vec<OsString> {
+        let mut args = Vec::new();
+        if self.offline {
+            args.push("--offline".into());
+        }
+        if let Some(root) = &self.module_root {
+            args.push("--module-root".into());
+            args.push(root.into());
+        }
+        // UIUA_PATH is inherited through the environment
+        for path in &self.search_paths {
+            args.push("--search-path".into());
+            args.push(path.into());
+        }
+        args
+    }
+    fn search_paths(&self) -> Vec<PathBuf> {
+        let mut paths = self.search_paths.clone();
+        paths.extend(env_search_paths());
+        paths
+    }
+}
+
 #[derive(clap::Args)]
 struct FormatterOptions {
     #[clap(
@@ -845,8 +1342,183 @@ fn print_stack(stack: &[Value], color: bool) {
     }
 }
 
-fn repl(mut env: Uiua, mut compiler: Compiler, color: bool, config: FormatConfig) {
-    let mut line_reader = DefaultEditor::new().expect("Failed to read from Stdin");
+struct CliDebugger {
+    stepper: Mutex<Stepper>,
+    compiler: Compiler,
+}
+
+const DEBUG_HELP: &str = "\
+Commands:
+  s, step          Step to the next instruction (default)
+  n, next          Step over function calls
+  o, out           Step out of the current function
+  c, continue      Run until the next breakpoint
+  b, break <line>  Set a breakpoint
+  d, delete <line> Remove a breakpoint
+  l, list          List breakpoints
+  p, print         Show the current state again
+  e, eval <code>   Evaluate code against a copy of the stack
+  q, quit          Stop the program
+  h, help          Show this message";
+
+impl Debugger for CliDebugger {
+    fn before_instr(&self, env: &Uiua, instr: &Instr, span: Option<&CodeSpan>) -> UiuaResult {
+        let mut stepper = self.stepper.lock();
+        if !stepper.should_pause(env, span) {
+            return Ok(());
+        }
+        print_debug_state(env, instr, span);
+        let stdin = stdin();
+        let mut line = String::new();
+        loop {
+            eprint!("{} ", "(debug)".bright_black());
+            _ = stderr().flush();
+            line.clear();
+            if stdin.lock().read_line(&mut line).unwrap_or(0) == 0 {
+                return Err(env.error("Debugging stopped"));
+            }
+            let (command, rest) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+            let line_arg = (!rest.is_empty()).then(|| rest.trim().parse::<u16>());
+            match (command, line_arg) {
+                ("" | "s" | "step", _) => stepper.step_into(),
+                ("n" | "next", _) => stepper.step_over(env),
+                ("o" | "out", _) => stepper.step_out(env),
+                ("c" | "continue", _) => stepper.resume(),
+                ("b" | "break", Some(Ok(line))) => {
+                    if stepper.add_breakpoint(Breakpoint::line(line)) {
+                        eprintln!("Breakpoint set on line {line}");
+                    } else {
+                        eprintln!("A breakpoint is already set on line {line}");
+                    }
+                    continue;
+                }
+                ("d" | "delete", Some(Ok(line))) => {
+                    if !stepper.remove_breakpoint(&Breakpoint::line(line)) {
+                        eprintln!("No breakpoint on line {line}");
+                    }
+                    continue;
+                }
+                ("b" | "break" | "d" | "delete", _) => {
+                    eprintln!("Expected a line number");
+                    continue;
+                }
+                ("l" | "list", _) => {
+                    if stepper.breakpoints.is_empty() {
+                        eprintln!("No breakpoints");
+                    }
+                    for bp in &stepper.breakpoints {
+                        eprintln!("  line {}", bp.line);
+                    }
+                    continue;
+                }
+                ("p" | "print", _) => {
+                    print_debug_state(env, instr, span);
+                    continue;
+                }
+                ("e" | "eval", _) => {
+                    match env.eval_snapshot(&self.compiler, rest) {
+                        Ok(stack) => print_stack(&stack, true),
+                        Err(e) => eprintln!("{}", e.report()),
+                    }
+                    continue;
+                }
+                ("q" | "quit", _) => return Err(env.error("Debugging stopped")),
+                ("h" | "help" | "?", _) => {
+                    eprintln!("{DEBUG_HELP}");
+                    continue;
+                }
+                (command, _) => {
+                    eprintln!("Unknown command `{command}`. Type `help` for a list of commands.");
+                    continue;
+                }
+            }
+            return Ok(());
+        }
+    }
+}
+
+fn print_debug_state(env: &Uiua, instr: &Instr, span: Option<&CodeSpan>) {
+    eprintln!();
+    if let Some(span) = span {
+        eprintln!("{} {}", "at".bright_black(), span.to_string().bold());
+        let line = env.inputs().get_with(&span.src, |input| {
+            (input.lines().nth(span.start.line as usize - 1)).map(String::from)
+        });
+        if let Some(line) = line {
+            let prefix = format!("{:>4} | ", span.start.line);
+            let width = if span.start.line == span.end.line {
+                (span.end.col - span.start.col).max(1) as usize
+            } else {
+                1
+            };
+            eprintln!("{}{line}", prefix.bright_black());
+            eprintln!(
+                "{}{}",
+                " ".repeat(prefix.chars().count() + span.start.col as usize - 1),
+                "^".repeat(width).bright_cyan()
+            );
+        }
+    }
+    eprintln!("{} {instr}", "next:".bright_black());
+    eprintln!("{}", "stack:".bright_black());
+    if env.stack().is_empty() {
+        eprintln!("  (empty)");
+    }
+    for value in env.stack() {
+        for line in value.show().lines() {
+            eprintln!("  {line}");
+        }
+    }
+    for temp in [TempStack::Inline, TempStack::Under] {
+        let values = env.temp_stack(temp);
+        if values.is_empty() {
+            continue;
+        }
+        eprintln!("{}", format!("{temp} stack:").bright_black());
+        for value in values {
+            for line in value.show().lines() {
+                eprintln!("  {line}");
+            }
+        }
+    }
+    eprintln!("{}", "call stack:".bright_black());
+    for frame in env.call_stack().iter().rev() {
+        match &frame.span {
+            Some(span) => eprintln!("  {} at {span}", frame.id),
+            None => eprintln!("  {} called at {}", frame.id, frame.call_span),
+        }
+    }
+}
+
+fn repl(
+    mut env: Uiua,
+    mut compiler: Compiler,
+    mut lines: Vec<String>,
+    session: Option<String>,
+    color: bool,
+    config: FormatConfig,
+) {
+    let editor_config = rustyline::Config::builder()
+        .completion_type(CompletionType::List)
+        .build();
+    let mut line_reader: Editor<ReplHelper, DefaultHistory> =
+        Editor::with_config(editor_config).expect("Failed to read from Stdin");
+    let formatted = Arc::new(Mutex::new(None));
+    line_reader.set_helper(Some(ReplHelper {
+        compiler: compiler.clone(),
+        formatted: formatted.clone(),
+    }));
+    line_reader.bind_sequence(
+        KeyEvent::from(' '),
+        EventHandler::Conditional(Box::new(GlyphConverter {
+            config: config.clone(),
+            formatted,
+        })),
+    );
+    let history_path = uiua_data_dir().map(|dir| dir.join("history.txt"));
+    if let Some(path) = &history_path {
+        _ = line_reader.load_history(path);
+    }
     let mut repl = || -> UiuaResult<bool> {
         let mut code = match line_reader.readline("» ") {
             Ok(code) => code,
@@ -857,29 +1529,103 @@ fn repl(mut env: Uiua, mut compiler: Compiler, color: bool, config: FormatConfig
             return Ok(true);
         }
 
-        match format_str(&code, &config) {
-            Ok(formatted) => {
-                code = formatted.output;
-                _ = line_reader.add_history_entry(&code);
+        let mut load = None;
+        let mut timed = false;
+        if let Some(command) = code.trim().strip_prefix(':') {
+            _ = line_reader.add_history_entry(&code);
+            let (command, arg) = (command.split_once(char::is_whitespace))
+                .map(|(command, arg)| (command, arg.trim()))
+                .unwrap_or((command, ""));
+            match (command, arg) {
+                ("bindings", "") => print_bindings(&compiler, color),
+                ("clear", "") => drop(env.take_stack()),
+                ("sig", expr) if !expr.is_empty() => {
+                    let mut comp = compiler.clone();
+                    comp.print_diagnostics(false).load_str(expr)?;
+                    match comp.assembly().top_level_signature() {
+                        Ok(sig) => println!("{sig}"),
+                        Err(e) => eprintln!("Signature cannot be inferred: {e}"),
+                    }
+                }
+                ("time", expr) if !expr.is_empty() => timed = true,
+                ("load", path) if !path.is_empty() => load = Some(PathBuf::from(path)),
+                ("save", path) if !path.is_empty() => {
+                    let mut path = PathBuf::from(path);
+                    if path.extension().is_none() {
+                        path.set_extension("ua");
+                    }
+                    let mut text = lines.join("\n");
+                    text.push('\n');
+                    match fs::write(&path, text) {
+                        Ok(()) => println!("Saved {} lines to {}", lines.len(), path.display()),
+                        Err(e) => eprintln!("Failed to write {}: {e}", path.display()),
+                    }
+                    save_session(session.as_deref(), &env, &compiler, &lines);
+                }
+                ("help", "") => println!("{REPL_HELP}"),
+                _ => eprintln!("Invalid command {code:?}. Use :help to see the commands."),
             }
-            Err(e) => {
-                _ = line_reader.add_history_entry(&code);
-                return Err(e);
+            if !timed && load.is_none() {
+                return Ok(true);
+            }
+            if timed {
+                code = arg.into();
+            }
+        }
+
+        if load.is_none() {
+            match format_str(&code, &config) {
+                Ok(formatted) => {
+                    code = formatted.output;
+                    if !timed {
+                        _ = line_reader.add_history_entry(&code);
+                    }
+                }
+                Err(e) => {
+                    if !timed {
+                        _ = line_reader.add_history_entry(&code);
+                    }
+                    return Err(e);
+                }
             }
         }
 
-        print!("↪ ");
         let backup = compiler.clone();
-        let res = compiler.load_str(&code).map(drop);
-        println!("{}", color_code(&code, &compiler));
+        let res = if let Some(path) = &load {
+            compiler.load_file(path).map(drop)
+        } else {
+            print!("↪ ");
+            let res = compiler.load_str(&code).map(drop);
+            println!("{}", color_code(&code, &compiler));
+            res
+        };
+        let start = Instant::now();
         let res = res.and_then(|()| env.run_asm(compiler.finish()));
+        let elapsed = start.elapsed();
 
-        print_stack(&env.take_stack(), color);
+        print_stack(env.stack(), color);
+        if timed && res.is_ok() {
+            let time = format!("{elapsed:.2?}");
+            println!(
+                "{}",
+                if color {
+                    time.bright_black()
+                } else {
+                    time.normal()
+                }
+            );
+        }
         let mut asm = env.take_asm();
         match res {
             Ok(()) => {
                 asm.remove_top_level();
                 *compiler.assembly_mut() = asm;
+                if let Some(helper) = line_reader.helper_mut() {
+                    helper.compiler = compiler.clone();
+                }
+                if load.is_none() {
+                    lines.push(code);
+                }
                 Ok(true)
             }
             Err(e) => {
@@ -899,6 +1645,321 @@ fn repl(mut env: Uiua, mut compiler: Compiler, color: bool, config: FormatConfig
             }
         }
     }
+    if let Some(path) = &history_path {
+        _ = line_reader.save_history(path);
+    }
+    save_session(session.as_deref(), &env, &compiler, &lines);
+}
+
+fn save_session(session: Option<&str>, env: &Uiua, compiler: &Compiler, lines: &[String]) {
+    if let Some(name) = session {
+        let saved = ReplSession::capture(env, compiler, lines);
+        if let Err(e) = saved.save(name) {
+            eprintln!("Failed to save session {name}: {e}");
+        }
+    }
+}
+
+/// Get the directory where per-user Uiua data is stored
+fn uiua_data_dir() -> Option<PathBuf> {
+    let base = if cfg!(windows) {
+        PathBuf::from(env::var_os("APPDATA")?)
+    } else if let Some(dir) = env::var_os("XDG_DATA_HOME") {
+        PathBuf::from(dir)
+    } else {
+        PathBuf::from(env::var_os("HOME")?)
+            .join(".local")
+            .join("share")
+    };
+    let dir = base.join("uiua");
+    fs::create_dir_all(&dir).ok()?;
+    Some(dir)
+}
+
+/// The state of a REPL session that is saved between runs
+#[derive(Serialize, Deserialize)]
+struct ReplSession {
+    /// The compiled assembly in `.uasm` form
+    assembly: String,
+    /// The names in scope and the indices of their bindings
+    names: Vec<(Ident, usize)>,
+    stack: Vec<Value>,
+    lines: Vec<String>,
+}
+
+impl ReplSession {
+    fn path(name: &str) -> Result<PathBuf, String> {
+        if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+            return Err(format!("Invalid session name {name:?}"));
+        }
+        let dir = uiua_data_dir()
+            .ok_or("Unable to find a data directory")?
+            .join("sessions");
+        fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+        Ok(dir.join(format!("{name}.json")))
+    }
+    fn load(name: &str) -> Result<Option<Self>, String> {
+        let path = Self::path(name)?;
+        if !path.exists() {
+            return Ok(None);
+        }
+        let json = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        serde_json::from_str(&json).map_err(|e| e.to_string())
+    }
+    fn save(&self, name: &str) -> Result<(), String> {
+        let json = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        fs::write(Self::path(name)?, json).map_err(|e| e.to_string())
+    }
+    fn capture(env: &Uiua, compiler: &Compiler, lines: &[String]) -> Self {
+        ReplSession {
+            assembly: compiler.assembly().to_uasm(),
+            names: (compiler.binding_indices())
+                .map(|(name, index)| (name.clone(), index))
+                .collect(),
+            stack: env.stack().to_vec(),
+            lines: lines.to_vec(),
+        }
+    }
+    /// Load the session into a runtime and compiler, returning the lines entered so far
+    fn restore(self, env: &mut Uiua, compiler: &mut Compiler) -> Result<Vec<String>, String> {
+        let asm = Assembly::from_uasm(&self.assembly)?;
+        *compiler = take(compiler).with_assembly(asm);
+        for (name, index) in self.names {
+            compiler.bind_existing(name, index);
+        }
+        for val in self.stack {
+            env.push(val);
+        }
+        Ok(self.lines)
+    }
+}
+
+const REPL_HELP: &str = "\
+Commands:
+  :bindings      list the bindings and their signatures
+  :sig <expr>    show the signature of an expression
+  :time <expr>   run an expression and show how long it took
+  :clear         clear the stack
+  :load <file>   run a file and import its bindings
+  :save <file>   save the lines entered so far to a file, and the session if there is one
+  :help          show this help";
+
+const REPL_COMMANDS: &[&str] = &["bindings", "sig", "time", "clear", "load", "save", "help"];
+
+/// Split a REPL line into a command prefix and the code that follows it
+///
+/// Returns `None` if the line is a command that does not take code
+fn split_repl_line(line: &str) -> Option<(&str, &str)> {
+    let Some(command) = line.strip_prefix(':') else {
+        return Some(("", line));
+    };
+    let (name, _) = command.split_once(' ')?;
+    matches!(name, "sig" | "time").then(|| line.split_at(name.len() + 2))
+}
+
+fn print_bindings(compiler: &Compiler, color: bool) {
+    let mut bindings: Vec<_> = compiler.bindings().collect();
+    bindings.sort_by_key(|(name, _)| *name);
+    for (name, binding) in bindings {
+        let desc = match &binding.kind {
+            BindingKind::Const(_) => "constant".into(),
+            BindingKind::Func(f) => f.signature().to_string(),
+            BindingKind::Module(_) => "module".into(),
+            BindingKind::Macro => "macro".into(),
+        };
+        if color {
+            println!("{name} {}", desc.bright_black());
+        } else {
+            println!("{name} {desc}");
+        }
+    }
+}
+
+/// Highlighting, completion, and signature hints for the REPL
+///
+/// The line being edited is only parsed, never compiled,
+/// so names are resolved against the bindings of previous lines.
+struct ReplHelper {
+    /// A compiler with all the bindings made so far
+    compiler: Compiler,
+    /// Formatted code waiting to replace the code before the cursor
+    formatted: Arc<Mutex<Option<String>>>,
+}
+
+impl Helper for ReplHelper {}
+impl Validator for ReplHelper {}
+
+impl Highlighter for ReplHelper {
+    fn highlight<'l>(&self, line: &'l str, _pos: usize) -> Cow<'l, str> {
+        match split_repl_line(line) {
+            Some((prefix, code)) => format!("{prefix}{}", color_code(code, &self.compiler)).into(),
+            None => line.into(),
+        }
+    }
+    fn highlight_hint<'h>(&self, hint: &'h str) -> Cow<'h, str> {
+        hint.bright_black().to_string().into()
+    }
+    fn highlight_char(&self, _line: &str, _pos: usize, _forced: bool) -> bool {
+        true
+    }
+}
+
+/// Get the start of the word that ends at the cursor
+fn word_start(line: &str, pos: usize) -> usize {
+    line[..pos]
+        .char_indices()
+        .rev()
+        .take_while(|&(_, c)| is_ident_char(c) || c == '&')
+        .last()
+        .map_or(pos, |(i, _)| i)
+}
+
+impl Completer for ReplHelper {
+    type Candidate = Pair;
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        // A single candidate replaces the code before the cursor and moves the cursor to its end,
+        // which is how typed ASCII gets converted to glyphs
+        if let Some(formatted) = self.formatted.lock().take() {
+            let pair = Pair {
+                display: formatted.clone(),
+                replacement: formatted,
+            };
+            return Ok((0, vec![pair]));
+        }
+        if let Some(command) = line[..pos].strip_prefix(':') {
+            if !command.contains(' ') {
+                let candidates = (REPL_COMMANDS.iter())
+                    .filter(|name| name.starts_with(command))
+                    .map(|name| Pair {
+                        display: format!(":{name}"),
+                        replacement: format!("{name} "),
+                    })
+                    .collect();
+                return Ok((1, candidates));
+            }
+        }
+        let Some((code_start, _)) = split_repl_line(line) else {
+            return Ok((pos, Vec::new()));
+        };
+        let start = word_start(line, pos);
+        if start < code_start.len() {
+            return Ok((pos, Vec::new()));
+        }
+        let prefix = &line[start..pos];
+        if prefix.is_empty() {
+            return Ok((pos, Vec::new()));
+        }
+        let mut candidates: Vec<Pair> = (self.compiler.bindings())
+            .filter(|(name, _)| name.starts_with(prefix))
+            .map(|(name, _)| Pair {
+                display: name.to_string(),
+                replacement: name.to_string(),
+            })
+            .collect();
+        candidates.sort_by(|a, b| a.display.cmp(&b.display));
+        for prim in Primitive::non_deprecated() {
+            if !prim.name().starts_with(prefix) {
+                continue;
+            }
+            candidates.push(match prim.glyph() {
+                Some(glyph) => Pair {
+                    display: format!("{glyph} {}", prim.name()),
+                    replacement: glyph.into(),
+                },
+                None => Pair {
+                    display: prim.name().into(),
+                    replacement: prim.name().into(),
+                },
+            });
+        }
+        Ok((start, candidates))
+    }
+}
+
+/// A hint that is only displayed and never inserted
+struct SignatureHint(String);
+
+impl Hint for SignatureHint {
+    fn display(&self) -> &str {
+        &self.0
+    }
+    fn completion(&self) -> Option<&str> {
+        None
+    }
+}
+
+impl Hinter for ReplHelper {
+    type Hint = SignatureHint;
+    fn hint(&self, line: &str, pos: usize, _ctx: &Context) -> Option<SignatureHint> {
+        let (prefix, line) = split_repl_line(line)?;
+        let pos = pos.checked_sub(prefix.len())?;
+        let (spans, inputs) = uiua::lsp::spans_with_compiler(line, &self.compiler);
+        let words: Vec<_> = (spans.into_iter())
+            .filter(|span| matches!(span.value, SpanKind::Primitive(_) | SpanKind::Ident(_)))
+            .collect();
+        // Prefer the word after the cursor, then the one that ends at it
+        let covers = |i: usize| {
+            words.iter().find(|span| {
+                let (start, end) = (span.span.start.byte_pos, span.span.end.byte_pos);
+                (start as usize..end as usize).contains(&i)
+            })
+        };
+        let span = covers(pos).or_else(|| covers(pos.checked_sub(1)?))?.clone();
+        let hint = match span.value {
+            SpanKind::Primitive(prim) => match prim.signature() {
+                Some(sig) => format!("{} {sig}", prim.name()),
+                None => prim.name().into(),
+            },
+            SpanKind::Ident(docs) => {
+                let name = span.span.as_str(&inputs, |s| s.to_string());
+                match docs.map(|docs| docs.kind) {
+                    Some(BindingDocsKind::Function { sig, .. }) => format!("{name} {sig}"),
+                    Some(BindingDocsKind::Modifier(margs)) => {
+                        format!("{name} {margs}-function modifier")
+                    }
+                    Some(BindingDocsKind::Constant(_)) => format!("{name} constant"),
+                    Some(BindingDocsKind::Module) => format!("{name} module"),
+                    None => {
+                        let prim = Primitive::from_format_name(&name)?;
+                        match prim.signature() {
+                            Some(sig) => format!("{} {sig}", prim.name()),
+                            None => prim.name().into(),
+                        }
+                    }
+                }
+            }
+            _ => return None,
+        };
+        Some(SignatureHint(format!("  {hint}")))
+    }
+}
+
+/// Converts names and ASCII before the cursor to glyphs when a space is typed
+struct GlyphConverter {
+    config: FormatConfig,
+    formatted: Arc<Mutex<Option<String>>>,
+}
+
+impl ConditionalEventHandler for GlyphConverter {
+    fn handle(&self, _: &Event, _: RepeatCount, _: bool, ctx: &EventContext) -> Option<Cmd> {
+        let (prefix, code) = split_repl_line(&ctx.line()[..ctx.pos()])?;
+        if code.trim().is_empty() {
+            return None;
+        }
+        // Unfinished code fails to format and is left alone
+        let formatted = format_str(code, &self.config).ok()?.output;
+        let formatted = formatted.trim_end_matches('\n');
+        if formatted == code.trim_end() {
+            return None;
+        }
+        *self.formatted.lock() = Some(format!("{prefix}{formatted} "));
+        Some(Cmd::Complete)
+    }
 }
 
 fn color_code(code: &str, compiler: &Compiler) -> String {
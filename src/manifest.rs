@@ -0,0 +1,284 @@
+//! Project manifests and lockfiles
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+/// The name of a project's manifest file
+pub const MANIFEST_FILE: &str = "uiua.toml";
+/// The name of a project's lockfile
+pub const LOCK_FILE: &str = "uiua.lock";
+/// The directory that `uiua vendor` copies git modules into
+pub const VENDOR_DIR: &str = "vendor";
+
+/// A project manifest, stored in `uiua.toml`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    /// Information about the project
+    #[serde(default)]
+    pub package: Package,
+    /// The project's dependencies by name
+    #[serde(default)]
+    pub dependencies: BTreeMap<String, Dependency>,
+}
+
+/// Information about a project
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Package {
+    /// The name of the project
+    #[serde(default)]
+    pub name: String,
+    /// The version of the project
+    #[serde(default)]
+    pub version: String,
+}
+
+/// A dependency on a git repository
+///
+/// At most one of `rev`, `tag`, and `branch` should be set.
+/// If none are, the repository's default branch is used.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Dependency {
+    /// The repository url
+    pub git: String,
+    /// A commit to pin to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rev: Option<String>,
+    /// A tag to pin to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    /// A branch to follow
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+}
+
+impl Dependency {
+    /// Get the git reference the dependency is pinned to, if any
+    pub fn reference(&self) -> Option<&str> {
+        (self.rev.as_deref())
+            .or(self.tag.as_deref())
+            .or(self.branch.as_deref())
+    }
+}
+
+impl Manifest {
+    /// Create a manifest for a new project
+    pub fn new(name: impl Into<String>) -> Self {
+        Manifest {
+            package: Package {
+                name: name.into(),
+                version: "0.1.0".into(),
+            },
+            dependencies: BTreeMap::new(),
+        }
+    }
+    /// Load the manifest in a directory, if there is one
+    pub fn load(dir: &Path) -> Result<Option<Self>, String> {
+        let path = dir.join(MANIFEST_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let text = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+        toml::from_str(&text)
+            .map(Some)
+            .map_err(|e| format!("Invalid {MANIFEST_FILE}: {e}"))
+    }
+    /// Save the manifest in a directory
+    pub fn save(&self, dir: &Path) -> Result<(), String> {
+        let text = toml::to_string(self).map_err(|e| e.to_string())?;
+        let path = dir.join(MANIFEST_FILE);
+        fs::write(&path, text).map_err(|e| format!("Failed to write {}: {e}", path.display()))
+    }
+    /// Find the dependency for a git url
+    pub fn dependency_for_url(&self, url: &str) -> Option<(&str, &Dependency)> {
+        let url = normalize_git_url(url);
+        (self.dependencies.iter())
+            .find(|(_, dep)| normalize_git_url(&dep.git) == url)
+            .map(|(name, dep)| (name.as_str(), dep))
+    }
+}
+
+/// A record of the commits that dependencies resolved to, stored in `uiua.lock`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Lockfile {
+    /// The locked dependencies
+    #[serde(default, rename = "package")]
+    pub packages: Vec<LockedDependency>,
+}
+
+/// A dependency resolved to a commit
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockedDependency {
+    /// The name of the dependency
+    pub name: String,
+    /// The repository url
+    pub git: String,
+    /// The reference the dependency was pinned to when it was resolved
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reference: Option<String>,
+    /// The resolved commit hash
+    pub commit: String,
+}
+
+impl Lockfile {
+    /// Load the lockfile in a directory, or an empty one if there is none
+    pub fn load(dir: &Path) -> Result<Self, String> {
+        let path = dir.join(LOCK_FILE);
+        if !path.exists() {
+            return Ok(Lockfile::default());
+        }
+        let text = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+        toml::from_str(&text).map_err(|e| format!("Invalid {LOCK_FILE}: {e}"))
+    }
+    /// Save the lockfile in a directory
+    pub fn save(&self, dir: &Path) -> Result<(), String> {
+        let text = toml::to_string(self).map_err(|e| e.to_string())?;
+        let text = format!("# This file is generated by uiua. Do not edit it by hand.\n\n{text}");
+        let path = dir.join(LOCK_FILE);
+        fs::write(&path, text).map_err(|e| format!("Failed to write {}: {e}", path.display()))
+    }
+    /// Get the locked commit for a dependency
+    ///
+    /// Returns `None` if the dependency has not been locked
+    /// or its url or reference have changed since it was.
+    pub fn get(&self, name: &str, dep: &Dependency) -> Option<&LockedDependency> {
+        self.packages.iter().find(|locked| {
+            locked.name == name
+                && normalize_git_url(&locked.git) == normalize_git_url(&dep.git)
+                && locked.reference.as_deref() == dep.reference()
+        })
+    }
+    /// Lock a dependency to a commit, replacing any previous entry for it
+    pub fn insert(&mut self, name: &str, dep: &Dependency, commit: impl Into<String>) {
+        self.packages.retain(|locked| locked.name != name);
+        self.packages.push(LockedDependency {
+            name: name.into(),
+            git: dep.git.clone(),
+            reference: dep.reference().map(Into::into),
+            commit: commit.into(),
+        });
+        self.packages.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+}
+
+/// Normalize a git url as it appears in a `git:` import
+///
+/// Urls without a known domain are assumed to be on GitHub.
+pub fn normalize_git_url(url: &str) -> String {
+    let mut url = url.trim().trim_end_matches(".git").to_string();
+    if ![".com", ".net", ".org", ".io", ".dev"]
+        .iter()
+        .any(|s| url.contains(s))
+    {
+        if !url.starts_with('/') {
+            url = format!("/{url}");
+        }
+        url = format!("github.com{url}");
+    }
+    if !(url.starts_with("https://") || url.starts_with("http://")) {
+        url = format!("https://{url}");
+    }
+    url
+}
+
+/// Get the directory of a project from the path of its main file
+///
+/// The project's manifest, lockfile, and vendored modules are in this directory.
+/// Code that is not in a file belongs to the project in the current directory.
+pub fn project_dir(main: Option<&Path>) -> &Path {
+    main.and_then(Path::parent)
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."))
+}
+
+/// Get the path of a git module relative to a local module root
+///
+/// This is the repository's host, owner, and name, like `github.com/owner/repo`.
+/// A module imported from a branch gets its own directory, like `github.com/owner/repo@branch`.
+/// Path separators and `%` in the branch name are percent-encoded.
+pub fn git_module_path(url: &str, branch: Option<&str>) -> Result<PathBuf, String> {
+    let url = normalize_git_url(url);
+    let path = (url.split_once("://").map_or(url.as_str(), |(_, path)| path)).trim_end_matches('/');
+    let mut parts = path.split('/');
+    let (Some(host), Some(owner), Some(repo), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err(format!("Invalid git url: {url}"));
+    };
+    // Each part must be a single normal path component
+    if [host, owner, repo]
+        .iter()
+        .any(|part| ["", ".", ".."].contains(part) || part.contains('\\'))
+    {
+        return Err(format!("Invalid git url: {url}"));
+    }
+    let repo = match branch {
+        Some(branch) => {
+            let mut escaped = String::with_capacity(branch.len());
+            for c in branch.chars() {
+                match c {
+                    '%' => escaped.push_str("%25"),
+                    '/' => escaped.push_str("%2F"),
+                    '\\' => escaped.push_str("%5C"),
+                    c => escaped.push(c),
+                }
+            }
+            format!("{repo}@{escaped}")
+        }
+        None => repo.into(),
+    };
+    Ok([host, owner, &repo].iter().collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn project_dirs() {
+        assert_eq!(project_dir(None), Path::new("."));
+        assert_eq!(project_dir(Some("main.ua".as_ref())), Path::new("."));
+        assert_eq!(project_dir(Some("a/b/main.ua".as_ref())), Path::new("a/b"));
+    }
+
+    #[test]
+    fn module_paths() {
+        let path = |url, branch| git_module_path(url, branch).unwrap();
+        let expected: PathBuf = ["github.com", "owner", "repo"].iter().collect();
+        assert_eq!(path("owner/repo", None), expected);
+        assert_eq!(path("https://github.com/owner/repo.git", None), expected);
+        assert_eq!(
+            path("gitlab.com/owner/repo/", None).iter().next().unwrap(),
+            "gitlab.com"
+        );
+        let branch: PathBuf = ["github.com", "owner", "repo@feature%2Fx"].iter().collect();
+        assert_eq!(path("owner/repo", Some("feature/x")), branch);
+        // Escaped branch names do not collide
+        let branches = ["feature/x", "feature-x", "feature%2Fx", "feature\\x", "x"];
+        for (i, a) in branches.iter().enumerate() {
+            for b in &branches[i + 1..] {
+                assert_ne!(path("owner/repo", Some(a)), path("owner/repo", Some(b)));
+            }
+        }
+        assert!(git_module_path("repo", None).is_err());
+        assert!(git_module_path("a/b/c/d", None).is_err());
+        // Parts that are not normal path components are rejected
+        for url in [
+            "github.com/../repo",
+            "github.com/owner/..",
+            "github.com/./repo",
+            "https://../owner/repo",
+            "github.com//repo",
+            "https:///owner/repo",
+            "github.com/owner\\..\\../repo",
+        ] {
+            assert!(git_module_path(url, None).is_err(), "{url}");
+        }
+    }
+}
@@ -560,7 +560,12 @@ impl Primitive {
             Primitive::Max => env.dyadic_oo_00_env(Value::max)?,
             Primitive::Atan => env.dyadic_oo_00_env(Value::atan2)?,
             Primitive::Complex => env.dyadic_oo_00_env(Value::complex)?,
-            Primitive::Match => env.dyadic_rr(|a, b| a == b)?,
+            Primitive::Match => {
+                let a = env.pop(1)?;
+                let b = env.pop(2)?;
+                env.record_match(&a, &b);
+                env.push(a == b);
+            }
             Primitive::Join => env.dyadic_oo_env(|a, b, env| a.join(b, true, env))?,
             Primitive::Transpose => env.monadic_mut(Value::transpose)?,
             Primitive::Keep => env.dyadic_oo_env(Value::keep)?,
@@ -683,7 +688,9 @@ impl Primitive {
             Primitive::Assert => {
                 let msg = env.pop(1)?;
                 let cond = env.pop(2)?;
-                if !cond.as_nat(env, "").is_ok_and(|n| n == 1) {
+                let passed = cond.as_nat(env, "").is_ok_and(|n| n == 1);
+                env.record_assert(&msg, passed);
+                if !passed {
                     return Err(UiuaErrorKind::Throw(
                         msg.into(),
                         env.span().clone(),
@@ -1591,7 +1598,8 @@ mod tests {
                 if let PrimDocLine::Example(ex) = line {
                     if [
                         "&sl", "&tcpc", "&tlsc", "&ast", "&clset", "&fo", "&fc", "&fde", "&ftr",
-                        "&fld", "&fif", "&fras",
+                        "&fld", "&fif", "&fras", "&fmd", "&fmv", "&fcp", "&fsz", "&fmod", "&fwk",
+                        "&udpb", "&httpr", "&httpw", "&httpc", "&runp",
                     ]
                     .iter()
                     .any(|prim| ex.input.contains(prim))
@@ -0,0 +1,285 @@
+//! Profiling of user programs
+
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use parking_lot::Mutex;
+
+use crate::{FunctionId, ImplPrimitive, InputSrc, Instr, Primitive, Uiua};
+
+/// A frame in a profiled call stack
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ProfileFrame {
+    /// A function
+    Function(FunctionId),
+    /// A primitive
+    Primitive(Primitive),
+    #[doc(hidden)]
+    /// An implementation primitive
+    ImplPrimitive(ImplPrimitive),
+}
+
+impl fmt::Display for ProfileFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProfileFrame::Function(id) => write!(f, "{id}"),
+            ProfileFrame::Primitive(prim) => write!(f, "{prim}"),
+            ProfileFrame::ImplPrimitive(prim) => write!(f, "{prim}"),
+        }
+    }
+}
+
+/// How often the profiler samples the call stack
+#[cfg(not(target_arch = "wasm32"))]
+const SAMPLE_INTERVAL: std::time::Duration = std::time::Duration::from_millis(1);
+
+/// Samples the call stack at a regular interval
+///
+/// A timer thread increments `ticks`. When a runtime sees a new tick after an instruction,
+/// it attributes the time since its last sample to its current call stack.
+#[derive(Clone)]
+pub(crate) struct Profiler {
+    /// Milliseconds spent in each call stack, shared between threads
+    times: Arc<Mutex<HashMap<Vec<ProfileFrame>, f64>>>,
+    /// The number of sample intervals that have passed
+    ticks: Arc<AtomicU64>,
+    /// The tick at which the last sample was taken
+    last_tick: u64,
+    /// The time at which the last sample was taken
+    last_time: f64,
+}
+
+impl Profiler {
+    fn new() -> Self {
+        let ticks = Arc::new(AtomicU64::new(0));
+        // The timer stops once no profiler is left to read the ticks
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let ticks = ticks.clone();
+            std::thread::spawn(move || {
+                while Arc::strong_count(&ticks) > 1 {
+                    std::thread::sleep(SAMPLE_INTERVAL);
+                    ticks.fetch_add(1, Ordering::Relaxed);
+                }
+            });
+        }
+        Profiler {
+            times: Default::default(),
+            ticks,
+            last_tick: 0,
+            last_time: instant::now(),
+        }
+    }
+    /// Create a profiler for another thread that records into the same times
+    pub(crate) fn spawn(&self) -> Self {
+        Profiler {
+            times: self.times.clone(),
+            ticks: self.ticks.clone(),
+            last_tick: self.ticks.load(Ordering::Relaxed),
+            last_time: instant::now(),
+        }
+    }
+}
+
+/// The time spent in each call stack of a program
+#[derive(Debug, Clone, Default)]
+pub struct Profile {
+    /// Call stacks, outermost frame first, and the milliseconds spent in each
+    pub stacks: Vec<(Vec<ProfileFrame>, f64)>,
+}
+
+impl Uiua {
+    /// Sample how long is spent in each function and primitive so that a [`Profile`] can be generated
+    ///
+    /// The call stack is sampled about once per millisecond.
+    /// Sampling needs a timer thread, so on WebAssembly the profile is always empty.
+    pub fn with_profiler(mut self) -> Self {
+        self.rt.profiler = Some(Profiler::new());
+        self
+    }
+    /// Get the profile of everything run so far
+    ///
+    /// Returns `None` if profiling was not enabled with [`Uiua::with_profiler`]
+    pub fn profile(&self) -> Option<Profile> {
+        let times = self.rt.profiler.as_ref()?.times.lock();
+        let mut stacks: Vec<_> = (times.iter())
+            .map(|(stack, time)| (stack.clone(), *time))
+            .collect();
+        stacks.sort_by(|(a, _), (b, _)| a.cmp(b));
+        Some(Profile { stacks })
+    }
+    /// Take a sample if a sample interval has passed
+    ///
+    /// `instr` is the index of the instruction that just finished running.
+    /// The time since the last sample is attributed to the current call stack.
+    pub(crate) fn profile_sample(&mut self, instr: usize) {
+        let Some(profiler) = &mut self.rt.profiler else {
+            return;
+        };
+        let tick = profiler.ticks.load(Ordering::Relaxed);
+        if tick == profiler.last_tick {
+            return;
+        }
+        profiler.last_tick = tick;
+        let now = instant::now();
+        let elapsed = now - profiler.last_time;
+        profiler.last_time = now;
+        // Only the outermost main frame is kept
+        let mut stack: Vec<ProfileFrame> = (self.rt.call_stack.iter().enumerate())
+            .filter(|(i, frame)| *i == 0 || frame.id != FunctionId::Main)
+            .map(|(_, frame)| ProfileFrame::Function(frame.id.clone()))
+            .collect();
+        match self.asm.instrs[instr] {
+            Instr::Prim(prim, _) => stack.push(ProfileFrame::Primitive(prim)),
+            Instr::ImplPrim(prim, _) => stack.push(ProfileFrame::ImplPrimitive(prim)),
+            _ => {}
+        }
+        *profiler.times.lock().entry(stack).or_default() += elapsed;
+    }
+}
+
+impl Profile {
+    /// Get the total milliseconds spent
+    pub fn total(&self) -> f64 {
+        self.stacks.iter().map(|(_, time)| time).sum()
+    }
+    /// Get the milliseconds spent directly in each function or primitive, longest first
+    pub fn self_times(&self) -> Vec<(ProfileFrame, f64)> {
+        let mut times: HashMap<&ProfileFrame, f64> = HashMap::new();
+        for (stack, time) in &self.stacks {
+            if let Some(frame) = stack.last() {
+                *times.entry(frame).or_default() += time;
+            }
+        }
+        let mut times: Vec<_> = (times.into_iter())
+            .map(|(frame, time)| (frame.clone(), time))
+            .collect();
+        times.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+        times
+    }
+    /// Format the profile as collapsed stacks, with times in microseconds
+    ///
+    /// This is the format used by flamegraph tools like `inferno` and `flamegraph.pl`.
+    pub fn to_folded(&self) -> String {
+        let mut folded = String::new();
+        for (stack, time) in &self.stacks {
+            let micros = (time * 1000.0).round();
+            if micros < 1.0 {
+                continue;
+            }
+            for (i, frame) in stack.iter().enumerate() {
+                if i > 0 {
+                    folded.push(';');
+                }
+                folded.push_str(&frame.to_string().replace([';', ' '], "_"));
+            }
+            folded.push_str(&format!(" {micros}\n"));
+        }
+        folded
+    }
+    /// Format the profile as a [speedscope](https://www.speedscope.app) JSON file
+    pub fn to_speedscope(&self, name: &str) -> String {
+        let mut frames: Vec<&ProfileFrame> = Vec::new();
+        let mut indices: HashMap<&ProfileFrame, usize> = HashMap::new();
+        let mut samples = Vec::new();
+        let mut weights = Vec::new();
+        for (stack, time) in &self.stacks {
+            let sample: Vec<usize> = (stack.iter())
+                .map(|frame| {
+                    *indices.entry(frame).or_insert_with(|| {
+                        frames.push(frame);
+                        frames.len() - 1
+                    })
+                })
+                .collect();
+            samples.push(sample);
+            weights.push(*time);
+        }
+        let frames: Vec<_> = (frames.into_iter())
+            .map(|frame| match frame {
+                ProfileFrame::Function(FunctionId::Anonymous(span)) => {
+                    let file = match &span.src {
+                        InputSrc::File(path) => path.display().to_string(),
+                        _ => String::new(),
+                    };
+                    serde_json::json!({
+                        "name": frame.to_string(),
+                        "file": file,
+                        "line": span.start.line,
+                        "col": span.start.col,
+                    })
+                }
+                frame => serde_json::json!({ "name": frame.to_string() }),
+            })
+            .collect();
+        let report = serde_json::json!({
+            "$schema": "https://www.speedscope.app/file-format-schema.json",
+            "name": name,
+            "exporter": "uiua",
+            "activeProfileIndex": 0,
+            "shared": { "frames": frames },
+            "profiles": [{
+                "type": "sampled",
+                "name": name,
+                "unit": "milliseconds",
+                "startValue": 0,
+                "endValue": self.total(),
+                "samples": samples,
+                "weights": weights,
+            }],
+        });
+        serde_json::to_string_pretty(&report).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Compiler, PreEvalMode};
+
+    #[test]
+    fn samples() {
+        let mut comp = Compiler::new();
+        (comp.pre_eval_mode(PreEvalMode::Lazy))
+            .load_str("F ← /+⇡\n⍥(◌F 100000) 20")
+            .unwrap();
+        let mut env = Uiua::with_safe_sys().with_profiler();
+        let start = instant::now();
+        env.run_asm(comp.finish()).unwrap();
+        let elapsed = instant::now() - start;
+        let profile = env.profile().unwrap();
+        assert!(profile.total() > 0.0);
+        assert!(profile.total() <= elapsed);
+        let range = ProfileFrame::Primitive(Primitive::Range);
+        assert!((profile.stacks.iter()).any(|(stack, _)| {
+            stack.first() == Some(&ProfileFrame::Function(FunctionId::Main))
+                && stack.last() == Some(&range)
+        }));
+    }
+
+    #[test]
+    fn formats() {
+        let add = ProfileFrame::Primitive(Primitive::Add);
+        let main = ProfileFrame::Function(FunctionId::Main);
+        let profile = Profile {
+            stacks: vec![
+                (vec![main.clone()], 1.0),
+                (vec![main.clone(), add.clone()], 2.5),
+                (vec![main.clone(), add.clone()], 0.0001),
+            ],
+        };
+        assert_eq!(profile.total(), 3.5001);
+        assert_eq!(profile.self_times(), [(add, 2.5001), (main, 1.0)]);
+        assert_eq!(profile.to_folded(), "main 1000\nmain;+ 2500\n");
+        let json: serde_json::Value = serde_json::from_str(&profile.to_speedscope("test")).unwrap();
+        assert_eq!(json["shared"]["frames"][1]["name"], "+");
+        assert_eq!(json["profiles"][0]["samples"][1], serde_json::json!([0, 1]));
+        assert_eq!(json["profiles"][0]["weights"][1], 2.5);
+    }
+}
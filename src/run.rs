@@ -6,8 +6,8 @@ use std::{
     collections::HashMap,
     fmt,
     hash::Hash,
-    mem::{size_of, take},
-    panic::{catch_unwind, AssertUnwindSafe},
+    mem::{replace, size_of, take},
+    panic::{catch_unwind, resume_unwind, AssertUnwindSafe},
     path::{Path, PathBuf},
     str::FromStr,
     sync::{
@@ -26,12 +26,13 @@ use crate::{
     array::Array,
     boxed::Boxed,
     check::instrs_temp_signatures,
+    cowslice::{set_memory_counter, MemoryCounter, MemoryLimitExceeded},
     function::*,
     lex::Span,
     value::Value,
-    Assembly, BindingKind, CodeSpan, Compiler, Complex, Ident, Inputs, IntoSysBackend, LocalName,
-    Primitive, SafeSys, SysBackend, SysOp, TraceFrame, UiuaError, UiuaErrorKind, UiuaResult,
-    VERSION,
+    Assembly, BindingKind, CodeSpan, Compiler, Complex, Coverage, Debugger, FromValue, Ident,
+    Inputs, IntoSysBackend, LocalName, Primitive, Profiler, SafeSys, SysBackend, SysOp,
+    TestRecorder, TraceFrame, UiuaError, UiuaErrorKind, UiuaResult, VERSION,
 };
 
 /// The Uiua interpreter
@@ -54,13 +55,17 @@ pub(crate) struct Runtime {
     /// The stack height at the start of each array currently being built
     pub(crate) array_stack: Vec<usize>,
     /// The call stack
-    call_stack: Vec<StackFrame>,
+    pub(crate) call_stack: Vec<StackFrame>,
     /// The stack for tracking recursion points
     recur_stack: Vec<usize>,
     /// The fill stack
     fill_stack: Vec<Fill>,
     /// A limit on the execution duration in milliseconds
     pub(crate) execution_limit: Option<f64>,
+    /// Counts the bytes in arrays if there is a memory limit
+    memory: Option<Arc<MemoryCounter>>,
+    /// A flag that can be set from another thread to stop execution
+    interrupt: InterruptHandle,
     /// The time at which execution started
     pub(crate) execution_start: f64,
     /// Whether to print the time taken to execute each instruction
@@ -79,6 +84,14 @@ pub(crate) struct Runtime {
     pub(crate) output_comments: HashMap<usize, Vec<Vec<Value>>>,
     /// Memoized values
     pub(crate) memo: Arc<ThreadLocal<RefCell<MemoMap>>>,
+    /// The step debugger
+    pub(crate) debugger: Option<Arc<dyn Debugger>>,
+    /// Records assertions while running tests
+    pub(crate) test_recorder: Option<TestRecorder>,
+    /// Execution counts for coverage
+    pub(crate) coverage: Option<Coverage>,
+    /// Time spent in each call stack
+    pub(crate) profiler: Option<Profiler>,
 }
 
 type MemoMap = HashMap<FunctionId, HashMap<Vec<Value>, Vec<Value>>>;
@@ -109,6 +122,21 @@ pub(crate) struct StackFrame {
     spans: Vec<(usize, Option<Primitive>)>,
 }
 
+/// Information about a frame on the call stack
+#[derive(Debug, Clone)]
+pub struct CallFrame {
+    /// The function being executed
+    pub id: FunctionId,
+    /// The function's signature
+    pub sig: Signature,
+    /// The span at which the function was called
+    pub call_span: Span,
+    /// The program counter within the function
+    pub pc: usize,
+    /// The span currently being executed within the function
+    pub span: Option<Span>,
+}
+
 #[derive(Debug, Clone)]
 struct Channel {
     pub send: Sender<Value>,
@@ -173,6 +201,29 @@ impl FromStr for RunMode {
     }
 }
 
+/// A handle for interrupting a running program from another thread
+///
+/// Get one with [`Uiua::interrupt_handle`].
+/// Once interrupted, the runtime fails with [`UiuaErrorKind::Interrupted`]
+/// until [`InterruptHandle::reset`] is called.
+#[derive(Debug, Clone, Default)]
+pub struct InterruptHandle(Arc<AtomicBool>);
+
+impl InterruptHandle {
+    /// Interrupt the runtime
+    pub fn interrupt(&self) {
+        self.0.store(true, atomic::Ordering::Relaxed);
+    }
+    /// Check if the runtime has been interrupted
+    pub fn is_interrupted(&self) -> bool {
+        self.0.load(atomic::Ordering::Relaxed)
+    }
+    /// Allow the runtime to run again after being interrupted
+    pub fn reset(&self) {
+        self.0.store(false, atomic::Ordering::Relaxed);
+    }
+}
+
 #[derive(Clone)]
 struct Fill {
     value: Value,
@@ -211,10 +262,16 @@ impl Default for Runtime {
             cli_arguments: Vec::new(),
             cli_file_path: PathBuf::new(),
             execution_limit: None,
+            memory: None,
+            interrupt: InterruptHandle::default(),
             execution_start: 0.0,
             thread: ThisThread::default(),
             output_comments: HashMap::new(),
             memo: Arc::new(ThreadLocal::new()),
+            debugger: None,
+            test_recorder: None,
+            coverage: None,
+            profiler: None,
         }
     }
 }
@@ -230,10 +287,15 @@ impl Uiua {
         Self::with_backend(SafeSys::default())
     }
     /// Create a new Uiua runtime with a custom IO backend
+    ///
+    /// If the backend has a [`SysBackend::memory_limit`], the runtime uses it.
     pub fn with_backend(backend: impl IntoSysBackend) -> Self {
+        let backend = backend.into_sys_backend();
+        let memory = (backend.memory_limit()).map(|bytes| Arc::new(MemoryCounter::new(bytes)));
         Uiua {
             rt: Runtime {
-                backend: backend.into_sys_backend(),
+                backend,
+                memory,
                 ..Runtime::default()
             },
             asm: Assembly::default(),
@@ -273,6 +335,38 @@ impl Uiua {
         self.rt.execution_limit = Some(limit.as_millis() as f64);
         self
     }
+    /// Limit the number of bytes that a run can allocate for arrays
+    ///
+    /// This replaces any limit set by the backend's [`SysBackend::memory_limit`].
+    ///
+    /// Allocations are counted from the start of each run, minus the ones freed during it.
+    /// Threads spawned by the runtime share its count.
+    /// Arrays freed on a thread that is not running this runtime are not subtracted.
+    ///
+    /// Large allocations are checked against the limit before they are made,
+    /// and the rest after each instruction.
+    /// When the limit would be exceeded, the program fails with [`UiuaErrorKind::MemoryLimit`].
+    pub fn with_memory_limit(mut self, bytes: usize) -> Self {
+        self.rt.memory = Some(Arc::new(MemoryCounter::new(bytes)));
+        self
+    }
+    /// Get a handle that can be used to interrupt the runtime from another thread
+    ///
+    /// Interrupting also stops threads spawned by the runtime.
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        self.rt.interrupt.clone()
+    }
+    /// Get the number of bytes allocated for arrays since the last run started
+    ///
+    /// This is only counted if a memory limit is set with [`Uiua::with_memory_limit`].
+    pub fn memory_usage(&self) -> Option<usize> {
+        self.rt.memory.as_ref().map(|memory| memory.usage())
+    }
+    /// Set a debugger to be called before each instruction
+    pub fn with_debugger(mut self, debugger: impl Debugger) -> Self {
+        self.rt.debugger = Some(Arc::new(debugger));
+        self
+    }
     /// Set the command line arguments
     pub fn with_args(mut self, args: Vec<String>) -> Self {
         self.rt.cli_arguments = args;
@@ -338,20 +432,32 @@ impl Uiua {
         fn run_asm(env: &mut Uiua, asm: Assembly) -> UiuaResult {
             env.asm = asm;
             env.rt.execution_start = instant::now();
-            let res = env.run_top_slices();
+            if let Some(memory) = &env.rt.memory {
+                memory.reset();
+            }
+            let res = env.with_memory_counter(Uiua::run_top_slices);
             if res.is_err() {
-                env.rt = Runtime {
-                    backend: env.rt.backend.clone(),
-                    execution_limit: env.rt.execution_limit,
-                    time_instrs: env.rt.time_instrs,
-                    output_comments: env.rt.output_comments.clone(),
-                    ..Runtime::default()
-                };
+                env.reset_runtime();
             }
             res
         }
         run_asm(self, asm.into())
     }
+    /// Reset the runtime after an error, keeping its configuration
+    pub(crate) fn reset_runtime(&mut self) {
+        self.rt = Runtime {
+            backend: self.rt.backend.clone(),
+            execution_limit: self.rt.execution_limit,
+            memory: self.rt.memory.clone(),
+            interrupt: self.rt.interrupt.clone(),
+            time_instrs: self.rt.time_instrs,
+            output_comments: self.rt.output_comments.clone(),
+            debugger: self.rt.debugger.clone(),
+            coverage: self.rt.coverage.clone(),
+            profiler: self.rt.profiler.clone(),
+            ..Runtime::default()
+        };
+    }
     pub(crate) fn run_top_slices(&mut self) -> UiuaResult {
         let top_slices = take(&mut self.asm.top_slices);
         let mut res = Ok(());
@@ -368,13 +474,14 @@ impl Uiua {
         self.asm.top_slices = top_slices;
         res
     }
-    fn catching_crash<T>(
+    pub(crate) fn catching_crash<T>(
         &mut self,
         input: impl fmt::Display,
         f: impl FnOnce(&mut Self) -> T,
     ) -> UiuaResult<T> {
         match catch_unwind(AssertUnwindSafe(|| f(self))) {
             Ok(res) => Ok(res),
+            Err(payload) if payload.is::<MemoryLimitExceeded>() => Err(self.memory_limit_error()),
             Err(_) => Err(self.error(format!(
                 "\
 The interpreter has crashed!
@@ -443,6 +550,18 @@ code:
             // }
             // println!("\n    {:?}", instr);
 
+            if let Some(debugger) = self.rt.debugger.clone() {
+                let span = (instr.span_index()).and_then(|i| self.asm.spans[i].clone().code());
+                if let Err(err) = debugger.before_instr(self, instr, span.as_ref()) {
+                    let frame = self.rt.call_stack.pop().unwrap();
+                    return Err(self.trace_error(err, frame));
+                }
+            }
+
+            if let Some(coverage) = &mut self.rt.coverage {
+                coverage.hit(i);
+            }
+
             if self.rt.time_instrs {
                 formatted_instr = format!("{instr:?}");
                 self.rt.last_time = instant::now();
@@ -660,6 +779,9 @@ code:
                 }
                 Instr::NoInline => Ok(()),
             };
+            if self.rt.profiler.is_some() {
+                self.profile_sample(i);
+            }
             if self.rt.time_instrs {
                 let end_time = instant::now();
                 let padding = self.rt.call_stack.len().saturating_sub(1) * 2;
@@ -672,6 +794,11 @@ code:
                 self.rt.last_time = instant::now();
             }
             if let Err(err) = res {
+                // Memory errors take precedence over the errors they cause
+                let err = match err.kind {
+                    UiuaErrorKind::MemoryLimit(..) | UiuaErrorKind::Interrupted(..) => err,
+                    _ => self.respect_memory_limit().err().unwrap_or(err),
+                };
                 // Trace errors
                 let frame = self.rt.call_stack.pop().unwrap();
                 return Err(self.trace_error(err, frame));
@@ -683,7 +810,14 @@ code:
         Ok(())
     }
     /// Timeout if an execution limit is set and has been exceeded
+    ///
+    /// This also fails if the runtime has been interrupted.
     pub fn respect_execution_limit(&self) -> UiuaResult {
+        if self.rt.interrupt.is_interrupted() {
+            return Err(
+                UiuaErrorKind::Interrupted(self.span(), self.inputs().clone().into()).into(),
+            );
+        }
         if let Some(limit) = self.rt.execution_limit {
             if instant::now() - self.rt.execution_start > limit {
                 return Err(
@@ -691,8 +825,44 @@ code:
                 );
             }
         }
+        self.respect_memory_limit()
+    }
+    /// Fail if a memory limit is set and has been exceeded
+    fn respect_memory_limit(&self) -> UiuaResult {
+        if let Some(memory) = &self.rt.memory {
+            if memory.take_exceeded() {
+                return Err(self.memory_limit_error());
+            }
+        }
         Ok(())
     }
+    fn memory_limit_error(&self) -> UiuaError {
+        UiuaErrorKind::MemoryLimit(self.span(), self.inputs().clone().into()).into()
+    }
+    /// Run a function with allocations on the current thread counted by this runtime
+    ///
+    /// Allocations that would exceed the memory limit unwind to here if nothing else catches them.
+    fn with_memory_counter<T>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> UiuaResult<T>,
+    ) -> UiuaResult<T> {
+        let prev = set_memory_counter(self.rt.memory.clone());
+        let call_depth = self.rt.call_stack.len();
+        let res = match catch_unwind(AssertUnwindSafe(|| f(self))) {
+            Ok(res) => res,
+            Err(payload) if payload.is::<MemoryLimitExceeded>() => {
+                let err = self.memory_limit_error();
+                self.rt.call_stack.truncate(call_depth);
+                Err(err)
+            }
+            Err(payload) => {
+                set_memory_counter(prev);
+                resume_unwind(payload)
+            }
+        };
+        set_memory_counter(prev);
+        res
+    }
     pub(crate) fn with_span<T>(
         &mut self,
         span: usize,
@@ -722,8 +892,84 @@ code:
         let call_span = self.span_index();
         self.call_with_span(f, call_span)
     }
+    /// Call a function with some arguments and get its outputs
+    ///
+    /// The first argument is on the top of the function's stack,
+    /// and the first output is the one that was on the top of the stack at the end.
+    ///
+    /// The call gets its own stack, so values already on the stack are neither used nor changed.
+    ///
+    /// Unless it is made while the runtime is already running,
+    /// the call starts the execution and memory limits afresh, like [`Uiua::run_asm`].
+    ///
+    /// # Errors
+    /// Returns an error if the number of arguments does not match the function's signature
+    /// or if the function fails
+    pub fn call_with(
+        &mut self,
+        f: &Function,
+        args: impl IntoIterator<Item = Value>,
+    ) -> UiuaResult<Vec<Value>> {
+        let sig = f.signature();
+        let mut args: Vec<Value> = args.into_iter().collect();
+        if args.len() != sig.args {
+            return Err(self.error(format!(
+                "Function with signature {sig} expects {} argument{}, but {} {} given",
+                sig.args,
+                if sig.args == 1 { "" } else { "s" },
+                args.len(),
+                if args.len() == 1 { "was" } else { "were" }
+            )));
+        }
+        // The bottom frame is always there
+        if self.rt.call_stack.len() <= 1 {
+            self.rt.execution_start = instant::now();
+            if let Some(memory) = &self.rt.memory {
+                memory.reset();
+            }
+        }
+        args.reverse();
+        let stack = replace(&mut self.rt.stack, args);
+        let temp_stacks = take(&mut self.rt.temp_stacks);
+        let array_stack = take(&mut self.rt.array_stack);
+        let res = self.with_memory_counter(|env| env.call(f.clone()));
+        let mut outputs = replace(&mut self.rt.stack, stack);
+        self.rt.temp_stacks = temp_stacks;
+        self.rt.array_stack = array_stack;
+        res?;
+        outputs.reverse();
+        Ok(outputs)
+    }
+    /// Call a function with some arguments and convert its outputs
+    ///
+    /// See [`Uiua::call_with`] for how arguments are passed.
+    ///
+    /// A single output is converted on its own.
+    /// Multiple outputs are converted as a list of boxes with the first output first,
+    /// so they can be converted to a tuple.
+    ///
+    /// # Errors
+    /// Returns an error if the function has no outputs,
+    /// if the call fails, or if the outputs cannot be converted
+    pub fn call_as<T: FromValue>(
+        &mut self,
+        f: &Function,
+        args: impl IntoIterator<Item = Value>,
+    ) -> UiuaResult<T> {
+        let sig = f.signature();
+        if sig.outputs == 0 {
+            return Err(self.error(format!("Function with signature {sig} has no outputs")));
+        }
+        let outputs = self.call_with(f, args)?;
+        let output = if sig.outputs == 1 {
+            outputs.into_iter().next().unwrap_or_default()
+        } else {
+            outputs.into_iter().map(Boxed).collect()
+        };
+        T::from_value(output, self)
+    }
     #[inline]
-    fn call_slice(&mut self, slice: FuncSlice) -> UiuaResult {
+    pub(crate) fn call_slice(&mut self, slice: FuncSlice) -> UiuaResult {
         let call_span = self.span_index();
         let frame = StackFrame {
             slice,
@@ -1002,6 +1248,30 @@ code:
     pub fn stack_mut(&mut self) -> &mut [Value] {
         &mut self.rt.stack
     }
+    /// Get a reference to a temp stack
+    pub fn temp_stack(&self, stack: TempStack) -> &[Value] {
+        &self.rt.temp_stacks[stack as usize]
+    }
+    /// Get the depth of the call stack
+    pub fn call_depth(&self) -> usize {
+        self.rt.call_stack.len()
+    }
+    /// Get information about the frames on the call stack
+    ///
+    /// The outermost frame is first
+    pub fn call_stack(&self) -> Vec<CallFrame> {
+        (self.rt.call_stack.iter())
+            // Skip the empty root frame
+            .filter(|frame| !(frame.id == FunctionId::Main && frame.slice.len == 0))
+            .map(|frame| CallFrame {
+                id: frame.id.clone(),
+                sig: frame.sig,
+                call_span: self.get_span(frame.call_span),
+                pc: frame.pc,
+                span: frame.spans.last().map(|&(i, _)| self.get_span(i)),
+            })
+            .collect()
+    }
     /// Pop a function from the function stack
     pub fn pop_function(&mut self) -> UiuaResult<Function> {
         self.rt.function_stack.pop().ok_or_else(|| {
@@ -1099,12 +1369,6 @@ code:
         self.push(a);
         Ok(())
     }
-    pub(crate) fn dyadic_rr<V: Into<Value>>(&mut self, f: fn(&Value, &Value) -> V) -> UiuaResult {
-        let a = self.pop(1)?;
-        let b = self.pop(2)?;
-        self.push(f(&a, &b));
-        Ok(())
-    }
     pub(crate) fn dyadic_oo_env<V: Into<Value>>(
         &mut self,
         f: fn(Value, Value, &Self) -> UiuaResult<V>,
@@ -1395,9 +1659,15 @@ code:
                 cli_file_path: self.rt.cli_file_path.clone(),
                 backend: self.rt.backend.clone(),
                 execution_limit: self.rt.execution_limit,
+                memory: self.rt.memory.clone(),
+                interrupt: self.rt.interrupt.clone(),
                 execution_start: self.rt.execution_start,
                 output_comments: HashMap::new(),
                 memo: self.rt.memo.clone(),
+                debugger: None,
+                test_recorder: None,
+                coverage: self.rt.coverage.clone(),
+                profiler: self.rt.profiler.as_ref().map(Profiler::spawn),
                 thread,
             },
         };
@@ -1405,10 +1675,16 @@ code:
         let recv = {
             let (send, recv) = crossbeam_channel::unbounded();
             if _pool {
-                rayon::spawn(move || _ = send.send(f(&mut env).map(|_| env.take_stack())));
+                rayon::spawn(move || {
+                    let res = env.with_memory_counter(f);
+                    _ = send.send(res.map(|_| env.take_stack()))
+                });
             } else {
                 std::thread::Builder::new()
-                    .spawn(move || _ = send.send(f(&mut env).map(|_| env.take_stack())))
+                    .spawn(move || {
+                        let res = env.with_memory_counter(f);
+                        _ = send.send(res.map(|_| env.take_stack()))
+                    })
                     .map_err(|e| self.error(format!("Error spawning thread: {e}")))?;
             }
             recv
@@ -0,0 +1,702 @@
+//! Conversions between [`Value`]s and types that implement [`serde::Serialize`] and [`serde::Deserialize`]
+//!
+//! The mapping is the same as the one used for JSON by `&json`:
+//! - Numbers and booleans become scalars
+//! - Strings become character lists
+//! - Sequences and tuples become arrays, with rows boxed if they have different shapes or types
+//! - Structs and maps become map arrays
+//! - `None` and `()` become `NaN`, and a `Some` that would also be `NaN` is boxed
+//! - Unit enum variants become their names, and other variants become a map from their name to their contents
+
+use std::{fmt, mem::take};
+
+use ecow::EcoVec;
+use serde::{
+    de::{self, DeserializeOwned, IntoDeserializer, Visitor},
+    ser::{self, Serialize},
+    Deserializer,
+};
+
+use crate::{Array, ArrayFlags, Boxed, Uiua, Value};
+
+/// An error that occurs when converting between [`Value`]s and Rust types with serde
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValueSerdeError(String);
+
+impl fmt::Display for ValueSerdeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl std::error::Error for ValueSerdeError {}
+
+impl ser::Error for ValueSerdeError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        ValueSerdeError(msg.to_string())
+    }
+}
+
+impl de::Error for ValueSerdeError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        ValueSerdeError(msg.to_string())
+    }
+}
+
+type Result<T, E = ValueSerdeError> = std::result::Result<T, E>;
+
+impl Value {
+    /// Convert a Rust value to a Uiua value
+    pub fn from_serialize<T: Serialize + ?Sized>(value: &T) -> Result<Self> {
+        value.serialize(ValueSerializer)
+    }
+    /// Convert the value to a Rust value
+    pub fn deserialize_into<T: DeserializeOwned>(self) -> Result<T> {
+        T::deserialize(self)
+    }
+}
+
+thread_local! {
+    /// A runtime for building maps, which requires one for errors
+    static MAP_ENV: Uiua = Uiua::with_safe_sys();
+}
+
+/// Combine values into rows of an array, boxing them if they cannot be combined
+fn rows_value(mut rows: Vec<Value>) -> Result<Value> {
+    for row in &mut rows {
+        if row.is_map() {
+            *row = Boxed(take(row)).into();
+        }
+    }
+    if rows
+        .windows(2)
+        .all(|win| win[0].shape() == win[1].shape() && win[0].type_name() == win[1].type_name())
+    {
+        MAP_ENV
+            .with(|env| Value::from_row_values(rows, env))
+            .map_err(|e| ValueSerdeError(e.to_string()))
+    } else {
+        Ok(Array::from(rows.into_iter().map(Boxed).collect::<EcoVec<_>>()).into())
+    }
+}
+
+/// Create a map array from keys and values
+fn map_value(keys: Vec<Value>, values: Vec<Value>) -> Result<Value> {
+    let keys = if keys.iter().all(|key| key.rank() == 0 && !key.is_map()) {
+        rows_value(keys)?
+    } else {
+        Array::from(keys.into_iter().map(Boxed).collect::<EcoVec<_>>()).into()
+    };
+    let mut values = rows_value(values)?;
+    MAP_ENV
+        .with(|env| values.map(keys, env))
+        .map_err(|e| ValueSerdeError(e.to_string()))?;
+    Ok(values)
+}
+
+/// A variant name and its contents as a single-entry map
+fn variant_value(variant: &str, value: Value) -> Result<Value> {
+    map_value(vec![variant.into()], vec![value])
+}
+
+/// A [`serde::Serializer`] that produces [`Value`]s
+///
+/// [`Value::from_serialize`] is usually more convenient.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValueSerializer;
+
+impl ser::Serializer for ValueSerializer {
+    type Ok = Value;
+    type Error = ValueSerdeError;
+    type SerializeSeq = SerializeRows;
+    type SerializeTuple = SerializeRows;
+    type SerializeTupleStruct = SerializeRows;
+    type SerializeTupleVariant = SerializeRows;
+    type SerializeMap = SerializeMap;
+    type SerializeStruct = SerializeMap;
+    type SerializeStructVariant = SerializeMap;
+
+    fn serialize_bool(self, v: bool) -> Result<Value> {
+        Ok(v.into())
+    }
+    fn serialize_i8(self, v: i8) -> Result<Value> {
+        Ok((v as f64).into())
+    }
+    fn serialize_i16(self, v: i16) -> Result<Value> {
+        Ok((v as f64).into())
+    }
+    fn serialize_i32(self, v: i32) -> Result<Value> {
+        Ok((v as f64).into())
+    }
+    fn serialize_i64(self, v: i64) -> Result<Value> {
+        Ok((v as f64).into())
+    }
+    fn serialize_u8(self, v: u8) -> Result<Value> {
+        Ok((v as f64).into())
+    }
+    fn serialize_u16(self, v: u16) -> Result<Value> {
+        Ok((v as f64).into())
+    }
+    fn serialize_u32(self, v: u32) -> Result<Value> {
+        Ok((v as f64).into())
+    }
+    fn serialize_u64(self, v: u64) -> Result<Value> {
+        Ok((v as f64).into())
+    }
+    fn serialize_f32(self, v: f32) -> Result<Value> {
+        Ok((v as f64).into())
+    }
+    fn serialize_f64(self, v: f64) -> Result<Value> {
+        Ok(v.into())
+    }
+    fn serialize_char(self, v: char) -> Result<Value> {
+        Ok(v.into())
+    }
+    fn serialize_str(self, v: &str) -> Result<Value> {
+        Ok(v.into())
+    }
+    fn serialize_bytes(self, v: &[u8]) -> Result<Value> {
+        Ok(v.iter().copied().collect())
+    }
+    fn serialize_none(self) -> Result<Value> {
+        Ok(f64::NAN.into())
+    }
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Value> {
+        let value = value.serialize(self)?;
+        Ok(if value.is_nan_scalar() || value.is_scalar_box() {
+            Boxed(value).into()
+        } else {
+            value
+        })
+    }
+    fn serialize_unit(self) -> Result<Value> {
+        Ok(f64::NAN.into())
+    }
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Value> {
+        Ok(f64::NAN.into())
+    }
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Value> {
+        Ok(variant.into())
+    }
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Value> {
+        value.serialize(self)
+    }
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Value> {
+        variant_value(variant, value.serialize(self)?)
+    }
+    fn serialize_seq(self, len: Option<usize>) -> Result<SerializeRows> {
+        Ok(SerializeRows {
+            variant: None,
+            rows: Vec::with_capacity(len.unwrap_or(0)),
+        })
+    }
+    fn serialize_tuple(self, len: usize) -> Result<SerializeRows> {
+        self.serialize_seq(Some(len))
+    }
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<SerializeRows> {
+        self.serialize_seq(Some(len))
+    }
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeRows> {
+        Ok(SerializeRows {
+            variant: Some(variant),
+            rows: Vec::with_capacity(len),
+        })
+    }
+    fn serialize_map(self, len: Option<usize>) -> Result<SerializeMap> {
+        Ok(SerializeMap {
+            variant: None,
+            keys: Vec::with_capacity(len.unwrap_or(0)),
+            values: Vec::with_capacity(len.unwrap_or(0)),
+        })
+    }
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<SerializeMap> {
+        self.serialize_map(Some(len))
+    }
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeMap> {
+        Ok(SerializeMap {
+            variant: Some(variant),
+            keys: Vec::with_capacity(len),
+            values: Vec::with_capacity(len),
+        })
+    }
+}
+
+/// Serializes sequences, tuples, and tuple variants into [`Value`]s
+#[doc(hidden)]
+pub struct SerializeRows {
+    variant: Option<&'static str>,
+    rows: Vec<Value>,
+}
+
+impl SerializeRows {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.rows.push(value.serialize(ValueSerializer)?);
+        Ok(())
+    }
+    fn finish(self) -> Result<Value> {
+        let value = rows_value(self.rows)?;
+        match self.variant {
+            Some(variant) => variant_value(variant, value),
+            None => Ok(value),
+        }
+    }
+}
+
+impl ser::SerializeSeq for SerializeRows {
+    type Ok = Value;
+    type Error = ValueSerdeError;
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+    fn end(self) -> Result<Value> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTuple for SerializeRows {
+    type Ok = Value;
+    type Error = ValueSerdeError;
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+    fn end(self) -> Result<Value> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeRows {
+    type Ok = Value;
+    type Error = ValueSerdeError;
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+    fn end(self) -> Result<Value> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleVariant for SerializeRows {
+    type Ok = Value;
+    type Error = ValueSerdeError;
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+    fn end(self) -> Result<Value> {
+        self.finish()
+    }
+}
+
+/// Serializes maps, structs, and struct variants into map [`Value`]s
+#[doc(hidden)]
+pub struct SerializeMap {
+    variant: Option<&'static str>,
+    keys: Vec<Value>,
+    values: Vec<Value>,
+}
+
+impl SerializeMap {
+    fn finish(self) -> Result<Value> {
+        let value = map_value(self.keys, self.values)?;
+        match self.variant {
+            Some(variant) => variant_value(variant, value),
+            None => Ok(value),
+        }
+    }
+}
+
+impl ser::SerializeMap for SerializeMap {
+    type Ok = Value;
+    type Error = ValueSerdeError;
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<()> {
+        self.keys.push(key.serialize(ValueSerializer)?);
+        Ok(())
+    }
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.values.push(value.serialize(ValueSerializer)?);
+        Ok(())
+    }
+    fn end(self) -> Result<Value> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStruct for SerializeMap {
+    type Ok = Value;
+    type Error = ValueSerdeError;
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.keys.push(key.into());
+        self.values.push(value.serialize(ValueSerializer)?);
+        Ok(())
+    }
+    fn end(self) -> Result<Value> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStructVariant for SerializeMap {
+    type Ok = Value;
+    type Error = ValueSerdeError;
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        ser::SerializeStruct::serialize_field(self, key, value)
+    }
+    fn end(self) -> Result<Value> {
+        self.finish()
+    }
+}
+
+impl Value {
+    fn is_nan_scalar(&self) -> bool {
+        matches!(self, Value::Num(arr) if arr.rank() == 0 && arr.data[0].is_nan())
+    }
+    fn is_scalar_box(&self) -> bool {
+        matches!(self, Value::Box(arr) if arr.rank() == 0)
+    }
+    /// Get the contents of a `Some` that was boxed to keep it distinct from `None`
+    fn some_contents(self) -> Self {
+        match self {
+            Value::Box(arr)
+                if arr.rank() == 0
+                    && (arr.data[0].0.is_nan_scalar() || arr.data[0].0.is_scalar_box()) =>
+            {
+                arr.data[0].0.clone()
+            }
+            value => value,
+        }
+    }
+    fn serde_type_error(&self, expected: &str) -> ValueSerdeError {
+        ValueSerdeError(format!(
+            "Expected {expected}, but found a rank {} {} array",
+            self.rank(),
+            self.type_name()
+        ))
+    }
+    fn into_serde_string(self) -> Result<String> {
+        match self {
+            Value::Char(arr) if arr.rank() <= 1 => Ok(arr.data.iter().collect()),
+            Value::Box(arr) if arr.rank() == 0 => arr.data[0].0.clone().into_serde_string(),
+            value => Err(value.serde_type_error("a string")),
+        }
+    }
+}
+
+impl<'de> IntoDeserializer<'de, ValueSerdeError> for Value {
+    type Deserializer = Self;
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de> Deserializer<'de> for Value {
+    type Error = ValueSerdeError;
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        if self.is_map() {
+            let (keys, values): (Vec<_>, Vec<_>) = self.map_kv().into_iter().unzip();
+            return visitor.visit_map(de::value::MapDeserializer::new(
+                keys.into_iter()
+                    .map(Value::unboxed)
+                    .zip(values.into_iter().map(Value::unboxed)),
+            ));
+        }
+        match self {
+            Value::Num(arr) if arr.rank() == 0 => {
+                let n = arr.data[0];
+                if n.fract() == 0.0 && n >= 0.0 && n <= u64::MAX as f64 {
+                    visitor.visit_u64(n as u64)
+                } else if n.fract() == 0.0 && n >= i64::MIN as f64 && n <= i64::MAX as f64 {
+                    visitor.visit_i64(n as i64)
+                } else {
+                    visitor.visit_f64(n)
+                }
+            }
+            Value::Byte(arr) if arr.rank() == 0 => {
+                if arr.meta().flags.contains(ArrayFlags::BOOLEAN_LITERAL) {
+                    visitor.visit_bool(arr.data[0] != 0)
+                } else {
+                    visitor.visit_u8(arr.data[0])
+                }
+            }
+            Value::Char(arr) if arr.rank() == 0 => visitor.visit_char(arr.data[0]),
+            Value::Char(arr) if arr.rank() == 1 => visitor.visit_string(arr.data.iter().collect()),
+            Value::Box(arr) if arr.rank() == 0 => arr.data[0].0.clone().deserialize_any(visitor),
+            Value::Complex(_) => Err(ValueSerdeError(
+                "Complex numbers cannot be deserialized".into(),
+            )),
+            value => visitor.visit_seq(de::value::SeqDeserializer::new(
+                value.into_rows().map(Value::unboxed),
+            )),
+        }
+    }
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self {
+            Value::Byte(arr) if arr.rank() == 0 && arr.data[0] <= 1 => {
+                visitor.visit_bool(arr.data[0] == 1)
+            }
+            Value::Num(arr) if arr.rank() == 0 && (arr.data[0] == 0.0 || arr.data[0] == 1.0) => {
+                visitor.visit_bool(arr.data[0] == 1.0)
+            }
+            Value::Box(arr) if arr.rank() == 0 => arr.data[0].0.clone().deserialize_bool(visitor),
+            value => Err(value.serde_type_error("a boolean")),
+        }
+    }
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_string(self.into_serde_string()?)
+    }
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_string(visitor)
+    }
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self {
+            Value::Byte(arr) if arr.rank() == 1 => visitor.visit_byte_buf(arr.data.into()),
+            value => value.deserialize_any(visitor),
+        }
+    }
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_bytes(visitor)
+    }
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        if self.is_nan_scalar() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self.some_contents())
+        }
+    }
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        if self.is_nan_scalar() {
+            visitor.visit_unit()
+        } else {
+            Err(self.serde_type_error("NaN"))
+        }
+    }
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.deserialize_unit(visitor)
+    }
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        let value = self.unboxed();
+        if value.is_map() {
+            let mut kv = value.map_kv().into_iter();
+            let (Some((variant, contents)), None) = (kv.next(), kv.next()) else {
+                return Err(ValueSerdeError(
+                    "Expected an enum map to have exactly one key".into(),
+                ));
+            };
+            let variant = variant.unboxed().into_serde_string()?;
+            visitor.visit_enum(EnumDeserializer {
+                variant,
+                contents: Some(contents.unboxed()),
+            })
+        } else {
+            let variant = value.into_serde_string()?;
+            visitor.visit_enum(EnumDeserializer {
+                variant,
+                contents: None,
+            })
+        }
+    }
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_unit()
+    }
+    serde::forward_to_deserialize_any! {
+        i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char
+        seq tuple tuple_struct map struct identifier
+    }
+}
+
+/// Deserializes an enum variant from its name and contents
+struct EnumDeserializer {
+    variant: String,
+    contents: Option<Value>,
+}
+
+impl<'de> de::EnumAccess<'de> for EnumDeserializer {
+    type Error = ValueSerdeError;
+    type Variant = VariantDeserializer;
+    fn variant_seed<V: de::DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, VariantDeserializer)> {
+        let variant = seed.deserialize(self.variant.into_deserializer())?;
+        Ok((variant, VariantDeserializer(self.contents)))
+    }
+}
+
+/// Deserializes the contents of an enum variant
+struct VariantDeserializer(Option<Value>);
+
+impl<'de> de::VariantAccess<'de> for VariantDeserializer {
+    type Error = ValueSerdeError;
+    fn unit_variant(self) -> Result<()> {
+        Ok(())
+    }
+    fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value> {
+        let contents = self
+            .0
+            .ok_or_else(|| ValueSerdeError("Expected newtype variant contents".into()))?;
+        seed.deserialize(contents)
+    }
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value> {
+        let contents = self
+            .0
+            .ok_or_else(|| ValueSerdeError("Expected tuple variant contents".into()))?;
+        contents.deserialize_any(visitor)
+    }
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        let contents = self
+            .0
+            .ok_or_else(|| ValueSerdeError("Expected struct variant contents".into()))?;
+        contents.deserialize_any(visitor)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+    use crate::convert::test::check_round_trip;
+
+    fn round_trip<T>(x: T) -> Value
+    where
+        T: Serialize + DeserializeOwned + PartialEq + fmt::Debug,
+    {
+        check_round_trip(
+            &x,
+            |x| Value::from_serialize(x).unwrap(),
+            |value| value.deserialize_into().unwrap(),
+        )
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Shape {
+        Empty,
+        Circle(f64),
+        Rect(f64, f64),
+        Named { name: String, sides: u8 },
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Point {
+        x: i32,
+        y: i32,
+        label: Option<String>,
+    }
+
+    #[test]
+    fn enums() {
+        assert_eq!(round_trip(Shape::Empty), Value::from("Empty"));
+        assert!(round_trip(Shape::Circle(2.0)).is_map());
+        round_trip(Shape::Rect(1.0, 2.0));
+        round_trip(Shape::Named {
+            name: "tri".into(),
+            sides: 3,
+        });
+        round_trip(vec![Shape::Empty, Shape::Circle(1.0)]);
+    }
+
+    #[test]
+    fn maps() {
+        let map: BTreeMap<String, Vec<i32>> =
+            [("a".into(), vec![1, 2]), ("b".into(), vec![3])].into();
+        assert!(round_trip(map).is_map());
+        let map: BTreeMap<u8, bool> = [(1, true), (2, false)].into();
+        round_trip(map);
+        round_trip(Point {
+            x: 1,
+            y: -2,
+            label: Some("p".into()),
+        });
+        round_trip(vec![
+            Point {
+                x: 1,
+                y: 2,
+                label: None,
+            },
+            Point {
+                x: 3,
+                y: 4,
+                label: Some("q".into()),
+            },
+        ]);
+    }
+
+    #[test]
+    fn options() {
+        assert!(round_trip(None::<f64>).is_nan_scalar());
+        assert_eq!(round_trip(Some(1.5)), Value::from(1.5));
+        let value = Value::from_serialize(&Some(f64::NAN)).unwrap();
+        let back: Option<f64> = value.deserialize_into().unwrap();
+        assert!(back.is_some_and(f64::is_nan));
+        round_trip(Some(None::<i32>));
+        round_trip(Some(Some(None::<i32>)));
+        round_trip(vec![Some(1), None, Some(3)]);
+        round_trip(vec![Some(()), None]);
+    }
+
+    #[test]
+    fn nested_sequences() {
+        assert_eq!(
+            round_trip(vec![vec![1, 2], vec![3, 4]]).shape().dims(),
+            [2, 2]
+        );
+        assert_eq!(round_trip(vec![vec![1], vec![2, 3]]).type_name(), "box");
+        round_trip(vec![vec![vec![1.5]], vec![vec![2.5, 3.5]]]);
+        round_trip((1u8, "two".to_string(), vec![3.0, 4.0]));
+        round_trip(vec!["a".to_string(), "bc".to_string()]);
+    }
+}
@@ -19,7 +19,7 @@ use serde::*;
 
 use crate::{
     algorithm::validate_size, cowslice::cowslice, primitive::PrimDoc, Array, Boxed, FfiType,
-    Purity, Signature, Uiua, UiuaResult, Value,
+    InterruptHandle, Purity, Signature, Uiua, UiuaResult, Value,
 };
 
 /// The text of Uiua's example module
@@ -151,6 +151,8 @@ pub enum SysOpClass {
     Images,
     Gifs,
     Tcp,
+    Udp,
+    Time,
     Ffi,
     Misc,
 }
@@ -220,6 +222,41 @@ sys_op! {
     /// Using [&cl] on *all 3* handles will kill the child process.
     /// [under][&runs] calls [&cl] on all 3 streams automatically.
     (1(3), RunStream, Command, "&runs", "run command stream", Mutating),
+    /// Run a command as a child process
+    ///
+    /// Expects a command and a map of options.
+    /// The command can be either a string, a rank `2` character array, or a rank `1` array of [box] strings.
+    /// The options map can have the following keys:
+    /// - `"env"` - a map of environment variables to set for the process
+    /// - `"cwd"` - the working directory of the process
+    /// An empty list can be used instead of the map for no options.
+    ///
+    /// Returns a process handle and 3 stream handles.
+    /// The process handle can be used with [&pwait], [&pkill], [&pstat], and [&pid].
+    /// The stream handles are the same as the ones returned by [&runs].
+    /// Using [&cl] on the process handle and *all 3* stream handles will kill the child process.
+    /// ex: &pwait &runp "ls" map {"cwd"} {"/"}
+    /// ex: &pwait &runp {"sh" "-c" "exit $CODE"} map {"env"} {map {"CODE"} {"3"}}
+    (2(4), RunProcess, Command, "&runp", "run command process", Mutating),
+    /// Wait for a process to exit
+    ///
+    /// Expects a process handle from [&runp] and returns the exit code.
+    /// If the process was terminated by a signal, the exit code is `¯1`.
+    /// If the process reads from stdin, close its stdin handle first so that it does not wait forever.
+    (1, ProcessWait, Command, "&pwait", "process - wait", Mutating),
+    /// Kill a process
+    ///
+    /// Expects a process handle from [&runp].
+    (1(0), ProcessKill, Command, "&pkill", "process - kill", Mutating),
+    /// Get the status of a process without waiting for it
+    ///
+    /// Expects a process handle from [&runp].
+    /// Returns the exit code if the process has exited, or [infinity] if it is still running.
+    (1, ProcessStatus, Command, "&pstat", "process - status", Mutating),
+    /// Get the operating system's id for a process
+    ///
+    /// Expects a process handle from [&runp].
+    (1, ProcessId, Command, "&pid", "process - id", Mutating),
     /// Change the current directory
     (1(0), ChangeDirectory, Filesystem, "&cd", "change directory", Mutating),
     /// Get the contents of the clipboard
@@ -359,6 +396,48 @@ sys_op! {
     ///   : &fwa Path +@A⇡26
     ///   : &fras Path
     (2(0), FWriteAll, Filesystem, "&fwa", "file - write all", Mutating),
+    /// Create a directory
+    ///
+    /// Any missing parent directories are also created.
+    /// It is not an error if the directory already exists.
+    /// ex: &fmd "a/b/c"
+    ///   : &fe "a/b"
+    (1(0), FMakeDir, Filesystem, "&fmd", "file - make directory", Mutating),
+    /// Move or rename a file or directory
+    ///
+    /// Expects a source path and a destination path.
+    /// ex: &fwa "old.txt" "Hello!"
+    ///   : &fmv "old.txt" "new.txt"
+    ///   : &fras "new.txt"
+    ///
+    /// See also: [&fcp]
+    (2(0), FMove, Filesystem, "&fmv", "file - move", Mutating),
+    /// Copy a file
+    ///
+    /// Expects a source path and a destination path.
+    /// The destination file will be overwritten if it exists.
+    /// ex: &fcp "example.txt" "copy.txt"
+    ///   : &fras "copy.txt"
+    ///
+    /// See also: [&fmv]
+    (2(0), FCopy, Filesystem, "&fcp", "file - copy", Mutating),
+    /// Get the size of a file in bytes
+    ///
+    /// ex: &fsz "example.txt"
+    (1, FSize, Filesystem, "&fsz", "file - size"),
+    /// Get the time a file was last modified
+    ///
+    /// The time is in seconds since the Unix epoch, like [now].
+    /// This is not supported on the web.
+    (1, FModified, Filesystem, "&fmod", "file - modified time"),
+    /// List all the files in a directory and its subdirectories
+    ///
+    /// The result is a list of boxed strings.
+    /// Directories themselves are not included.
+    /// ex: &fwk "."
+    ///
+    /// See also: [&fld]
+    (1, FWalk, Filesystem, "&fwk", "file - walk directory"),
     /// Decode an image from a byte array
     ///
     /// Returns the image format as a string and a rank-`3` numeric array.
@@ -559,6 +638,118 @@ sys_op! {
     /// - The HTTP version
     /// - The `Host` header (if not defined)
     (2, HttpsWrite, Tcp, "&httpsw", "https - Make an HTTP(S) request", Mutating),
+    /// Read an HTTP request from a TCP or TLS socket
+    ///
+    /// Returns a map with the keys `"method"`, `"path"`, `"headers"`, and `"body"`.
+    /// The headers are a map from lowercase header names to values.
+    /// The body is a byte array.
+    /// ex: &httpr &tcpa &tcpl "0.0.0.0:8080"
+    ///
+    /// See also: [&httpw]
+    (1, HttpRead, Tcp, "&httpr", "http - read request", Mutating),
+    /// Write an HTTP response to a TCP or TLS socket
+    ///
+    /// Expects a map with the optional keys `"status"`, `"headers"`, and `"body"`.
+    /// The status defaults to `200` and the body can be a string or a byte array.
+    /// The `Content-Length` header is added automatically.
+    /// ex: &tcpa &tcpl "0.0.0.0:8080"
+    ///   : ⊸&httpr
+    ///   : &httpw map {"status" "body"} {200 "Hello, world!"}
+    ///
+    /// See also: [&httpr]
+    (2(0), HttpWrite, Tcp, "&httpw", "http - write response", Mutating),
+    /// Make an HTTP request
+    ///
+    /// Expects either a URL or a map with the key `"url"` and the optional keys `"method"`, `"headers"`, and `"body"`.
+    /// URLs starting with `https://` use TLS.
+    /// Returns a map with the keys `"status"`, `"headers"`, and `"body"`.
+    /// The headers are a map from lowercase header names to values.
+    /// The body is a byte array.
+    /// ex: °utf °□ get "body" &httpc "https://example.com"
+    /// ex: &httpc map {"url" "method" "body"} {"https://example.com" "POST" "Hi!"}
+    (1, HttpClient, Tcp, "&httpc", "http - client request", Mutating),
+    /// Create a UDP socket and bind it to an address
+    ///
+    /// Returns a socket handle
+    /// Use port `0` to bind to any available port.
+    /// Use [&udps] to send datagrams and [&udpr] to receive them.
+    /// [under][&udpb] calls [&cl] automatically.
+    (1, UdpBind, Udp, "&udpb", "udp - bind", Mutating),
+    /// Send a datagram to an address with a UDP socket
+    ///
+    /// Expects the data, the address to send to, and a socket handle.
+    /// The data can be a string or a byte array.
+    /// ex: &udps "hello" "127.0.0.1:8125" &udpb "0.0.0.0:0"
+    (3(0), UdpSendTo, Udp, "&udps", "udp - send to", Mutating),
+    /// Receive a datagram with a UDP socket
+    ///
+    /// Expects the maximum number of bytes to receive and a socket handle.
+    /// Returns the received bytes and the address of the sender as a string.
+    /// If the datagram is longer than the maximum, the rest of it is discarded.
+    /// ex: &udpr 1024 &udpb "0.0.0.0:8125"
+    (2(2), UdpReceiveFrom, Udp, "&udpr", "udp - receive from", Mutating),
+    /// Set the read and write timeout of a UDP socket in seconds
+    ///
+    /// If the timeout is [infinity], the socket will block indefinitely.
+    (2(0), UdpSetTimeout, Udp, "&udpst", "udp - set timeout", Mutating),
+    /// Split a timestamp into its date and time parts
+    ///
+    /// Expects a number of seconds since the Unix epoch, like the ones returned by [now].
+    /// Returns a list of the year, month, day, hour, minute, and second in UTC.
+    /// The second may have a fractional part.
+    /// ex: &dt 0
+    /// ex: &dt 1700000000.25
+    /// Works on arrays of timestamps.
+    /// ex: &dt [0 86400 1e9]
+    ///
+    /// See also: [&dtb]
+    (1, DateTime, Time, "&dt", "datetime - parts", Pure),
+    /// Build a timestamp from its date and time parts
+    ///
+    /// Expects a list of up to 6 numbers: the year, month, day, hour, minute, and second in UTC.
+    /// Missing parts default to the start of the period.
+    /// Returns a number of seconds since the Unix epoch.
+    /// ex: &dtb [2024 2 29 12 30 15]
+    /// ex: &dtb [2024 3]
+    /// Parts that are out of range carry over into the next larger part.
+    /// ex: &dt &dtb [2023 14 31]
+    ///
+    /// See also: [&dt]
+    (1, DateTimeBuild, Time, "&dtb", "datetime - build", Pure),
+    /// Format a timestamp as a string
+    ///
+    /// Expects a strftime-style pattern and a timestamp.
+    /// Common pattern specifiers are `%Y` for the year, `%m` for the month, `%d` for the day, `%H` for the hour, `%M` for the minute, and `%S` for the second.
+    /// A full list can be found [here](https://docs.rs/chrono/latest/chrono/format/strftime/index.html).
+    /// ex: &dtf "%Y-%m-%d %H:%M:%S" 1700000000
+    /// ex: &dtf "%A, %B %e" 0
+    /// Formatting an array of timestamps returns an array of boxed strings.
+    /// ex: &dtf "%F" [0 1e9 2e9]
+    ///
+    /// See also: [&dtp]
+    (2, DateTimeFormat, Time, "&dtf", "datetime - format", Pure),
+    /// Parse a string into a timestamp
+    ///
+    /// Expects a strftime-style pattern and a string.
+    /// The pattern specifiers are the same as for [&dtf].
+    /// If the pattern has no time zone, the time is assumed to be UTC.
+    /// If the pattern has no time of day, the time is midnight.
+    /// ex: &dtp "%Y-%m-%d %H:%M:%S" "2023-11-14 22:13:20"
+    /// ex: &dtp "%d/%m/%Y" "25/12/2024"
+    /// ex: &dtp "%Y-%m-%dT%H:%M:%S%z" "2024-06-01T09:00:00+0200"
+    /// Parsing an array of boxed strings returns an array of timestamps.
+    /// ex: &dtp "%F" {"2000-01-01" "2024-02-29"}
+    ///
+    /// See also: [&dtf]
+    (2, DateTimeParse, Time, "&dtp", "datetime - parse", Pure),
+    /// Offset a timestamp by a time zone
+    ///
+    /// Expects a time zone offset and a timestamp.
+    /// The offset can be a number of hours or a string like `"+05:30"`, `"-0800"`, or `"UTC+1"`.
+    /// The result can be used with [&dt] and [&dtf] to get the local date and time.
+    /// ex: &dt &dtz ¯5 1700000000
+    /// ex: &dtf "%H:%M" &dtz "+05:30" 1700000000
+    (2, DateTimeOffset, Time, "&dtz", "datetime - time zone offset", Pure),
     /// Capture an image from a webcam
     ///
     /// Takes the index of the webcam to capture from.
@@ -680,9 +871,9 @@ sys_op! {
 pub struct Handle(pub u64);
 
 impl Handle {
-    const STDIN: Self = Self(0);
-    const STDOUT: Self = Self(1);
-    const STDERR: Self = Self(2);
+    pub(crate) const STDIN: Self = Self(0);
+    pub(crate) const STDOUT: Self = Self(1);
+    pub(crate) const STDERR: Self = Self(2);
     /// The first handle that can be used by the user
     pub const FIRST_UNRESERVED: Self = Self(3);
 }
@@ -732,9 +923,11 @@ pub enum HandleKind {
     TlsListener(SocketAddr),
     TcpSocket(SocketAddr),
     TlsSocket(SocketAddr),
+    UdpSocket(SocketAddr),
     ChildStdin(String),
     ChildStdout(String),
     ChildStderr(String),
+    Process(String),
 }
 
 impl fmt::Display for HandleKind {
@@ -745,13 +938,24 @@ impl fmt::Display for HandleKind {
             Self::TlsListener(addr) => write!(f, "tls listener {}", addr),
             Self::TcpSocket(addr) => write!(f, "tcp socket {}", addr),
             Self::TlsSocket(addr) => write!(f, "tls socket {}", addr),
+            Self::UdpSocket(addr) => write!(f, "udp socket {}", addr),
             Self::ChildStdin(com) => write!(f, "stdin {com}"),
             Self::ChildStdout(com) => write!(f, "stdout {com}"),
             Self::ChildStderr(com) => write!(f, "stderr {com}"),
+            Self::Process(com) => write!(f, "process {com}"),
         }
     }
 }
 
+/// Options for spawning a child process
+#[derive(Debug, Clone, Default)]
+pub struct SpawnOptions {
+    /// Environment variables to set for the process
+    pub env: Vec<(String, String)>,
+    /// The working directory of the process
+    pub cwd: Option<String>,
+}
+
 #[cfg(feature = "image")]
 pub(crate) type WebcamImage = image::RgbImage;
 #[cfg(not(feature = "image"))]
@@ -764,6 +968,13 @@ pub trait SysBackend: Any + Send + Sync + 'static {
     fn any(&self) -> &dyn Any;
     /// Cast the backend to `&mut dyn Any`
     fn any_mut(&mut self) -> &mut dyn Any;
+    /// The maximum number of bytes that a run may allocate for arrays
+    ///
+    /// Runtimes created with this backend use this as their memory limit.
+    /// See [`Uiua::with_memory_limit`](crate::Uiua::with_memory_limit).
+    fn memory_limit(&self) -> Option<usize> {
+        None
+    }
     /// Save a color-formatted version of an error message for later printing
     fn save_error_color(&self, message: String, colored: String) {}
     /// Print a string (without a newline) to stdout
@@ -837,6 +1048,39 @@ pub trait SysBackend: Any + Send + Sync + 'static {
     fn trash(&self, path: &str) -> Result<(), String> {
         Err("Trashing files is not supported in this environment".into())
     }
+    /// Create a directory and any missing parent directories
+    fn create_dir(&self, path: &str) -> Result<(), String> {
+        Err("Creating directories is not supported in this environment".into())
+    }
+    /// Move or rename a file or directory
+    fn rename(&self, from: &str, to: &str) -> Result<(), String> {
+        Err("Moving files is not supported in this environment".into())
+    }
+    /// Copy a file
+    fn copy_file(&self, from: &str, to: &str) -> Result<(), String> {
+        let contents = self.file_read_all(from.as_ref())?;
+        self.file_write_all(to.as_ref(), &contents)
+    }
+    /// Get the size of a file in bytes
+    fn file_size(&self, path: &str) -> Result<u64, String> {
+        Err("Getting file sizes is not supported in this environment".into())
+    }
+    /// Get the time a file was last modified in seconds since the Unix epoch
+    fn modified_time(&self, path: &str) -> Result<f64, String> {
+        Err("Getting file modification times is not supported in this environment".into())
+    }
+    /// List all the files in a directory and its subdirectories
+    fn walk_dir(&self, path: &str) -> Result<Vec<String>, String> {
+        let mut files = Vec::new();
+        for child in self.list_dir(path)? {
+            if self.is_file(&child)? {
+                files.push(child);
+            } else {
+                files.extend(self.walk_dir(&child)?);
+            }
+        }
+        Ok(files)
+    }
     /// Read at most `count` bytes from a stream
     fn read(&self, handle: Handle, count: usize) -> Result<Vec<u8>, String> {
         Err("Reading from streams is not supported in this environment".into())
@@ -963,6 +1207,34 @@ pub trait SysBackend: Any + Send + Sync + 'static {
     ) -> Result<(), String> {
         Err("TCP sockets are not supported in this environment".into())
     }
+    /// Create a UDP socket and bind it to an address
+    fn udp_bind(&self, addr: &str) -> Result<Handle, String> {
+        Err("UDP sockets are not supported in this environment".into())
+    }
+    /// Get the local address of a UDP socket
+    fn udp_addr(&self, handle: Handle) -> Result<SocketAddr, String> {
+        Err("UDP sockets are not supported in this environment".into())
+    }
+    /// Send a datagram to an address with a UDP socket
+    fn udp_send_to(&self, handle: Handle, data: &[u8], addr: &str) -> Result<(), String> {
+        Err("UDP sockets are not supported in this environment".into())
+    }
+    /// Receive a datagram of at most `max_len` bytes with a UDP socket
+    ///
+    /// Returns the received bytes and the address of the sender.
+    /// Should stop waiting with an error once `interrupt` is interrupted.
+    fn udp_recv_from(
+        &self,
+        handle: Handle,
+        max_len: usize,
+        interrupt: &InterruptHandle,
+    ) -> Result<(Vec<u8>, SocketAddr), String> {
+        Err("UDP sockets are not supported in this environment".into())
+    }
+    /// Set the read and write timeout of a UDP socket
+    fn udp_set_timeout(&self, handle: Handle, timeout: Option<Duration>) -> Result<(), String> {
+        Err("UDP sockets are not supported in this environment".into())
+    }
     /// Close a stream
     fn close(&self, handle: Handle) -> Result<(), String> {
         Ok(())
@@ -987,10 +1259,47 @@ pub trait SysBackend: Any + Send + Sync + 'static {
     fn run_command_stream(&self, command: &str, args: &[&str]) -> Result<[Handle; 3], String> {
         Err("Running streamed commands is not supported in this environment".into())
     }
+    /// Spawn a child process
+    ///
+    /// Should return a process handle followed by stdin, stdout, and stderr stream handles
+    fn spawn_process(
+        &self,
+        command: &str,
+        args: &[&str],
+        options: &SpawnOptions,
+    ) -> Result<[Handle; 4], String> {
+        Err("Spawning processes is not supported in this environment".into())
+    }
+    /// Wait for a child process to exit and return its exit code
+    ///
+    /// Should stop waiting with an error once `interrupt` is interrupted.
+    fn wait_process(&self, handle: Handle, interrupt: &InterruptHandle) -> Result<i32, String> {
+        Err("Spawning processes is not supported in this environment".into())
+    }
+    /// Kill a child process
+    fn kill_process(&self, handle: Handle) -> Result<(), String> {
+        Err("Spawning processes is not supported in this environment".into())
+    }
+    /// Get the exit code of a child process, or `None` if it is still running
+    fn process_status(&self, handle: Handle) -> Result<Option<i32>, String> {
+        Err("Spawning processes is not supported in this environment".into())
+    }
+    /// Get the operating system's id for a child process
+    fn process_id(&self, handle: Handle) -> Result<u32, String> {
+        Err("Spawning processes is not supported in this environment".into())
+    }
     /// Change the current directory
     fn change_directory(&self, path: &str) -> Result<(), String> {
         Err("Changing directories is not supported in this environment".into())
     }
+    /// Get the current directory
+    fn current_directory(&self) -> Result<PathBuf, String> {
+        Err("Getting the current directory is not supported in this environment".into())
+    }
+    /// Get the target of a symbolic link, or `None` if the path is not one
+    fn read_link(&self, path: &Path) -> Option<PathBuf> {
+        None
+    }
     /// Make an HTTPS request on a TCP socket
     fn https_get(&self, request: &str, handle: Handle) -> Result<String, String> {
         Err("Making HTTPS requests is not supported in this environment".into())
@@ -1024,6 +1333,20 @@ pub trait SysBackend: Any + Send + Sync + 'static {
     fn load_git_module(&self, url: &str, branch: Option<&str>) -> Result<PathBuf, String> {
         Err("Loading git modules is not supported in this environment".into())
     }
+    /// Load a git repo as a module for the project in a directory
+    ///
+    /// `project` is the directory of the file being compiled.
+    /// A `uiua.toml` manifest there may pin the module to a specific commit.
+    ///
+    /// By default, this ignores the project and calls [`SysBackend::load_git_module`]
+    fn load_project_git_module(
+        &self,
+        url: &str,
+        branch: Option<&str>,
+        project: &Path,
+    ) -> Result<PathBuf, String> {
+        self.load_git_module(url, branch)
+    }
 }
 
 impl fmt::Debug for dyn SysBackend {
@@ -1447,6 +1770,49 @@ impl SysOp {
                 let is_file = env.rt.backend.is_file(&path).map_err(|e| env.error(e))?;
                 env.push(is_file);
             }
+            SysOp::FMakeDir => {
+                let path = env.pop(1)?.as_string(env, "Path must be a string")?;
+                env.rt.backend.create_dir(&path).map_err(|e| env.error(e))?;
+            }
+            SysOp::FMove => {
+                let from = env.pop(1)?.as_string(env, "Source path must be a string")?;
+                let to = env
+                    .pop(2)?
+                    .as_string(env, "Destination path must be a string")?;
+                env.rt
+                    .backend
+                    .rename(&from, &to)
+                    .map_err(|e| env.error(e))?;
+            }
+            SysOp::FCopy => {
+                let from = env.pop(1)?.as_string(env, "Source path must be a string")?;
+                let to = env
+                    .pop(2)?
+                    .as_string(env, "Destination path must be a string")?;
+                env.rt
+                    .backend
+                    .copy_file(&from, &to)
+                    .map_err(|e| env.error(e))?;
+            }
+            SysOp::FSize => {
+                let path = env.pop(1)?.as_string(env, "Path must be a string")?;
+                let size = env.rt.backend.file_size(&path).map_err(|e| env.error(e))?;
+                env.push(size as f64);
+            }
+            SysOp::FModified => {
+                let path = env.pop(1)?.as_string(env, "Path must be a string")?;
+                let time = env
+                    .rt
+                    .backend
+                    .modified_time(&path)
+                    .map_err(|e| env.error(e))?;
+                env.push(time);
+            }
+            SysOp::FWalk => {
+                let path = env.pop(1)?.as_string(env, "Path must be a string")?;
+                let paths = env.rt.backend.walk_dir(&path).map_err(|e| env.error(e))?;
+                env.push(Array::<Boxed>::from_iter(paths));
+            }
             SysOp::Invoke => {
                 let path = env.pop(1)?.as_string(env, "Invoke path must be a string")?;
                 env.rt.backend.invoke(&path).map_err(|e| env.error(e))?;
@@ -1681,7 +2047,13 @@ impl SysOp {
                     let max = limit - env.rt.execution_start;
                     seconds = seconds.min(max);
                 }
-                env.rt.backend.sleep(seconds).map_err(|e| env.error(e))?;
+                // Sleep in short steps so that interrupts are not delayed
+                while seconds > 0.0 {
+                    let step = seconds.min(0.05);
+                    env.rt.backend.sleep(step).map_err(|e| env.error(e))?;
+                    seconds -= step;
+                    env.respect_execution_limit()?;
+                }
             }
             SysOp::TcpListen => {
                 let addr = env.pop(1)?.as_string(env, "Address must be a string")?;
@@ -1771,6 +2143,135 @@ impl SysOp {
                     .tcp_set_write_timeout(handle, timeout)
                     .map_err(|e| env.error(e))?;
             }
+            SysOp::UdpBind => {
+                let addr = env.pop(1)?.as_string(env, "Address must be a string")?;
+                let handle = env.rt.backend.udp_bind(&addr).map_err(|e| env.error(e))?;
+                let sock_addr = env.rt.backend.udp_addr(handle).map_err(|e| env.error(e))?;
+                let handle = handle.value(HandleKind::UdpSocket(sock_addr));
+                env.push(handle);
+            }
+            SysOp::UdpSendTo => {
+                let data = env.pop(1)?;
+                let addr = env.pop(2)?.as_string(env, "Address must be a string")?;
+                let handle = env.pop(3)?.as_handle(env, "")?;
+                let bytes: Vec<u8> = match data {
+                    Value::Num(arr) => arr.data.iter().map(|&x| x as u8).collect(),
+                    Value::Byte(arr) => arr.data.into(),
+                    Value::Complex(_) => return Err(env.error("Cannot send complex array")),
+                    Value::Char(arr) => arr.data.iter().collect::<String>().into(),
+                    Value::Box(_) => return Err(env.error("Cannot send box array")),
+                };
+                (env.rt.backend)
+                    .udp_send_to(handle, &bytes, &addr)
+                    .map_err(|e| env.error(e))?;
+            }
+            SysOp::UdpReceiveFrom => {
+                let max_len = env
+                    .pop(1)?
+                    .as_nat(env, "Maximum length must be a natural number")?;
+                validate_size::<u8>([max_len], env)?;
+                let handle = env.pop(2)?.as_handle(env, "")?;
+                let res = (env.rt.backend).udp_recv_from(handle, max_len, &env.interrupt_handle());
+                env.respect_execution_limit()?;
+                let (bytes, addr) = res.map_err(|e| env.error(e))?;
+                env.push(addr.to_string());
+                env.push(Array::<u8>::from_iter(bytes));
+            }
+            SysOp::UdpSetTimeout => {
+                let timeout = env.pop(1)?.as_num(env, "Timeout must be a number")?.abs();
+                let timeout = if timeout.is_infinite() {
+                    None
+                } else {
+                    Some(Duration::from_secs_f64(timeout))
+                };
+                let handle = env.pop(2)?.as_handle(env, "")?;
+                (env.rt.backend)
+                    .udp_set_timeout(handle, timeout)
+                    .map_err(|e| env.error(e))?;
+            }
+            SysOp::DateTime => {
+                #[cfg(feature = "chrono")]
+                {
+                    let time = env.pop(1)?;
+                    let parts = crate::sys_time::to_parts(&time, env)?;
+                    env.push(parts);
+                }
+                #[cfg(not(feature = "chrono"))]
+                return Err(env.error("Dates and times are not supported in this environment"));
+            }
+            SysOp::DateTimeBuild => {
+                #[cfg(feature = "chrono")]
+                {
+                    let parts = env.pop(1)?;
+                    let time = crate::sys_time::from_parts(&parts, env)?;
+                    env.push(time);
+                }
+                #[cfg(not(feature = "chrono"))]
+                return Err(env.error("Dates and times are not supported in this environment"));
+            }
+            SysOp::DateTimeFormat => {
+                #[cfg(feature = "chrono")]
+                {
+                    let pattern = env.pop(1)?.as_string(env, "Pattern must be a string")?;
+                    let time = env.pop(2)?;
+                    let formatted = crate::sys_time::format(&pattern, &time, env)?;
+                    env.push(formatted);
+                }
+                #[cfg(not(feature = "chrono"))]
+                return Err(env.error("Dates and times are not supported in this environment"));
+            }
+            SysOp::DateTimeParse => {
+                #[cfg(feature = "chrono")]
+                {
+                    let pattern = env.pop(1)?.as_string(env, "Pattern must be a string")?;
+                    let date = env.pop(2)?;
+                    let time = crate::sys_time::parse(&pattern, &date, env)?;
+                    env.push(time);
+                }
+                #[cfg(not(feature = "chrono"))]
+                return Err(env.error("Dates and times are not supported in this environment"));
+            }
+            SysOp::DateTimeOffset => {
+                #[cfg(feature = "chrono")]
+                {
+                    let offset = env.pop(1)?;
+                    let time = env.pop(2)?;
+                    let time = crate::sys_time::offset(&offset, &time, env)?;
+                    env.push(time);
+                }
+                #[cfg(not(feature = "chrono"))]
+                return Err(env.error("Dates and times are not supported in this environment"));
+            }
+            SysOp::HttpRead => {
+                #[cfg(feature = "http")]
+                {
+                    let handle = env.pop(1)?.as_handle(env, "")?;
+                    let request = crate::sys_http::read_request(handle, env)?;
+                    env.push(request);
+                }
+                #[cfg(not(feature = "http"))]
+                return Err(env.error("HTTP is not supported in this environment"));
+            }
+            SysOp::HttpWrite => {
+                #[cfg(feature = "http")]
+                {
+                    let response = env.pop(1)?;
+                    let handle = env.pop(2)?.as_handle(env, "")?;
+                    crate::sys_http::write_response(handle, response, env)?;
+                }
+                #[cfg(not(feature = "http"))]
+                return Err(env.error("HTTP is not supported in this environment"));
+            }
+            SysOp::HttpClient => {
+                #[cfg(feature = "http")]
+                {
+                    let request = env.pop(1)?;
+                    let response = crate::sys_http::request(request, env)?;
+                    env.push(response);
+                }
+                #[cfg(not(feature = "http"))]
+                return Err(env.error("HTTP is not supported in this environment"));
+            }
             SysOp::HttpsWrite => {
                 let http = env
                     .pop(1)?
@@ -1821,6 +2322,53 @@ impl SysOp {
                     env.push(handle.value(kind(command.clone())));
                 }
             }
+            SysOp::RunProcess => {
+                let (command, args) = value_to_command(&env.pop(1)?, env)?;
+                let args: Vec<_> = args.iter().map(|s| s.as_str()).collect();
+                let options = value_to_spawn_options(env.pop(2)?, env)?;
+                let handles = (env.rt.backend)
+                    .spawn_process(&command, &args, &options)
+                    .map_err(|e| env.error(e))?;
+                for (handle, kind) in handles
+                    .into_iter()
+                    .zip([
+                        HandleKind::Process,
+                        HandleKind::ChildStdin,
+                        HandleKind::ChildStdout,
+                        HandleKind::ChildStderr,
+                    ])
+                    .rev()
+                {
+                    env.push(handle.value(kind(command.clone())));
+                }
+            }
+            SysOp::ProcessWait => {
+                let handle = env.pop(1)?.as_handle(env, "")?;
+                let res = (env.rt.backend).wait_process(handle, &env.interrupt_handle());
+                env.respect_execution_limit()?;
+                let code = res.map_err(|e| env.error(e))?;
+                env.push(code);
+            }
+            SysOp::ProcessKill => {
+                let handle = env.pop(1)?.as_handle(env, "")?;
+                (env.rt.backend)
+                    .kill_process(handle)
+                    .map_err(|e| env.error(e))?;
+            }
+            SysOp::ProcessStatus => {
+                let handle = env.pop(1)?.as_handle(env, "")?;
+                let status = (env.rt.backend)
+                    .process_status(handle)
+                    .map_err(|e| env.error(e))?;
+                env.push(status.map_or(f64::INFINITY, |code| code as f64));
+            }
+            SysOp::ProcessId => {
+                let handle = env.pop(1)?.as_handle(env, "")?;
+                let id = (env.rt.backend)
+                    .process_id(handle)
+                    .map_err(|e| env.error(e))?;
+                env.push(id as f64);
+            }
             SysOp::ChangeDirectory => {
                 let path = env.pop(1)?.as_string(env, "Path must be a string")?;
                 (env.rt.backend)
@@ -1912,6 +2460,38 @@ impl SysOp {
     }
 }
 
+fn value_to_spawn_options(value: Value, env: &Uiua) -> UiuaResult<SpawnOptions> {
+    let mut options = SpawnOptions::default();
+    if !value.is_map() {
+        if value.row_count() == 0 {
+            return Ok(options);
+        }
+        return Err(env.error("Process options must be a map"));
+    }
+    for (key, value) in value.map_kv() {
+        let key = key.as_string(env, "Process option names must be strings")?;
+        let value = value.unpacked();
+        match key.as_str() {
+            "env" => {
+                if !value.is_map() {
+                    return Err(env.error("Process environment must be a map"));
+                }
+                for (name, value) in value.map_kv() {
+                    let name = name.as_string(env, "Environment variable names must be strings")?;
+                    let value = (value.unpacked())
+                        .as_string(env, "Environment variable values must be strings")?;
+                    options.env.push((name, value));
+                }
+            }
+            "cwd" => {
+                options.cwd = Some(value.as_string(env, "Working directory must be a string")?)
+            }
+            key => return Err(env.error(format!("Unknown process option {key:?}"))),
+        }
+    }
+    Ok(options)
+}
+
 fn value_to_command(value: &Value, env: &Uiua) -> UiuaResult<(String, Vec<String>)> {
     let mut strings = Vec::new();
     match value {
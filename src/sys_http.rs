@@ -0,0 +1,510 @@
+//! HTTP requests and responses on top of the stream handles of a [`SysBackend`]
+//!
+//! Requests and responses are represented as maps with boxed values.
+
+use ecow::EcoVec;
+
+use crate::{Array, Boxed, Handle, SysBackend, Uiua, UiuaResult, Value};
+
+/// Read an HTTP request from a stream
+///
+/// Returns a map with `method`, `path`, `headers`, and `body` keys
+pub(crate) fn read_request(handle: Handle, env: &Uiua) -> UiuaResult<Value> {
+    let backend = &*env.rt.backend;
+    let head =
+        read_line(backend, handle, b"\r\n\r\n", "HTTP request head").map_err(|e| env.error(e))?;
+    if head.is_empty() {
+        return Err(env.error("Connection closed before a request was received"));
+    }
+    let mut headers = [httparse::EMPTY_HEADER; 64];
+    let mut req = httparse::Request::new(&mut headers);
+    match req.parse(&head) {
+        Ok(httparse::Status::Complete(_)) => {}
+        Ok(httparse::Status::Partial) => return Err(env.error("Incomplete HTTP request")),
+        Err(e) => return Err(env.error(format!("Failed to parse HTTP request: {e}"))),
+    }
+    let method = req.method.unwrap_or_default().to_string();
+    let path = req.path.unwrap_or_default().to_string();
+    let headers = collect_headers(req.headers);
+    let body = read_body(backend, handle, &headers, false).map_err(|e| env.error(e))?;
+    map_value(
+        [
+            ("method", method.into()),
+            ("path", path.into()),
+            ("headers", headers_value(headers, env)?),
+            ("body", Array::<u8>::from_iter(body).into()),
+        ],
+        env,
+    )
+}
+
+/// Write an HTTP response to a stream
+///
+/// The response is a map with optional `status`, `headers`, and `body` keys.
+pub(crate) fn write_response(handle: Handle, response: Value, env: &Uiua) -> UiuaResult {
+    let status = match field(&response, "status", "HTTP response", env)? {
+        Some(status) => status.as_nat(env, "HTTP status must be a natural number")?,
+        None => 200,
+    };
+    if !(100..1000).contains(&status) {
+        return Err(env.error(format!("{status} is not a valid HTTP status")));
+    }
+    let headers = match field(&response, "headers", "HTTP response", env)? {
+        Some(headers) => headers_from_value(headers, env)?,
+        None => Vec::new(),
+    };
+    let body = match field(&response, "body", "HTTP response", env)? {
+        Some(body) => body.into_bytes(env, "HTTP body must be a string or byte array")?,
+        None => Vec::new(),
+    };
+    let mut bytes = format!("HTTP/1.1 {status} {}\r\n", reason_phrase(status)).into_bytes();
+    write_headers(&mut bytes, &headers, body.len(), None);
+    bytes.extend(body);
+    (env.rt.backend.write(handle, &bytes)).map_err(|e| env.error(e))
+}
+
+/// Make an HTTP request
+///
+/// The request is either a URL or a map with a `url` key and optional
+/// `method`, `headers`, and `body` keys.
+///
+/// Returns a map with `status`, `headers`, and `body` keys
+pub(crate) fn request(request: Value, env: &Uiua) -> UiuaResult<Value> {
+    let (url, method, headers, body) = if request.is_map() {
+        let url = field(&request, "url", "HTTP request", env)?
+            .ok_or_else(|| env.error("HTTP request must have a url"))?
+            .as_string(env, "URL must be a string")?;
+        let method = match field(&request, "method", "HTTP request", env)? {
+            Some(method) => method.as_string(env, "HTTP method must be a string")?,
+            None => "GET".into(),
+        };
+        let headers = match field(&request, "headers", "HTTP request", env)? {
+            Some(headers) => headers_from_value(headers, env)?,
+            None => Vec::new(),
+        };
+        let body = match field(&request, "body", "HTTP request", env)? {
+            Some(body) => body.into_bytes(env, "HTTP body must be a string or byte array")?,
+            None => Vec::new(),
+        };
+        (url, method, headers, body)
+    } else {
+        let url = request.as_string(env, "HTTP request must be a URL or a map")?;
+        (url, "GET".into(), Vec::new(), Vec::new())
+    };
+    let url = Url::parse(&url).map_err(|e| env.error(e))?;
+    if method.is_empty() || !method.bytes().all(|b| b.is_ascii_alphabetic() || b == b'-') {
+        return Err(env.error(format!("Invalid HTTP method {method:?}")));
+    }
+
+    let mut bytes = format!("{method} {} HTTP/1.1\r\n", url.path).into_bytes();
+    write_headers(&mut bytes, &headers, body.len(), Some(&url.authority));
+    bytes.extend(body);
+
+    let backend = &*env.rt.backend;
+    let addr = format!("{}:{}", url.host, url.port);
+    let handle = if url.tls {
+        backend.tls_connect(&addr)
+    } else {
+        backend.tcp_connect(&addr)
+    }
+    .map_err(|e| env.error(e))?;
+    let res = backend
+        .write(handle, &bytes)
+        .and_then(|_| read_response(backend, handle, &method));
+    _ = backend.close(handle);
+    let (status, headers, body) = res.map_err(|e| env.error(e))?;
+    map_value(
+        [
+            ("status", (status as f64).into()),
+            ("headers", headers_value(headers, env)?),
+            ("body", Array::<u8>::from_iter(body).into()),
+        ],
+        env,
+    )
+}
+
+type Headers = Vec<(String, String)>;
+
+fn read_response(
+    backend: &dyn SysBackend,
+    handle: Handle,
+    method: &str,
+) -> Result<(u16, Headers, Vec<u8>), String> {
+    loop {
+        let head = read_line(backend, handle, b"\r\n\r\n", "HTTP response head")?;
+        if head.is_empty() {
+            return Err("Connection closed before a response was received".into());
+        }
+        let mut headers = [httparse::EMPTY_HEADER; 64];
+        let mut res = httparse::Response::new(&mut headers);
+        match res.parse(&head) {
+            Ok(httparse::Status::Complete(_)) => {}
+            Ok(httparse::Status::Partial) => return Err("Incomplete HTTP response".into()),
+            Err(e) => return Err(format!("Failed to parse HTTP response: {e}")),
+        }
+        let status = res.code.unwrap_or_default();
+        // Informational responses are followed by the real one
+        if (100..200).contains(&status) {
+            continue;
+        }
+        let headers = collect_headers(res.headers);
+        let body = if method.eq_ignore_ascii_case("HEAD") || status == 204 || status == 304 {
+            Vec::new()
+        } else {
+            read_body(backend, handle, &headers, true)?
+        };
+        return Ok((status, headers, body));
+    }
+}
+
+/// Lowercase header names and join repeated headers
+fn collect_headers(headers: &[httparse::Header]) -> Headers {
+    let mut collected: Headers = Vec::new();
+    for header in headers {
+        let name = header.name.to_ascii_lowercase();
+        let value = String::from_utf8_lossy(header.value).into_owned();
+        if let Some((_, existing)) = collected.iter_mut().find(|(n, _)| *n == name) {
+            existing.push_str(", ");
+            existing.push_str(&value);
+        } else {
+            collected.push((name, value));
+        }
+    }
+    collected
+}
+
+fn header<'a>(headers: &'a Headers, name: &str) -> Option<&'a str> {
+    (headers.iter())
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(_, v)| v.as_str())
+}
+
+fn write_headers(bytes: &mut Vec<u8>, headers: &Headers, body_len: usize, host: Option<&str>) {
+    if let Some(host) = host {
+        if header(headers, "host").is_none() {
+            bytes.extend(format!("Host: {host}\r\n").into_bytes());
+        }
+        if header(headers, "connection").is_none() {
+            bytes.extend(b"Connection: close\r\n");
+        }
+    }
+    for (name, value) in headers {
+        bytes.extend(format!("{name}: {value}\r\n").into_bytes());
+    }
+    if header(headers, "content-length").is_none()
+        && header(headers, "transfer-encoding").is_none()
+        && (host.is_none() || body_len > 0)
+    {
+        bytes.extend(format!("Content-Length: {body_len}\r\n").into_bytes());
+    }
+    bytes.extend(b"\r\n");
+}
+
+fn read_body(
+    backend: &dyn SysBackend,
+    handle: Handle,
+    headers: &Headers,
+    until_closed: bool,
+) -> Result<Vec<u8>, String> {
+    if header(headers, "transfer-encoding").is_some_and(|enc| enc.contains("chunked")) {
+        let mut body = Vec::new();
+        loop {
+            let line = read_line(backend, handle, b"\r\n", "HTTP chunk size line")?;
+            let line = String::from_utf8_lossy(&line);
+            let size = line.split(';').next().unwrap_or_default().trim();
+            let size = usize::from_str_radix(size, 16)
+                .map_err(|_| format!("Invalid chunk size {size:?} in HTTP body"))?;
+            if size == 0 {
+                // Skip trailers
+                while !matches!(
+                    &*read_line(backend, handle, b"\r\n", "HTTP trailer")?,
+                    b"\r\n" | b""
+                ) {}
+                break;
+            }
+            body.extend(read_exact(backend, handle, size)?);
+            read_exact(backend, handle, 2)?;
+        }
+        Ok(body)
+    } else if let Some(len) = header(headers, "content-length") {
+        let len =
+            (len.trim().parse()).map_err(|_| format!("Invalid HTTP content length {len:?}"))?;
+        read_exact(backend, handle, len)
+    } else if until_closed {
+        backend.read_all(handle)
+    } else {
+        Ok(Vec::new())
+    }
+}
+
+/// The maximum length of a header block or a line of a chunked body
+const MAX_LINE_LEN: usize = 64 << 10;
+
+/// Read up to and including a delimiter
+///
+/// Fails if the delimiter does not appear within [`MAX_LINE_LEN`] bytes.
+fn read_line(
+    backend: &dyn SysBackend,
+    handle: Handle,
+    delim: &[u8],
+    what: &str,
+) -> Result<Vec<u8>, String> {
+    let mut buffer = Vec::new();
+    while !buffer.ends_with(delim) {
+        if buffer.len() >= MAX_LINE_LEN {
+            return Err(format!(
+                "{what} is longer than the maximum of {MAX_LINE_LEN} bytes"
+            ));
+        }
+        let bytes = backend.read(handle, 1)?;
+        if bytes.is_empty() {
+            break;
+        }
+        buffer.extend(bytes);
+    }
+    Ok(buffer)
+}
+
+fn read_exact(backend: &dyn SysBackend, handle: Handle, len: usize) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::with_capacity(len.min(1 << 16));
+    while bytes.len() < len {
+        let chunk = backend.read(handle, (len - bytes.len()).min(1 << 16))?;
+        if chunk.is_empty() {
+            return Err("Connection closed before the full HTTP body was received".into());
+        }
+        bytes.extend(chunk);
+    }
+    Ok(bytes)
+}
+
+/// Get a field of a map, unboxing it
+fn field(map: &Value, name: &str, what: &str, env: &Uiua) -> UiuaResult<Option<Value>> {
+    if !map.is_map() {
+        return Err(env.error(format!("{what} must be a map")));
+    }
+    Ok(map.map_kv().into_iter().find_map(|(key, value)| {
+        (key.as_string(env, "").ok().as_deref() == Some(name)).then(|| value.unpacked())
+    }))
+}
+
+fn headers_from_value(headers: Value, env: &Uiua) -> UiuaResult<Headers> {
+    if !headers.is_map() {
+        return Err(env.error("HTTP headers must be a map"));
+    }
+    let mut collected = Vec::new();
+    for (name, value) in headers.map_kv() {
+        let name = name.as_string(env, "HTTP header names must be strings")?;
+        let value = (value.unpacked()).as_string(env, "HTTP header values must be strings")?;
+        if name.contains(['\r', '\n', ':']) || value.contains(['\r', '\n']) {
+            return Err(env.error(format!("Invalid HTTP header {name:?}")));
+        }
+        collected.push((name, value));
+    }
+    Ok(collected)
+}
+
+fn headers_value(headers: Headers, env: &Uiua) -> UiuaResult<Value> {
+    let (names, values): (EcoVec<_>, EcoVec<_>) = headers
+        .into_iter()
+        .map(|(name, value)| (Boxed(name.into()), Boxed(value.into())))
+        .unzip();
+    let mut value: Value = values.into();
+    value.map(names.into(), env)?;
+    Ok(value)
+}
+
+fn map_value<const N: usize>(entries: [(&str, Value); N], env: &Uiua) -> UiuaResult<Value> {
+    let (keys, values): (EcoVec<_>, EcoVec<_>) = entries
+        .into_iter()
+        .map(|(key, value)| (Boxed(key.into()), Boxed(value)))
+        .unzip();
+    let mut value: Value = values.into();
+    value.map(keys.into(), env)?;
+    Ok(value)
+}
+
+struct Url {
+    tls: bool,
+    authority: String,
+    host: String,
+    port: u16,
+    path: String,
+}
+
+impl Url {
+    fn parse(url: &str) -> Result<Self, String> {
+        let (tls, rest) = if let Some(rest) = url.strip_prefix("https://") {
+            (true, rest)
+        } else if let Some(rest) = url.strip_prefix("http://") {
+            (false, rest)
+        } else {
+            return Err(format!("URL {url:?} must start with http:// or https://"));
+        };
+        // Fragments are not sent to the server
+        let rest = rest.split('#').next().unwrap_or_default();
+        if rest.contains(|c: char| c.is_whitespace() || c.is_control()) {
+            return Err(format!("URL {url:?} contains whitespace"));
+        }
+        let split = rest.find(['/', '?']).unwrap_or(rest.len());
+        let (authority, path) = rest.split_at(split);
+        let path = match path {
+            "" => "/".into(),
+            path if path.starts_with('?') => format!("/{path}"),
+            path => path.into(),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) if !port.contains(']') => {
+                let port = port
+                    .parse()
+                    .map_err(|_| format!("Invalid port in URL {url:?}"))?;
+                (host, port)
+            }
+            _ => (authority, if tls { 443 } else { 80 }),
+        };
+        if host.is_empty() {
+            return Err(format!("URL {url:?} has no host"));
+        }
+        Ok(Url {
+            tls,
+            authority: authority.into(),
+            host: host.into(),
+            port,
+            path,
+        })
+    }
+}
+
+fn reason_phrase(status: usize) -> &'static str {
+    match status {
+        100 => "Continue",
+        101 => "Switching Protocols",
+        200 => "OK",
+        201 => "Created",
+        202 => "Accepted",
+        204 => "No Content",
+        206 => "Partial Content",
+        301 => "Moved Permanently",
+        302 => "Found",
+        303 => "See Other",
+        304 => "Not Modified",
+        307 => "Temporary Redirect",
+        308 => "Permanent Redirect",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        408 => "Request Timeout",
+        409 => "Conflict",
+        410 => "Gone",
+        413 => "Content Too Large",
+        415 => "Unsupported Media Type",
+        422 => "Unprocessable Content",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        501 => "Not Implemented",
+        502 => "Bad Gateway",
+        503 => "Service Unavailable",
+        504 => "Gateway Timeout",
+        _ => "",
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_url() {
+        let url = Url::parse("https://example.com").unwrap();
+        assert!(url.tls);
+        assert_eq!(
+            (&*url.host, url.port, &*url.path),
+            ("example.com", 443, "/")
+        );
+        let url = Url::parse("http://[::1]:8080?q=1#top").unwrap();
+        assert_eq!((&*url.authority, url.port), ("[::1]:8080", 8080));
+        assert_eq!(url.path, "/?q=1");
+        assert!(Url::parse("ftp://example.com").is_err());
+        assert!(Url::parse("http:///path").is_err());
+        assert!(Url::parse("http://a/b c").is_err());
+        assert!(Url::parse("http://a/\r\nX: y").is_err());
+    }
+
+    #[cfg(feature = "native_sys")]
+    #[test]
+    fn client_request() {
+        use std::{
+            io::{BufRead, BufReader, Read, Write},
+            net::TcpListener,
+            thread,
+        };
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut head = String::new();
+            while !head.ends_with("\r\n\r\n") {
+                reader.read_line(&mut head).unwrap();
+            }
+            let mut body = [0; 2];
+            reader.read_exact(&mut body).unwrap();
+            (reader.get_mut())
+                .write_all(
+                    b"HTTP/1.1 201 Created\r\nX-A: 1\r\nX-a: 2\r\n\
+                    Transfer-Encoding: chunked\r\n\r\n3\r\nabc\r\n2\r\nde\r\n0\r\n\r\n",
+                )
+                .unwrap();
+            (head, body)
+        });
+        let mut env = Uiua::with_native_sys();
+        env.run_str(&format!(
+            r#"&httpc map {{"url" "method" "body"}} {{"http://{addr}/p" "POST" "hi"}}
+            ⊃(°□get "status"|°utf °□get "body"|°□get "x-a" °□get "headers")"#
+        ))
+        .unwrap();
+        let (head, body) = server.join().unwrap();
+        assert!(head.starts_with("POST /p HTTP/1.1\r\n"), "{head}");
+        assert!(head.contains(&format!("Host: {addr}\r\n")), "{head}");
+        assert!(head.contains("Content-Length: 2\r\n"), "{head}");
+        assert_eq!(&body, b"hi");
+        assert_eq!(env.pop_num().unwrap(), 201.0);
+        assert_eq!(env.pop_string().unwrap(), "abcde");
+        assert_eq!(env.pop_string().unwrap(), "1, 2");
+
+        let Err(err) = env.run_str(r#"&httpc map {"url" "method"} {"http://a" "GET /x"}"#) else {
+            panic!("expected an invalid method error");
+        };
+        assert!(err.to_string().contains("Invalid HTTP method"), "{err}");
+    }
+
+    #[cfg(feature = "native_sys")]
+    #[test]
+    fn oversized_heads() {
+        use std::{io::Write, net::TcpListener, thread};
+        let long = "a".repeat(MAX_LINE_LEN);
+        let responses = [
+            format!("HTTP/1.1 200 OK\r\nX-Long: {long}\r\n\r\n"),
+            format!("HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n{long}\r\n"),
+        ];
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                // The client hangs up once the limit is reached
+                _ = stream.write_all(response.as_bytes());
+            }
+        });
+        let mut env = Uiua::with_native_sys();
+        for what in ["response head", "chunk size line"] {
+            let Err(err) = env.run_str(&format!(r#"&httpc "http://{addr}""#)) else {
+                panic!("expected the {what} to be too long");
+            };
+            assert!(err.to_string().contains(what), "{err}");
+            assert!(err.to_string().contains("maximum"), "{err}");
+        }
+        server.join().unwrap();
+    }
+}
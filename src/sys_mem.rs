@@ -0,0 +1,500 @@
+//! An in-memory [`SysBackend`] for tests and sandboxed embedding
+
+use std::{
+    any::Any,
+    collections::{BTreeMap, BTreeSet, HashMap, VecDeque},
+    mem::take,
+    path::{Component, Path, PathBuf},
+};
+
+use parking_lot::Mutex;
+
+use crate::{Handle, SysBackend};
+
+/// A system backend with an in-memory filesystem
+///
+/// Files can be preloaded with [`MemSys::with_file`] and inspected after a run with [`MemSys::snapshot`].
+/// Stdin is read from a buffer that can be filled with [`MemSys::with_stdin`],
+/// and stdout and stderr are captured.
+///
+/// Paths are resolved relative to a virtual current directory, which starts at the root.
+#[derive(Default)]
+pub struct MemSys {
+    state: Mutex<MemState>,
+}
+
+#[derive(Default)]
+struct MemState {
+    files: BTreeMap<PathBuf, Vec<u8>>,
+    dirs: BTreeSet<PathBuf>,
+    cwd: PathBuf,
+    streams: HashMap<Handle, MemStream>,
+    next_handle: u64,
+    stdin: VecDeque<u8>,
+    stdout: Vec<u8>,
+    stderr: Vec<u8>,
+}
+
+struct MemStream {
+    path: PathBuf,
+    pos: usize,
+    writeable: bool,
+}
+
+impl MemState {
+    fn resolve(&self, path: &Path) -> PathBuf {
+        let mut resolved = if path.is_absolute() {
+            PathBuf::new()
+        } else {
+            self.cwd.clone()
+        };
+        for comp in path.components() {
+            match comp {
+                Component::Normal(name) => resolved.push(name),
+                Component::ParentDir => {
+                    resolved.pop();
+                }
+                Component::CurDir | Component::RootDir | Component::Prefix(_) => {}
+            }
+        }
+        resolved
+    }
+    fn is_dir(&self, path: &Path) -> bool {
+        path.as_os_str().is_empty() || self.dirs.contains(path)
+    }
+    /// Add a directory and its parents, failing if any of them is a file
+    fn add_dir(&mut self, path: &Path) -> Result<(), String> {
+        let ancestors = path.ancestors().filter(|dir| !dir.as_os_str().is_empty());
+        if let Some(file) = ancestors.clone().find(|dir| self.files.contains_key(*dir)) {
+            return Err(format!("{} is a file", file.display()));
+        }
+        self.dirs.extend(ancestors.map(Into::into));
+        Ok(())
+    }
+    fn add_file(&mut self, path: PathBuf, contents: Vec<u8>) -> Result<(), String> {
+        if self.is_dir(&path) {
+            return Err(format!("{} is a directory", path.display()));
+        }
+        if let Some(parent) = path.parent() {
+            self.add_dir(parent)?;
+        }
+        self.files.insert(path, contents);
+        Ok(())
+    }
+    fn check_parent(&self, path: &Path, display: &Path) -> Result<(), String> {
+        if self.is_dir(path) {
+            return Err(format!("{} is a directory", display.display()));
+        }
+        match (path.parent(), display.parent()) {
+            (Some(parent), Some(display)) if !self.is_dir(parent) => {
+                Err(format!("Directory not found: {}", display.display()))
+            }
+            _ => Ok(()),
+        }
+    }
+    fn new_handle(&mut self) -> Handle {
+        let handle = Handle(Handle::FIRST_UNRESERVED.0 + self.next_handle);
+        self.next_handle += 1;
+        handle
+    }
+    fn open(&mut self, path: PathBuf, writeable: bool) -> Handle {
+        let handle = self.new_handle();
+        (self.streams).insert(
+            handle,
+            MemStream {
+                path,
+                pos: 0,
+                writeable,
+            },
+        );
+        handle
+    }
+    fn stream(&mut self, handle: Handle) -> Result<(&mut MemStream, &mut Vec<u8>), String> {
+        let stream = (self.streams.get_mut(&handle)).ok_or("Invalid file handle")?;
+        let contents = (self.files.get_mut(&stream.path))
+            .ok_or_else(|| format!("File not found: {}", stream.path.display()))?;
+        Ok((stream, contents))
+    }
+}
+
+impl MemSys {
+    /// Create a new in-memory system backend with an empty filesystem
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Add a file to the filesystem
+    ///
+    /// Its parent directories are created if they do not exist.
+    ///
+    /// # Panics
+    /// Panics if the path is a directory or if one of its parents is a file
+    pub fn with_file(self, path: impl AsRef<Path>, contents: impl Into<Vec<u8>>) -> Self {
+        self.add_file(path, contents);
+        self
+    }
+    /// Add a directory to the filesystem
+    ///
+    /// # Panics
+    /// Panics if the path or one of its parents is a file
+    pub fn with_dir(self, path: impl AsRef<Path>) -> Self {
+        self.add_dir(path);
+        self
+    }
+    /// Add some bytes to the end of stdin
+    pub fn with_stdin(self, stdin: impl AsRef<[u8]>) -> Self {
+        self.push_stdin(stdin);
+        self
+    }
+    /// Add a file to the filesystem, replacing it if it exists
+    ///
+    /// Its parent directories are created if they do not exist.
+    ///
+    /// # Panics
+    /// Panics if the path is a directory or if one of its parents is a file
+    pub fn add_file(&self, path: impl AsRef<Path>, contents: impl Into<Vec<u8>>) {
+        let mut state = self.state.lock();
+        let path = state.resolve(path.as_ref());
+        if let Err(e) = state.add_file(path, contents.into()) {
+            panic!("Failed to add file: {e}");
+        }
+    }
+    /// Add a directory and its parents to the filesystem
+    ///
+    /// # Panics
+    /// Panics if the path or one of its parents is a file
+    pub fn add_dir(&self, path: impl AsRef<Path>) {
+        let mut state = self.state.lock();
+        let path = state.resolve(path.as_ref());
+        if let Err(e) = state.add_dir(&path) {
+            panic!("Failed to add directory: {e}");
+        }
+    }
+    /// Add some bytes to the end of stdin
+    pub fn push_stdin(&self, stdin: impl AsRef<[u8]>) {
+        (self.state.lock().stdin).extend(stdin.as_ref());
+    }
+    /// Get the contents of a file
+    pub fn file(&self, path: impl AsRef<Path>) -> Option<Vec<u8>> {
+        let state = self.state.lock();
+        state.files.get(&state.resolve(path.as_ref())).cloned()
+    }
+    /// Get a copy of every file in the filesystem
+    ///
+    /// Paths are relative to the root.
+    pub fn snapshot(&self) -> BTreeMap<PathBuf, Vec<u8>> {
+        self.state.lock().files.clone()
+    }
+    /// Get every directory in the filesystem
+    ///
+    /// Paths are relative to the root.
+    pub fn dirs(&self) -> BTreeSet<PathBuf> {
+        self.state.lock().dirs.clone()
+    }
+    /// Take the captured stdout
+    pub fn take_stdout(&self) -> Vec<u8> {
+        take(&mut self.state.lock().stdout)
+    }
+    /// Take the captured stderr
+    pub fn take_stderr(&self) -> Vec<u8> {
+        take(&mut self.state.lock().stderr)
+    }
+}
+
+impl SysBackend for MemSys {
+    fn any(&self) -> &dyn Any {
+        self
+    }
+    fn any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+    fn print_str_stdout(&self, s: &str) -> Result<(), String> {
+        (self.state.lock().stdout).extend_from_slice(s.as_bytes());
+        Ok(())
+    }
+    fn print_str_stderr(&self, s: &str) -> Result<(), String> {
+        (self.state.lock().stderr).extend_from_slice(s.as_bytes());
+        Ok(())
+    }
+    fn scan_line_stdin(&self) -> Result<Option<String>, String> {
+        let mut state = self.state.lock();
+        if state.stdin.is_empty() {
+            return Ok(None);
+        }
+        let end =
+            (state.stdin.iter().position(|&b| b == b'\n')).map_or(state.stdin.len(), |i| i + 1);
+        let bytes: Vec<u8> = state.stdin.drain(..end).collect();
+        let line = String::from_utf8(bytes).map_err(|e| e.to_string())?;
+        Ok(Some(line.trim_end_matches(['\n', '\r']).into()))
+    }
+    fn scan_stdin(&self, count: usize) -> Result<Vec<u8>, String> {
+        let mut state = self.state.lock();
+        let count = count.min(state.stdin.len());
+        Ok(state.stdin.drain(..count).collect())
+    }
+    fn file_exists(&self, path: &str) -> bool {
+        let state = self.state.lock();
+        let path = state.resolve(path.as_ref());
+        state.files.contains_key(&path) || state.is_dir(&path)
+    }
+    fn list_dir(&self, path: &str) -> Result<Vec<String>, String> {
+        let state = self.state.lock();
+        let dir = state.resolve(path.as_ref());
+        if !state.is_dir(&dir) {
+            return Err(format!("Directory not found: {path}"));
+        }
+        let mut children: Vec<String> = (state.files.keys())
+            .chain(&state.dirs)
+            .filter(|child| child.parent() == Some(&dir))
+            .map(|child| {
+                let name = child.file_name().unwrap_or_default();
+                Path::new(path).join(name).to_string_lossy().into_owned()
+            })
+            .collect();
+        children.sort();
+        Ok(children)
+    }
+    fn is_file(&self, path: &str) -> Result<bool, String> {
+        let state = self.state.lock();
+        let resolved = state.resolve(path.as_ref());
+        if state.files.contains_key(&resolved) {
+            Ok(true)
+        } else if state.is_dir(&resolved) {
+            Ok(false)
+        } else {
+            Err(format!("File not found: {path}"))
+        }
+    }
+    fn delete(&self, path: &str) -> Result<(), String> {
+        let mut state = self.state.lock();
+        let resolved = state.resolve(path.as_ref());
+        if resolved.as_os_str().is_empty() {
+            return Err("Cannot delete the root directory".into());
+        }
+        if !state.files.contains_key(&resolved) && !state.dirs.contains(&resolved) {
+            return Err(format!("File not found: {path}"));
+        }
+        state.files.retain(|file, _| !file.starts_with(&resolved));
+        state.dirs.retain(|dir| !dir.starts_with(&resolved));
+        Ok(())
+    }
+    fn trash(&self, path: &str) -> Result<(), String> {
+        self.delete(path)
+    }
+    fn create_dir(&self, path: &str) -> Result<(), String> {
+        let mut state = self.state.lock();
+        let resolved = state.resolve(path.as_ref());
+        state.add_dir(&resolved)
+    }
+    fn rename(&self, from: &str, to: &str) -> Result<(), String> {
+        let mut state = self.state.lock();
+        let (from_path, to_path) = (state.resolve(from.as_ref()), state.resolve(to.as_ref()));
+        if !state.files.contains_key(&from_path) && !state.dirs.contains(&from_path) {
+            return Err(format!("File not found: {from}"));
+        }
+        if to_path != from_path && to_path.starts_with(&from_path) {
+            return Err(format!("Cannot move {from} into itself"));
+        }
+        // Existing directories are never replaced, and files only by files
+        state.check_parent(&to_path, to.as_ref())?;
+        if state.dirs.contains(&from_path) && state.files.contains_key(&to_path) {
+            return Err(format!("Cannot replace file {to} with directory {from}"));
+        }
+        let moved = |path: &Path| to_path.join(path.strip_prefix(&from_path).unwrap());
+        let files: Vec<_> = (state.files.keys())
+            .filter(|file| file.starts_with(&from_path))
+            .cloned()
+            .collect();
+        for file in files {
+            let contents = state.files.remove(&file).unwrap();
+            state.files.insert(moved(&file), contents);
+        }
+        let dirs: Vec<_> = (state.dirs.iter())
+            .filter(|dir| dir.starts_with(&from_path))
+            .cloned()
+            .collect();
+        for dir in dirs {
+            state.dirs.remove(&dir);
+            state.dirs.insert(moved(&dir));
+        }
+        for stream in state.streams.values_mut() {
+            if stream.path.starts_with(&from_path) {
+                stream.path = moved(&stream.path);
+            }
+        }
+        Ok(())
+    }
+    fn file_size(&self, path: &str) -> Result<u64, String> {
+        let state = self.state.lock();
+        (state.files.get(&state.resolve(path.as_ref())))
+            .map(|contents| contents.len() as u64)
+            .ok_or_else(|| format!("File not found: {path}"))
+    }
+    fn read(&self, handle: Handle, count: usize) -> Result<Vec<u8>, String> {
+        if handle == Handle::STDIN {
+            return self.scan_stdin(count);
+        }
+        let mut state = self.state.lock();
+        let (stream, contents) = state.stream(handle)?;
+        let start = stream.pos.min(contents.len());
+        let end = start.saturating_add(count).min(contents.len());
+        stream.pos = end;
+        Ok(contents[start..end].to_vec())
+    }
+    fn read_all(&self, handle: Handle) -> Result<Vec<u8>, String> {
+        self.read(handle, usize::MAX)
+    }
+    fn write(&self, handle: Handle, bytes: &[u8]) -> Result<(), String> {
+        let mut state = self.state.lock();
+        match handle {
+            Handle::STDOUT => state.stdout.extend_from_slice(bytes),
+            Handle::STDERR => state.stderr.extend_from_slice(bytes),
+            _ => {
+                let (stream, contents) = state.stream(handle)?;
+                if !stream.writeable {
+                    return Err("File is not writeable".into());
+                }
+                let end = stream.pos + bytes.len();
+                if contents.len() < end {
+                    contents.resize(end, 0);
+                }
+                contents[stream.pos..end].copy_from_slice(bytes);
+                stream.pos = end;
+            }
+        }
+        Ok(())
+    }
+    fn create_file(&self, path: &Path) -> Result<Handle, String> {
+        let mut state = self.state.lock();
+        let resolved = state.resolve(path);
+        state.check_parent(&resolved, path)?;
+        state.files.insert(resolved.clone(), Vec::new());
+        Ok(state.open(resolved, true))
+    }
+    fn open_file(&self, path: &Path, write: bool) -> Result<Handle, String> {
+        let mut state = self.state.lock();
+        let resolved = state.resolve(path);
+        if !state.files.contains_key(&resolved) {
+            return Err(format!("File not found: {}", path.display()));
+        }
+        Ok(state.open(resolved, write))
+    }
+    fn file_read_all(&self, path: &Path) -> Result<Vec<u8>, String> {
+        let state = self.state.lock();
+        (state.files.get(&state.resolve(path)))
+            .cloned()
+            .ok_or_else(|| format!("File not found: {}", path.display()))
+    }
+    fn file_write_all(&self, path: &Path, contents: &[u8]) -> Result<(), String> {
+        let mut state = self.state.lock();
+        let resolved = state.resolve(path);
+        state.check_parent(&resolved, path)?;
+        state.files.insert(resolved, contents.to_vec());
+        Ok(())
+    }
+    fn close(&self, handle: Handle) -> Result<(), String> {
+        (self.state.lock().streams.remove(&handle))
+            .map(drop)
+            .ok_or_else(|| "Invalid file handle".into())
+    }
+    fn change_directory(&self, path: &str) -> Result<(), String> {
+        let mut state = self.state.lock();
+        let resolved = state.resolve(path.as_ref());
+        if !state.is_dir(&resolved) {
+            return Err(format!("Directory not found: {path}"));
+        }
+        state.cwd = resolved;
+        Ok(())
+    }
+    fn current_directory(&self) -> Result<PathBuf, String> {
+        Ok(Path::new("/").join(&self.state.lock().cwd))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn delete() {
+        let sys = MemSys::new()
+            .with_file("a/b.txt", "b")
+            .with_file("a/c/d.txt", "d")
+            .with_file("e.txt", "e");
+        sys.delete("a/c").unwrap();
+        assert_eq!(sys.list_dir("a").unwrap(), ["a/b.txt"]);
+        sys.delete("e.txt").unwrap();
+        assert!(!sys.file_exists("e.txt"));
+        let err = sys.delete("e.txt").unwrap_err();
+        assert!(err.contains("not found"), "{err}");
+        assert!(sys.delete("missing/dir").is_err());
+        assert!(sys.delete("/").is_err());
+        assert!(sys.file_exists("a/b.txt"));
+    }
+
+    #[test]
+    fn std_handles() {
+        let sys = MemSys::new().with_stdin("in");
+        sys.write(Handle::STDOUT, b"out").unwrap();
+        sys.print_str_stdout("put").unwrap();
+        sys.write(Handle::STDERR, b"err").unwrap();
+        assert_eq!(sys.take_stdout(), b"output");
+        assert_eq!(sys.take_stderr(), b"err");
+        assert_eq!(sys.read(Handle::STDIN, 1).unwrap(), b"i");
+        assert_eq!(sys.read_all(Handle::STDIN).unwrap(), b"n");
+        assert!(sys.write(Handle::FIRST_UNRESERVED, b"x").is_err());
+    }
+
+    #[test]
+    fn files() {
+        let sys = MemSys::new().with_dir("d");
+        let handle = sys.create_file("d/f.txt".as_ref()).unwrap();
+        sys.write(handle, b"hello").unwrap();
+        sys.close(handle).unwrap();
+        assert_eq!(sys.file("d/f.txt").unwrap(), b"hello");
+        sys.change_directory("d").unwrap();
+        assert_eq!(sys.file_read_all("f.txt".as_ref()).unwrap(), b"hello");
+        sys.rename("f.txt", "../g.txt").unwrap();
+        assert_eq!(sys.file("/g.txt").unwrap(), b"hello");
+        assert!(sys.file_write_all("missing/h.txt".as_ref(), b"").is_err());
+    }
+
+    #[test]
+    fn directories() {
+        let sys = MemSys::new().with_file("a/b.txt", "b");
+        sys.create_dir("a/c/d").unwrap();
+        sys.create_dir("a/c").unwrap();
+        assert!(sys.create_dir("a/b.txt").is_err());
+        let err = sys.create_dir("a/b.txt/c/d").unwrap_err();
+        assert!(err.contains("b.txt is a file"), "{err}");
+        assert!(sys.list_dir("a/b.txt/c").is_err());
+        assert!(sys.list_dir("a/b.txt").is_err());
+        sys.copy_file("a/b.txt", "a/c/d/e.txt").unwrap();
+        assert_eq!(sys.file_size("a/c/d/e.txt").unwrap(), 1);
+        assert!(sys.file_size("a/c").is_err());
+        assert_eq!(sys.walk_dir("a").unwrap(), ["a/b.txt", "a/c/d/e.txt"]);
+        assert!(sys.rename("a", "a/c/a").is_err());
+        sys.rename("a/c", "f").unwrap();
+        assert_eq!(sys.walk_dir("/").unwrap(), ["/a/b.txt", "/f/d/e.txt"]);
+        assert!(sys.is_file("a/c").is_err());
+    }
+
+    #[test]
+    fn rename_onto_existing() {
+        let sys = MemSys::new()
+            .with_file("a.txt", "a")
+            .with_file("b.txt", "b")
+            .with_file("d/e.txt", "e")
+            .with_dir("empty");
+        let err = sys.rename("a.txt", "empty").unwrap_err();
+        assert!(err.contains("empty is a directory"), "{err}");
+        let err = sys.rename("d", "b.txt").unwrap_err();
+        assert!(err.contains("Cannot replace file"), "{err}");
+        assert!(sys.rename("empty", "d").is_err());
+        assert_eq!(sys.walk_dir("/").unwrap(), ["/a.txt", "/b.txt", "/d/e.txt"]);
+        assert!(sys.is_file("empty").is_ok_and(|is_file| !is_file));
+        // Files replace files
+        sys.rename("a.txt", "b.txt").unwrap();
+        assert_eq!(sys.file("b.txt").unwrap(), b"a");
+        assert!(!sys.file_exists("a.txt"));
+    }
+}
@@ -12,10 +12,10 @@ use std::{
         Arc,
     },
     thread::sleep,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
-use crate::{Handle, SysBackend};
+use crate::{Dependency, Handle, InterruptHandle, Lockfile, Manifest, SysBackend};
 use dashmap::DashMap;
 use once_cell::sync::Lazy;
 
@@ -25,15 +25,18 @@ pub struct NativeSys;
 
 struct GlobalNativeSys {
     output_enabled: AtomicBool,
+    output_hook: parking_lot::RwLock<Option<OutputHook>>,
     next_handle: AtomicU64,
     files: DashMap<Handle, BufReader<File>>,
     child_stdins: DashMap<Handle, ChildStream<ChildStdin>>,
     child_stdouts: DashMap<Handle, ChildStream<ChildStdout>>,
     child_stderrs: DashMap<Handle, ChildStream<ChildStderr>>,
+    processes: DashMap<Handle, SharedChild>,
     tcp_listeners: DashMap<Handle, TcpListener>,
     tls_listeners: DashMap<Handle, TlsListener>,
     tcp_sockets: DashMap<Handle, TcpStream>,
     tls_sockets: DashMap<Handle, TlsSocket>,
+    udp_sockets: DashMap<Handle, UdpSocket>,
     #[cfg(feature = "webcam")]
     cam_channels: DashMap<usize, WebcamChannel>,
     hostnames: DashMap<Handle, String>,
@@ -58,15 +61,17 @@ enum SysStream<'a> {
     TlsSocket(dashmap::mapref::one::Ref<'a, Handle, TlsSocket>),
 }
 
+type SharedChild = Arc<parking_lot::Mutex<Child>>;
+
 struct ChildStream<T> {
     stream: T,
-    child: Arc<Child>,
+    child: SharedChild,
 }
 
 impl<T> Drop for ChildStream<T> {
     fn drop(&mut self) {
         if let Some(child) = Arc::get_mut(&mut self.child) {
-            _ = child.kill();
+            _ = child.get_mut().kill();
         }
     }
 }
@@ -200,15 +205,18 @@ impl Default for GlobalNativeSys {
     fn default() -> Self {
         Self {
             output_enabled: AtomicBool::new(true),
+            output_hook: parking_lot::RwLock::new(None),
             next_handle: Handle::FIRST_UNRESERVED.0.into(),
             files: DashMap::new(),
             child_stdins: DashMap::new(),
             child_stdouts: DashMap::new(),
             child_stderrs: DashMap::new(),
+            processes: DashMap::new(),
             tcp_listeners: DashMap::new(),
             tls_listeners: DashMap::new(),
             tcp_sockets: DashMap::new(),
             tls_sockets: DashMap::new(),
+            udp_sockets: DashMap::new(),
             #[cfg(feature = "webcam")]
             cam_channels: DashMap::new(),
             hostnames: DashMap::new(),
@@ -234,9 +242,11 @@ impl GlobalNativeSys {
                 && !self.child_stdins.contains_key(&handle)
                 && !self.child_stdouts.contains_key(&handle)
                 && !self.child_stderrs.contains_key(&handle)
+                && !self.processes.contains_key(&handle)
                 && !self.tcp_listeners.contains_key(&handle)
                 && !self.tcp_sockets.contains_key(&handle)
                 && !self.tls_sockets.contains_key(&handle)
+                && !self.udp_sockets.contains_key(&handle)
             {
                 return handle;
             }
@@ -260,6 +270,42 @@ impl GlobalNativeSys {
             return Err("Invalid file handle".to_string());
         })
     }
+    fn insert_child(&self, mut child: Child) -> (SharedChild, [Handle; 3]) {
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+        let stderr = child.stderr.take().unwrap();
+        let child = Arc::new(parking_lot::Mutex::new(child));
+        let stdin_handle = self.new_handle();
+        self.child_stdins.insert(
+            stdin_handle,
+            ChildStream {
+                stream: stdin,
+                child: child.clone(),
+            },
+        );
+        let stdout_handle = self.new_handle();
+        self.child_stdouts.insert(
+            stdout_handle,
+            ChildStream {
+                stream: stdout,
+                child: child.clone(),
+            },
+        );
+        let stderr_handle = self.new_handle();
+        self.child_stderrs.insert(
+            stderr_handle,
+            ChildStream {
+                stream: stderr,
+                child: child.clone(),
+            },
+        );
+        (child, [stdin_handle, stdout_handle, stderr_handle])
+    }
+    fn get_process(&self, handle: Handle) -> Result<SharedChild, String> {
+        (self.processes.get(&handle))
+            .map(|child| child.clone())
+            .ok_or_else(|| "Invalid process handle".to_string())
+    }
     fn get_tcp_listener<T>(&self, handle: Handle, f: impl FnOnce(&TcpListener) -> T) -> Option<T> {
         if let Some(listener) = self.tcp_listeners.get(&handle) {
             Some(f(&listener))
@@ -278,6 +324,9 @@ impl GlobalNativeSys {
 
 static NATIVE_SYS: Lazy<GlobalNativeSys> = Lazy::new(Default::default);
 
+/// How long blocking operations wait before checking if the runtime has been interrupted
+const INTERRUPT_POLL: Duration = Duration::from_millis(50);
+
 #[cfg(all(feature = "audio", feature = "binary"))]
 #[doc(hidden)]
 pub fn set_audio_stream_time(time: f64) {
@@ -303,6 +352,31 @@ pub(crate) fn set_output_enabled(enabled: bool) -> bool {
         .swap(enabled, atomic::Ordering::Relaxed)
 }
 
+/// A standard output stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum OutputStream {
+    Stdout,
+    Stderr,
+    Trace,
+}
+
+/// A function that receives output in place of the standard streams
+pub(crate) type OutputHook = Arc<dyn Fn(OutputStream, &str) + Send + Sync>;
+
+/// Redirect output that would be printed to the standard streams
+pub(crate) fn set_output_hook(hook: Option<OutputHook>) {
+    *NATIVE_SYS.output_hook.write() = hook;
+}
+
+fn hook_output(stream: OutputStream, s: &str) -> bool {
+    if let Some(hook) = &*NATIVE_SYS.output_hook.read() {
+        hook(stream, s);
+        true
+    } else {
+        false
+    }
+}
+
 impl SysBackend for NativeSys {
     fn any(&self) -> &dyn Any {
         self
@@ -311,7 +385,7 @@ impl SysBackend for NativeSys {
         self
     }
     fn print_str_stdout(&self, s: &str) -> Result<(), String> {
-        if !output_enabled() {
+        if hook_output(OutputStream::Stdout, s) || !output_enabled() {
             return Ok(());
         }
         let mut stdout = stdout().lock();
@@ -319,7 +393,7 @@ impl SysBackend for NativeSys {
         stdout.flush().map_err(|e| e.to_string())
     }
     fn print_str_stderr(&self, s: &str) -> Result<(), String> {
-        if !output_enabled() {
+        if hook_output(OutputStream::Stderr, s) || !output_enabled() {
             return Ok(());
         }
         let mut stderr = stderr().lock();
@@ -327,7 +401,7 @@ impl SysBackend for NativeSys {
         stderr.flush().map_err(|e| e.to_string())
     }
     fn print_str_trace(&self, s: &str) {
-        if !output_enabled() {
+        if hook_output(OutputStream::Trace, s) || !output_enabled() {
             return;
         }
         eprint!("{s}");
@@ -443,6 +517,47 @@ impl SysBackend for NativeSys {
     fn trash(&self, path: &str) -> Result<(), String> {
         trash::delete(path).map_err(|e| e.to_string())
     }
+    fn create_dir(&self, path: &str) -> Result<(), String> {
+        fs::create_dir_all(path).map_err(|e| e.to_string())
+    }
+    fn rename(&self, from: &str, to: &str) -> Result<(), String> {
+        fs::rename(from, to).map_err(|e| e.to_string())
+    }
+    fn copy_file(&self, from: &str, to: &str) -> Result<(), String> {
+        fs::copy(from, to).map(drop).map_err(|e| e.to_string())
+    }
+    fn file_size(&self, path: &str) -> Result<u64, String> {
+        fs::metadata(path)
+            .map(|m| m.len())
+            .map_err(|e| e.to_string())
+    }
+    fn modified_time(&self, path: &str) -> Result<f64, String> {
+        let modified =
+            (fs::metadata(path).and_then(|m| m.modified())).map_err(|e| e.to_string())?;
+        let since_epoch = modified
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_err(|e| e.to_string())?;
+        Ok(since_epoch.as_secs_f64())
+    }
+    fn walk_dir(&self, path: &str) -> Result<Vec<String>, String> {
+        let mut files = Vec::new();
+        let mut dirs = vec![PathBuf::from(path)];
+        while let Some(dir) = dirs.pop() {
+            for entry in fs::read_dir(&dir).map_err(|e| e.to_string())? {
+                let entry = entry.map_err(|e| e.to_string())?;
+                let file_type = entry.file_type().map_err(|e| e.to_string())?;
+                if file_type.is_dir() {
+                    dirs.push(entry.path());
+                } else if file_type.is_symlink() && entry.path().is_dir() {
+                    // Symlinks to directories are skipped to avoid cycles
+                } else {
+                    files.push(entry.path().to_string_lossy().into());
+                }
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
     fn read(&self, handle: Handle, len: usize) -> Result<Vec<u8>, String> {
         Ok(match NATIVE_SYS.get_stream(handle)? {
             SysStream::File(mut file) => {
@@ -813,12 +928,89 @@ impl SysBackend for NativeSys {
             .ok_or_else(|| "Invalid tcp socket handle".to_string())?
             .map_err(|e| e.to_string())
     }
+    fn udp_bind(&self, addr: &str) -> Result<Handle, String> {
+        let handle = NATIVE_SYS.new_handle();
+        let socket = UdpSocket::bind(addr).map_err(|e| e.to_string())?;
+        NATIVE_SYS.udp_sockets.insert(handle, socket);
+        Ok(handle)
+    }
+    fn udp_addr(&self, handle: Handle) -> Result<SocketAddr, String> {
+        let socket = (NATIVE_SYS.udp_sockets.get(&handle))
+            .ok_or_else(|| "Invalid udp socket handle".to_string())?;
+        socket.local_addr().map_err(|e| e.to_string())
+    }
+    fn udp_send_to(&self, handle: Handle, data: &[u8], addr: &str) -> Result<(), String> {
+        let socket = (NATIVE_SYS.udp_sockets.get(&handle))
+            .ok_or_else(|| "Invalid udp socket handle".to_string())?;
+        let sent = socket.send_to(data, addr).map_err(|e| e.to_string())?;
+        if sent < data.len() {
+            return Err(format!(
+                "Only {sent} of {} bytes were sent in the datagram",
+                data.len()
+            ));
+        }
+        Ok(())
+    }
+    fn udp_recv_from(
+        &self,
+        handle: Handle,
+        max_len: usize,
+        interrupt: &InterruptHandle,
+    ) -> Result<(Vec<u8>, SocketAddr), String> {
+        let socket = (NATIVE_SYS.udp_sockets.get(&handle))
+            .ok_or_else(|| "Invalid udp socket handle".to_string())?;
+        let timeout = socket.read_timeout().map_err(|e| e.to_string())?;
+        let start = Instant::now();
+        let mut buf = vec![0; max_len];
+        // Wait in short steps so that the runtime can be interrupted
+        let res = loop {
+            if interrupt.is_interrupted() {
+                break Err("Receiving was interrupted".into());
+            }
+            let step = match timeout.map(|timeout| timeout.saturating_sub(start.elapsed())) {
+                Some(Duration::ZERO) => break Err("Receiving timed out".into()),
+                Some(remaining) => remaining.min(INTERRUPT_POLL),
+                None => INTERRUPT_POLL,
+            };
+            if let Err(e) = socket.set_read_timeout(Some(step)) {
+                break Err(e.to_string());
+            }
+            match socket.recv_from(&mut buf) {
+                Ok(received) => break Ok(received),
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                    ) => {}
+                Err(e) => break Err(e.to_string()),
+            }
+        };
+        socket
+            .set_read_timeout(timeout)
+            .map_err(|e| e.to_string())?;
+        let (len, addr) = res?;
+        buf.truncate(len);
+        Ok((buf, addr))
+    }
+    fn udp_set_timeout(&self, handle: Handle, timeout: Option<Duration>) -> Result<(), String> {
+        let socket = (NATIVE_SYS.udp_sockets.get(&handle))
+            .ok_or_else(|| "Invalid udp socket handle".to_string())?;
+        socket
+            .set_read_timeout(timeout)
+            .map_err(|e| e.to_string())?;
+        socket.set_write_timeout(timeout).map_err(|e| e.to_string())
+    }
     fn close(&self, handle: Handle) -> Result<(), String> {
         if NATIVE_SYS.child_stdins.remove(&handle).is_some()
             || NATIVE_SYS.child_stdouts.remove(&handle).is_some()
             || NATIVE_SYS.child_stderrs.remove(&handle).is_some()
         {
             Ok(())
+        } else if let Some((_, mut child)) = NATIVE_SYS.processes.remove(&handle) {
+            if let Some(child) = Arc::get_mut(&mut child) {
+                _ = child.get_mut().kill();
+            }
+            Ok(())
         } else if let Some((_, mut file)) = NATIVE_SYS.files.remove(&handle) {
             file.get_mut().flush().map_err(|e| e.to_string())
         } else if let Some((_, socket)) = NATIVE_SYS.tcp_sockets.remove(&handle) {
@@ -829,6 +1021,7 @@ impl SysBackend for NativeSys {
             (&mut &socket).flush().map_err(|e| e.to_string())
         } else if NATIVE_SYS.tcp_listeners.remove(&handle).is_some()
             || NATIVE_SYS.tls_listeners.remove(&handle).is_some()
+            || NATIVE_SYS.udp_sockets.remove(&handle).is_some()
         {
             NATIVE_SYS.hostnames.remove(&handle);
             Ok(())
@@ -865,46 +1058,77 @@ impl SysBackend for NativeSys {
         ))
     }
     fn run_command_stream(&self, command: &str, args: &[&str]) -> Result<[Handle; 3], String> {
-        let mut child = Command::new(command)
+        let child = Command::new(command)
             .args(args)
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
             .spawn()
             .map_err(|e| e.to_string())?;
-        let stdin = child.stdin.take().unwrap();
-        let stdout = child.stdout.take().unwrap();
-        let stderr = child.stderr.take().unwrap();
-        let child = Arc::new(child);
-        let stdin_handle = NATIVE_SYS.new_handle();
-        NATIVE_SYS.child_stdins.insert(
-            stdin_handle,
-            ChildStream {
-                stream: stdin,
-                child: child.clone(),
-            },
-        );
-        let stdout_handle = NATIVE_SYS.new_handle();
-        NATIVE_SYS.child_stdouts.insert(
-            stdout_handle,
-            ChildStream {
-                stream: stdout,
-                child: child.clone(),
-            },
-        );
-        let stderr_handle = NATIVE_SYS.new_handle();
-        NATIVE_SYS.child_stderrs.insert(
-            stderr_handle,
-            ChildStream {
-                stream: stderr,
-                child,
-            },
-        );
-        Ok([stdin_handle, stdout_handle, stderr_handle])
+        let (_, handles) = NATIVE_SYS.insert_child(child);
+        Ok(handles)
+    }
+    fn spawn_process(
+        &self,
+        command: &str,
+        args: &[&str],
+        options: &crate::SpawnOptions,
+    ) -> Result<[Handle; 4], String> {
+        let mut command = Command::new(command);
+        command
+            .args(args)
+            .envs(options.env.iter().map(|(k, v)| (k, v)))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        if let Some(cwd) = &options.cwd {
+            command.current_dir(cwd);
+        }
+        let child = command.spawn().map_err(|e| e.to_string())?;
+        let (child, [stdin, stdout, stderr]) = NATIVE_SYS.insert_child(child);
+        let handle = NATIVE_SYS.new_handle();
+        NATIVE_SYS.processes.insert(handle, child);
+        Ok([handle, stdin, stdout, stderr])
+    }
+    fn wait_process(&self, handle: Handle, interrupt: &InterruptHandle) -> Result<i32, String> {
+        let child = NATIVE_SYS.get_process(handle)?;
+        // Poll so that the process can be killed from another thread while waiting
+        // and so that the runtime can be interrupted
+        loop {
+            if let Some(status) = child.lock().try_wait().map_err(|e| e.to_string())? {
+                return Ok(status.code().unwrap_or(-1));
+            }
+            if interrupt.is_interrupted() {
+                return Err("Waiting for the process was interrupted".into());
+            }
+            sleep(Duration::from_millis(10));
+        }
+    }
+    fn kill_process(&self, handle: Handle) -> Result<(), String> {
+        let child = NATIVE_SYS.get_process(handle)?;
+        let mut child = child.lock();
+        if child.try_wait().map_err(|e| e.to_string())?.is_none() {
+            child.kill().map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+    fn process_status(&self, handle: Handle) -> Result<Option<i32>, String> {
+        let child = NATIVE_SYS.get_process(handle)?;
+        let status = child.lock().try_wait().map_err(|e| e.to_string())?;
+        Ok(status.map(|status| status.code().unwrap_or(-1)))
+    }
+    fn process_id(&self, handle: Handle) -> Result<u32, String> {
+        Ok(NATIVE_SYS.get_process(handle)?.lock().id())
     }
     fn change_directory(&self, path: &str) -> Result<(), String> {
         env::set_current_dir(path).map_err(|e| e.to_string())
     }
+    fn current_directory(&self) -> Result<PathBuf, String> {
+        env::current_dir().map_err(|e| e.to_string())
+    }
+    fn read_link(&self, path: &Path) -> Option<PathBuf> {
+        fs::read_link(path).ok()
+    }
     #[cfg(feature = "tls")]
     fn https_get(&self, request: &str, handle: Handle) -> Result<String, String> {
         use std::io;
@@ -1077,6 +1301,112 @@ impl SysBackend for NativeSys {
         NATIVE_SYS.git_paths.insert(url.to_string(), res.clone());
         res
     }
+    fn load_project_git_module(
+        &self,
+        url: &str,
+        branch: Option<&str>,
+        project: &Path,
+    ) -> Result<PathBuf, String> {
+        // Dependencies declared in the project's manifest are pinned
+        let Some(manifest) = Manifest::load(project)? else {
+            return self.load_git_module(url, branch);
+        };
+        let Some((name, dep)) = manifest.dependency_for_url(url) else {
+            return self.load_git_module(url, branch);
+        };
+        let key = format!("{url} for {}", project.display());
+        if let Some(path) = NATIVE_SYS.git_paths.get(&key) {
+            if path.is_err() || path.as_ref().unwrap().exists() {
+                return path.clone();
+            }
+        }
+        let res = load_pinned_git_module(url, name, dep, project);
+        NATIVE_SYS.git_paths.insert(key, res.clone());
+        res
+    }
+}
+
+/// Load a git module declared in a project's manifest, checking out the commit in the lockfile
+///
+/// If the dependency is not locked yet, its pinned reference is resolved and locked.
+/// The module is cloned into the project's `uiua-modules` directory.
+fn load_pinned_git_module(
+    url: &str,
+    name: &str,
+    dep: &Dependency,
+    dir: &Path,
+) -> Result<PathBuf, String> {
+    let modules_path = dir.join("uiua-modules");
+    let module_path = modules_path.join(name);
+    let path = module_path.join("lib.ua");
+    if !module_path.exists() {
+        fs::create_dir_all(&modules_path).map_err(|e| e.to_string())?;
+        let module_path = module_path.to_string_lossy();
+        run_git(None, &["clone", "--quiet", url, &module_path])
+            .map_err(|e| format!("Failed to clone {url}: {e}"))?;
+    }
+    let mut lockfile = Lockfile::load(dir)?;
+    let target = match lockfile.get(name, dep) {
+        Some(locked) => Some(locked.commit.clone()),
+        None => dep.reference().map(Into::into),
+    };
+    if let Some(target) = target {
+        let resolve = || {
+            [target.clone(), format!("origin/{target}")]
+                .into_iter()
+                .find_map(|target| {
+                    let spec = format!("{target}^{{commit}}");
+                    run_git(
+                        Some(&module_path),
+                        &["rev-parse", "--verify", "--quiet", &spec],
+                    )
+                    .ok()
+                })
+        };
+        let commit = match resolve() {
+            Some(commit) => commit,
+            None => {
+                run_git(
+                    Some(&module_path),
+                    &["fetch", "--quiet", "--tags", "origin"],
+                )
+                .map_err(|e| format!("Failed to fetch {url}: {e}"))?;
+                resolve().ok_or_else(|| format!("`{target}` not found in {url}"))?
+            }
+        };
+        let head = run_git(Some(&module_path), &["rev-parse", "HEAD"])?;
+        if head != commit {
+            run_git(
+                Some(&module_path),
+                &["checkout", "--quiet", "--detach", &commit],
+            )
+            .map_err(|e| format!("Failed to check out {commit} in {url}: {e}"))?;
+        }
+    }
+    let commit = run_git(Some(&module_path), &["rev-parse", "HEAD"])?;
+    if lockfile.get(name, dep).map(|locked| &locked.commit) != Some(&commit) {
+        lockfile.insert(name, dep, commit);
+        lockfile.save(dir)?;
+    }
+    Ok(path)
+}
+
+/// Run a git command, returning its trimmed output
+fn run_git(dir: Option<&Path>, args: &[&str]) -> Result<String, String> {
+    let mut command = Command::new("git");
+    if let Some(dir) = dir {
+        command.arg("-C").arg(dir);
+    }
+    let output = command
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| e.to_string())?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).trim().into())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().into())
+    }
 }
 
 /// Takes an HTTP request, validates it, and fixes it (if possible) by adding
@@ -1166,3 +1496,68 @@ fn check_http(mut request: String, hostname: &str) -> Result<String, String> {
 
     Ok(request)
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn udp_round_trip() {
+        let sys = NativeSys;
+        let a = sys.udp_bind("127.0.0.1:0").unwrap();
+        let b = sys.udp_bind("127.0.0.1:0").unwrap();
+        let b_addr = sys.udp_addr(b).unwrap().to_string();
+        sys.udp_set_timeout(b, Some(Duration::from_secs(5)))
+            .unwrap();
+        sys.udp_send_to(a, b"hello", &b_addr).unwrap();
+        let interrupt = InterruptHandle::default();
+        let (bytes, from) = sys.udp_recv_from(b, 16, &interrupt).unwrap();
+        assert_eq!(bytes, b"hello");
+        assert_eq!(from, sys.udp_addr(a).unwrap());
+        // Waiting stops when interrupted or when the timeout runs out
+        interrupt.interrupt();
+        let err = sys.udp_recv_from(b, 16, &interrupt).unwrap_err();
+        assert!(err.contains("interrupted"), "{err}");
+        interrupt.reset();
+        sys.udp_set_timeout(b, Some(Duration::from_millis(100)))
+            .unwrap();
+        assert!(sys.udp_recv_from(b, 16, &interrupt).is_err());
+        sys.close(a).unwrap();
+        sys.close(b).unwrap();
+        assert!(sys.udp_addr(a).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn processes() {
+        let sys = NativeSys;
+        let interrupt = InterruptHandle::default();
+        let options = crate::SpawnOptions {
+            env: vec![("CODE".into(), "3".into())],
+            cwd: Some("/".into()),
+        };
+        let [process, stdin, stdout, stderr] =
+            (sys.spawn_process("sh", &["-c", "pwd; exit $CODE"], &options)).unwrap();
+        assert_eq!(sys.wait_process(process, &interrupt).unwrap(), 3);
+        assert_eq!(sys.process_status(process).unwrap(), Some(3));
+        assert_eq!(sys.read_all(stdout).unwrap(), b"/\n");
+        for handle in [process, stdin, stdout, stderr] {
+            sys.close(handle).unwrap();
+        }
+        assert!(sys.wait_process(process, &interrupt).is_err());
+
+        let handles = (sys.spawn_process("sleep", &["10"], &Default::default())).unwrap();
+        let process = handles[0];
+        assert!(sys.process_id(process).unwrap() > 0);
+        assert_eq!(sys.process_status(process).unwrap(), None);
+        interrupt.interrupt();
+        let err = sys.wait_process(process, &interrupt).unwrap_err();
+        assert!(err.contains("interrupted"), "{err}");
+        interrupt.reset();
+        sys.kill_process(process).unwrap();
+        assert_eq!(sys.wait_process(process, &interrupt).unwrap(), -1);
+        for handle in handles {
+            sys.close(handle).unwrap();
+        }
+    }
+}
@@ -0,0 +1,795 @@
+use std::{
+    any::Any,
+    collections::HashSet,
+    net::SocketAddr,
+    path::{Component, Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+#[cfg(feature = "image")]
+use image::DynamicImage;
+
+use crate::{
+    sys::WebcamImage, AudioStreamFn, FfiType, Handle, InterruptHandle, IntoSysBackend,
+    SpawnOptions, SysBackend, SysOpClass, Value,
+};
+
+/// A system backend that restricts what another backend is allowed to do
+///
+/// Each [`SysOpClass`] can be allowed or denied separately.
+/// All classes are denied by default.
+/// Denied calls fail with an error that names the denied class.
+///
+/// Filesystem access can be restricted to a single directory with [`PolicySys::with_root`],
+/// and the amount of data read and written can be capped with
+/// [`PolicySys::with_read_limit`] and [`PolicySys::with_output_limit`].
+/// The memory used for arrays can be capped with [`PolicySys::with_memory_limit`].
+pub struct PolicySys {
+    backend: Arc<dyn SysBackend>,
+    allowed: HashSet<SysOpClass>,
+    root: Option<PathBuf>,
+    read_limit: Option<usize>,
+    output_limit: Option<usize>,
+    memory_limit: Option<usize>,
+    output_len: AtomicUsize,
+}
+
+impl PolicySys {
+    /// Wrap a system backend with a policy that denies everything
+    pub fn new(backend: impl IntoSysBackend) -> Self {
+        PolicySys {
+            backend: backend.into_sys_backend(),
+            allowed: HashSet::new(),
+            root: None,
+            read_limit: None,
+            output_limit: None,
+            memory_limit: None,
+            output_len: AtomicUsize::new(0),
+        }
+    }
+    /// Allow a class of system functions
+    pub fn allow(mut self, class: SysOpClass) -> Self {
+        self.allowed.insert(class);
+        self
+    }
+    /// Allow all classes of system functions
+    pub fn allow_all(mut self) -> Self {
+        self.allowed.extend(SysOpClass::all());
+        self
+    }
+    /// Deny a class of system functions
+    pub fn deny(mut self, class: SysOpClass) -> Self {
+        self.allowed.remove(&class);
+        self
+    }
+    /// Restrict filesystem access to paths inside a directory
+    ///
+    /// Relative paths are resolved against the wrapped backend's current directory.
+    /// Symlinks are followed, so a link inside the directory cannot be used to escape it.
+    pub fn with_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.root = Some(self.resolve_path(&root.into()));
+        self
+    }
+    /// Limit the number of bytes that a single read can return
+    ///
+    /// This applies to reading from files, streams, and stdin.
+    pub fn with_read_limit(mut self, bytes: usize) -> Self {
+        self.read_limit = Some(bytes);
+        self
+    }
+    /// Limit the total number of bytes that can be written
+    ///
+    /// This includes stdout, stderr, files, and streams.
+    pub fn with_output_limit(mut self, bytes: usize) -> Self {
+        self.output_limit = Some(bytes);
+        self
+    }
+    /// Limit the number of bytes that a run can allocate for arrays
+    ///
+    /// Runtimes created with [`Uiua::with_backend`](crate::Uiua::with_backend) use this limit.
+    /// It works like [`Uiua::with_memory_limit`](crate::Uiua::with_memory_limit).
+    pub fn with_memory_limit(mut self, bytes: usize) -> Self {
+        self.memory_limit = Some(bytes);
+        self
+    }
+    /// Check if a class of system functions is allowed
+    pub fn allows(&self, class: SysOpClass) -> bool {
+        self.allowed.contains(&class)
+    }
+    /// Get the wrapped backend
+    pub fn backend(&self) -> &dyn SysBackend {
+        &*self.backend
+    }
+    /// Get the total number of bytes written so far
+    pub fn output_len(&self) -> usize {
+        self.output_len.load(Ordering::Relaxed)
+    }
+    fn check(&self, class: SysOpClass) -> Result<(), String> {
+        if self.allows(class) {
+            Ok(())
+        } else {
+            Err(format!(
+                "{class:?} operations are not allowed in this environment"
+            ))
+        }
+    }
+    fn check_path(&self, path: &Path) -> Result<(), String> {
+        self.check(SysOpClass::Filesystem)?;
+        let Some(root) = &self.root else {
+            return Ok(());
+        };
+        if self.resolve_path(path).starts_with(root) {
+            Ok(())
+        } else {
+            Err(format!(
+                "Access to {} is not allowed because it is outside of {}",
+                path.display(),
+                root.display()
+            ))
+        }
+    }
+    /// Check that git modules can be cloned into a directory
+    ///
+    /// Cloning runs `git`, so it is also a command.
+    fn check_git_target(&self, dir: &Path) -> Result<(), String> {
+        self.check(SysOpClass::Tcp)?;
+        self.check(SysOpClass::Command)?;
+        self.check_path(dir)
+    }
+    /// Make a path absolute and resolve `.`, `..`, and symlinks as the wrapped backend sees them
+    ///
+    /// Symlinks whose targets do not exist yet are still followed,
+    /// because creating a file through one would create its target.
+    fn resolve_path(&self, path: &Path) -> PathBuf {
+        resolve_path_impl(&*self.backend, path, MAX_LINKS)
+    }
+    /// Remove paths that lead outside of the root, such as through symlinks
+    fn inside_root(&self, mut paths: Vec<String>) -> Vec<String> {
+        if self.root.is_some() {
+            paths.retain(|path| self.check_path(path.as_ref()).is_ok());
+        }
+        paths
+    }
+    fn check_output(&self, len: usize) -> Result<(), String> {
+        let Some(limit) = self.output_limit else {
+            self.output_len.fetch_add(len, Ordering::Relaxed);
+            return Ok(());
+        };
+        let prev = self.output_len.fetch_add(len, Ordering::Relaxed);
+        if prev.saturating_add(len) > limit {
+            self.output_len.fetch_sub(len, Ordering::Relaxed);
+            Err(format!("Output limit of {limit} bytes exceeded"))
+        } else {
+            Ok(())
+        }
+    }
+    fn check_read_len(&self, len: usize) -> Result<(), String> {
+        match self.read_limit {
+            Some(limit) if len > limit => Err(format!("Read limit of {limit} bytes exceeded")),
+            _ => Ok(()),
+        }
+    }
+    fn check_read(&self, bytes: Vec<u8>) -> Result<Vec<u8>, String> {
+        self.check_read_len(bytes.len())?;
+        Ok(bytes)
+    }
+    /// The count to pass to the wrapped backend so that going over the limit is detectable
+    fn read_count(&self, count: usize) -> usize {
+        match self.read_limit {
+            Some(limit) => count.min(limit.saturating_add(1)),
+            None => count,
+        }
+    }
+    fn read_all_limited(&self, handle: Handle) -> Result<Vec<u8>, String> {
+        let Some(limit) = self.read_limit else {
+            return self.backend.read_all(handle);
+        };
+        let mut buffer = Vec::new();
+        loop {
+            let count = (limit + 1 - buffer.len()).min(1 << 16);
+            let bytes = self.backend.read(handle, count)?;
+            if bytes.is_empty() {
+                break;
+            }
+            buffer.extend_from_slice(&bytes);
+            if buffer.len() > limit {
+                break;
+            }
+        }
+        self.check_read(buffer)
+    }
+}
+
+/// The maximum number of dangling symlinks followed when resolving a path
+const MAX_LINKS: usize = 40;
+
+fn resolve_path_impl(backend: &dyn SysBackend, path: &Path, links: usize) -> PathBuf {
+    let path = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        // Without a current directory, relative paths start at the filesystem root
+        (backend.current_directory())
+            .unwrap_or_else(|_| PathBuf::from("/"))
+            .join(path)
+    };
+    let mut resolved = PathBuf::new();
+    for comp in path.components() {
+        match comp {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            comp => {
+                resolved.push(comp);
+                if let Some(target) = backend.read_link(&resolved).filter(|_| links > 0) {
+                    resolved.pop();
+                    resolved = resolve_path_impl(backend, &resolved.join(target), links - 1);
+                }
+            }
+        }
+    }
+    resolved
+}
+
+impl SysBackend for PolicySys {
+    fn any(&self) -> &dyn Any {
+        self
+    }
+    fn any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+    fn memory_limit(&self) -> Option<usize> {
+        self.memory_limit.or_else(|| self.backend.memory_limit())
+    }
+    fn save_error_color(&self, message: String, colored: String) {
+        self.backend.save_error_color(message, colored)
+    }
+    fn print_str_stdout(&self, s: &str) -> Result<(), String> {
+        self.check(SysOpClass::StdIO)?;
+        self.check_output(s.len())?;
+        self.backend.print_str_stdout(s)
+    }
+    fn print_str_stderr(&self, s: &str) -> Result<(), String> {
+        self.check(SysOpClass::StdIO)?;
+        self.check_output(s.len())?;
+        self.backend.print_str_stderr(s)
+    }
+    fn print_str_trace(&self, s: &str) {
+        if self.allows(SysOpClass::StdIO) && self.check_output(s.len()).is_ok() {
+            self.backend.print_str_trace(s)
+        }
+    }
+    fn scan_line_stdin(&self) -> Result<Option<String>, String> {
+        self.check(SysOpClass::StdIO)?;
+        let line = self.backend.scan_line_stdin()?;
+        if let Some(line) = &line {
+            self.check_read_len(line.len())?;
+        }
+        Ok(line)
+    }
+    fn scan_stdin(&self, count: usize) -> Result<Vec<u8>, String> {
+        self.check(SysOpClass::StdIO)?;
+        let bytes = self.backend.scan_stdin(self.read_count(count))?;
+        self.check_read(bytes)
+    }
+    fn scan_until_stdin(&self, delim: &[u8]) -> Result<Vec<u8>, String> {
+        self.check(SysOpClass::StdIO)?;
+        if self.read_limit.is_none() {
+            return self.backend.scan_until_stdin(delim);
+        }
+        let mut buffer = Vec::new();
+        loop {
+            let bytes = self.backend.scan_stdin(1)?;
+            if bytes.is_empty() {
+                break;
+            }
+            buffer.extend_from_slice(&bytes);
+            if buffer.ends_with(delim) {
+                break;
+            }
+            self.check_read_len(buffer.len())?;
+        }
+        Ok(buffer)
+    }
+    fn set_raw_mode(&self, raw_mode: bool) -> Result<(), String> {
+        self.check(SysOpClass::Env)?;
+        self.backend.set_raw_mode(raw_mode)
+    }
+    fn var(&self, name: &str) -> Option<String> {
+        if self.allows(SysOpClass::Env) {
+            self.backend.var(name)
+        } else {
+            None
+        }
+    }
+    fn term_size(&self) -> Result<(usize, usize), String> {
+        self.check(SysOpClass::Env)?;
+        self.backend.term_size()
+    }
+    fn exit(&self, status: i32) -> Result<(), String> {
+        self.check(SysOpClass::Misc)?;
+        self.backend.exit(status)
+    }
+    fn file_exists(&self, path: &str) -> bool {
+        self.check_path(path.as_ref()).is_ok() && self.backend.file_exists(path)
+    }
+    fn list_dir(&self, path: &str) -> Result<Vec<String>, String> {
+        self.check_path(path.as_ref())?;
+        let paths = self.backend.list_dir(path)?;
+        Ok(self.inside_root(paths))
+    }
+    fn is_file(&self, path: &str) -> Result<bool, String> {
+        self.check_path(path.as_ref())?;
+        self.backend.is_file(path)
+    }
+    fn delete(&self, path: &str) -> Result<(), String> {
+        self.check_path(path.as_ref())?;
+        self.backend.delete(path)
+    }
+    fn trash(&self, path: &str) -> Result<(), String> {
+        self.check_path(path.as_ref())?;
+        self.backend.trash(path)
+    }
+    fn create_dir(&self, path: &str) -> Result<(), String> {
+        self.check_path(path.as_ref())?;
+        self.backend.create_dir(path)
+    }
+    fn rename(&self, from: &str, to: &str) -> Result<(), String> {
+        self.check_path(from.as_ref())?;
+        self.check_path(to.as_ref())?;
+        self.backend.rename(from, to)
+    }
+    fn copy_file(&self, from: &str, to: &str) -> Result<(), String> {
+        self.check_path(from.as_ref())?;
+        self.check_path(to.as_ref())?;
+        let size = self.backend.file_size(from).unwrap_or(0);
+        self.check_read_len(size as usize)?;
+        self.check_output(size as usize)?;
+        self.backend.copy_file(from, to)
+    }
+    fn file_size(&self, path: &str) -> Result<u64, String> {
+        self.check_path(path.as_ref())?;
+        self.backend.file_size(path)
+    }
+    fn modified_time(&self, path: &str) -> Result<f64, String> {
+        self.check_path(path.as_ref())?;
+        self.backend.modified_time(path)
+    }
+    fn walk_dir(&self, path: &str) -> Result<Vec<String>, String> {
+        self.check_path(path.as_ref())?;
+        let paths = self.backend.walk_dir(path)?;
+        Ok(self.inside_root(paths))
+    }
+    fn read(&self, handle: Handle, count: usize) -> Result<Vec<u8>, String> {
+        self.check(SysOpClass::Stream)?;
+        let bytes = self.backend.read(handle, self.read_count(count))?;
+        self.check_read(bytes)
+    }
+    fn read_all(&self, handle: Handle) -> Result<Vec<u8>, String> {
+        self.check(SysOpClass::Stream)?;
+        self.read_all_limited(handle)
+    }
+    fn read_until(&self, handle: Handle, delim: &[u8]) -> Result<Vec<u8>, String> {
+        self.check(SysOpClass::Stream)?;
+        if self.read_limit.is_none() {
+            return self.backend.read_until(handle, delim);
+        }
+        let mut buffer = Vec::new();
+        loop {
+            let bytes = self.backend.read(handle, 1)?;
+            if bytes.is_empty() {
+                break;
+            }
+            buffer.extend_from_slice(&bytes);
+            if buffer.ends_with(delim) {
+                break;
+            }
+            self.check_read_len(buffer.len())?;
+        }
+        Ok(buffer)
+    }
+    fn write(&self, handle: Handle, contents: &[u8]) -> Result<(), String> {
+        self.check(SysOpClass::Stream)?;
+        self.check_output(contents.len())?;
+        self.backend.write(handle, contents)
+    }
+    fn create_file(&self, path: &Path) -> Result<Handle, String> {
+        self.check_path(path)?;
+        self.backend.create_file(path)
+    }
+    fn open_file(&self, path: &Path, write: bool) -> Result<Handle, String> {
+        self.check_path(path)?;
+        self.backend.open_file(path, write)
+    }
+    fn file_read_all(&self, path: &Path) -> Result<Vec<u8>, String> {
+        self.check_path(path)?;
+        if self.read_limit.is_none() {
+            return self.backend.file_read_all(path);
+        }
+        let handle = self.backend.open_file(path, false)?;
+        let bytes = self.read_all_limited(handle);
+        self.backend.close(handle)?;
+        bytes
+    }
+    fn file_write_all(&self, path: &Path, contents: &[u8]) -> Result<(), String> {
+        self.check_path(path)?;
+        self.check_output(contents.len())?;
+        self.backend.file_write_all(path, contents)
+    }
+    fn clipboard(&self) -> Result<String, String> {
+        self.check(SysOpClass::Misc)?;
+        self.backend.clipboard()
+    }
+    fn set_clipboard(&self, contents: &str) -> Result<(), String> {
+        self.check(SysOpClass::Misc)?;
+        self.backend.set_clipboard(contents)
+    }
+    fn sleep(&self, seconds: f64) -> Result<(), String> {
+        self.check(SysOpClass::Misc)?;
+        self.backend.sleep(seconds)
+    }
+    #[cfg(feature = "image")]
+    fn show_image(&self, image: DynamicImage) -> Result<(), String> {
+        self.check(SysOpClass::Images)?;
+        self.backend.show_image(image)
+    }
+    fn show_gif(&self, gif_bytes: Vec<u8>) -> Result<(), String> {
+        self.check(SysOpClass::Gifs)?;
+        self.backend.show_gif(gif_bytes)
+    }
+    fn play_audio(&self, wave_bytes: Vec<u8>) -> Result<(), String> {
+        self.check(SysOpClass::Audio)?;
+        self.backend.play_audio(wave_bytes)
+    }
+    fn audio_sample_rate(&self) -> u32 {
+        self.backend.audio_sample_rate()
+    }
+    fn stream_audio(&self, f: AudioStreamFn) -> Result<(), String> {
+        self.check(SysOpClass::Audio)?;
+        self.backend.stream_audio(f)
+    }
+    fn tcp_listen(&self, addr: &str) -> Result<Handle, String> {
+        self.check(SysOpClass::Tcp)?;
+        self.backend.tcp_listen(addr)
+    }
+    fn tls_listen(&self, addr: &str, cert: &[u8], key: &[u8]) -> Result<Handle, String> {
+        self.check(SysOpClass::Tcp)?;
+        self.backend.tls_listen(addr, cert, key)
+    }
+    fn tcp_accept(&self, handle: Handle) -> Result<Handle, String> {
+        self.check(SysOpClass::Tcp)?;
+        self.backend.tcp_accept(handle)
+    }
+    fn tcp_connect(&self, addr: &str) -> Result<Handle, String> {
+        self.check(SysOpClass::Tcp)?;
+        self.backend.tcp_connect(addr)
+    }
+    fn tls_connect(&self, addr: &str) -> Result<Handle, String> {
+        self.check(SysOpClass::Tcp)?;
+        self.backend.tls_connect(addr)
+    }
+    fn tcp_addr(&self, handle: Handle) -> Result<SocketAddr, String> {
+        self.check(SysOpClass::Tcp)?;
+        self.backend.tcp_addr(handle)
+    }
+    fn tcp_set_non_blocking(&self, handle: Handle, non_blocking: bool) -> Result<(), String> {
+        self.check(SysOpClass::Tcp)?;
+        self.backend.tcp_set_non_blocking(handle, non_blocking)
+    }
+    fn tcp_set_read_timeout(
+        &self,
+        handle: Handle,
+        timeout: Option<Duration>,
+    ) -> Result<(), String> {
+        self.check(SysOpClass::Tcp)?;
+        self.backend.tcp_set_read_timeout(handle, timeout)
+    }
+    fn tcp_set_write_timeout(
+        &self,
+        handle: Handle,
+        timeout: Option<Duration>,
+    ) -> Result<(), String> {
+        self.check(SysOpClass::Tcp)?;
+        self.backend.tcp_set_write_timeout(handle, timeout)
+    }
+    fn udp_bind(&self, addr: &str) -> Result<Handle, String> {
+        self.check(SysOpClass::Udp)?;
+        self.backend.udp_bind(addr)
+    }
+    fn udp_addr(&self, handle: Handle) -> Result<SocketAddr, String> {
+        self.check(SysOpClass::Udp)?;
+        self.backend.udp_addr(handle)
+    }
+    fn udp_send_to(&self, handle: Handle, data: &[u8], addr: &str) -> Result<(), String> {
+        self.check(SysOpClass::Udp)?;
+        self.check_output(data.len())?;
+        self.backend.udp_send_to(handle, data, addr)
+    }
+    fn udp_recv_from(
+        &self,
+        handle: Handle,
+        max_len: usize,
+        interrupt: &InterruptHandle,
+    ) -> Result<(Vec<u8>, SocketAddr), String> {
+        self.check(SysOpClass::Udp)?;
+        let max_len = self.read_count(max_len);
+        let (bytes, addr) = (self.backend).udp_recv_from(handle, max_len, interrupt)?;
+        self.check_read_len(bytes.len())?;
+        Ok((bytes, addr))
+    }
+    fn udp_set_timeout(&self, handle: Handle, timeout: Option<Duration>) -> Result<(), String> {
+        self.check(SysOpClass::Udp)?;
+        self.backend.udp_set_timeout(handle, timeout)
+    }
+    fn close(&self, handle: Handle) -> Result<(), String> {
+        self.check(SysOpClass::Stream)?;
+        self.backend.close(handle)
+    }
+    fn invoke(&self, path: &str) -> Result<(), String> {
+        self.check(SysOpClass::Command)?;
+        self.backend.invoke(path)
+    }
+    fn run_command_inherit(&self, command: &str, args: &[&str]) -> Result<i32, String> {
+        self.check(SysOpClass::Command)?;
+        self.backend.run_command_inherit(command, args)
+    }
+    fn run_command_capture(
+        &self,
+        command: &str,
+        args: &[&str],
+    ) -> Result<(i32, String, String), String> {
+        self.check(SysOpClass::Command)?;
+        self.backend.run_command_capture(command, args)
+    }
+    fn run_command_stream(&self, command: &str, args: &[&str]) -> Result<[Handle; 3], String> {
+        self.check(SysOpClass::Command)?;
+        self.backend.run_command_stream(command, args)
+    }
+    fn spawn_process(
+        &self,
+        command: &str,
+        args: &[&str],
+        options: &SpawnOptions,
+    ) -> Result<[Handle; 4], String> {
+        self.check(SysOpClass::Command)?;
+        self.backend.spawn_process(command, args, options)
+    }
+    fn wait_process(&self, handle: Handle, interrupt: &InterruptHandle) -> Result<i32, String> {
+        self.check(SysOpClass::Command)?;
+        self.backend.wait_process(handle, interrupt)
+    }
+    fn kill_process(&self, handle: Handle) -> Result<(), String> {
+        self.check(SysOpClass::Command)?;
+        self.backend.kill_process(handle)
+    }
+    fn process_status(&self, handle: Handle) -> Result<Option<i32>, String> {
+        self.check(SysOpClass::Command)?;
+        self.backend.process_status(handle)
+    }
+    fn process_id(&self, handle: Handle) -> Result<u32, String> {
+        self.check(SysOpClass::Command)?;
+        self.backend.process_id(handle)
+    }
+    fn change_directory(&self, path: &str) -> Result<(), String> {
+        self.check_path(path.as_ref())?;
+        self.backend.change_directory(path)
+    }
+    fn current_directory(&self) -> Result<PathBuf, String> {
+        self.check(SysOpClass::Filesystem)?;
+        self.backend.current_directory()
+    }
+    fn read_link(&self, path: &Path) -> Option<PathBuf> {
+        self.check_path(path).ok()?;
+        self.backend.read_link(path)
+    }
+    fn https_get(&self, request: &str, handle: Handle) -> Result<String, String> {
+        self.check(SysOpClass::Tcp)?;
+        self.check_output(request.len())?;
+        let response = self.backend.https_get(request, handle)?;
+        self.check_read_len(response.len())?;
+        Ok(response)
+    }
+    fn webcam_capture(&self, index: usize) -> Result<WebcamImage, String> {
+        self.check(SysOpClass::Misc)?;
+        self.backend.webcam_capture(index)
+    }
+    fn ffi(
+        &self,
+        file: &str,
+        result_ty: FfiType,
+        name: &str,
+        arg_tys: &[FfiType],
+        args: &[Value],
+    ) -> Result<Value, String> {
+        self.check(SysOpClass::Ffi)?;
+        self.check_path(file.as_ref())?;
+        self.backend.ffi(file, result_ty, name, arg_tys, args)
+    }
+    fn mem_copy(&self, ty: FfiType, ptr: *const (), len: usize) -> Result<Value, String> {
+        self.check(SysOpClass::Ffi)?;
+        self.backend.mem_copy(ty, ptr, len)
+    }
+    fn mem_free(&self, ptr: *const ()) -> Result<(), String> {
+        self.check(SysOpClass::Ffi)?;
+        self.backend.mem_free(ptr)
+    }
+    fn load_git_module(&self, url: &str, branch: Option<&str>) -> Result<PathBuf, String> {
+        // Modules are cloned into the current directory
+        self.check_git_target(Path::new("."))?;
+        self.backend.load_git_module(url, branch)
+    }
+    fn load_project_git_module(
+        &self,
+        url: &str,
+        branch: Option<&str>,
+        project: &Path,
+    ) -> Result<PathBuf, String> {
+        // Modules that are not pinned are cloned into the current directory
+        self.check_git_target(project)?;
+        self.check_git_target(Path::new("."))?;
+        self.backend.load_project_git_module(url, branch, project)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::MemSys;
+
+    fn mem_policy(mem: MemSys) -> PolicySys {
+        PolicySys::new(mem).allow_all().with_root("/sandbox")
+    }
+
+    #[test]
+    fn denied_classes() {
+        let sys = PolicySys::new(MemSys::new().with_file("/a.txt", "a"));
+        let err = sys.print_str_stdout("hi").unwrap_err();
+        assert!(err.contains("StdIO"), "{err}");
+        assert!(sys.file_read_all("/a.txt".as_ref()).is_err());
+        assert!(!sys.file_exists("/a.txt"));
+        assert_eq!(sys.var("HOME"), None);
+        let sys = sys.allow(SysOpClass::Filesystem);
+        assert_eq!(sys.file_read_all("/a.txt".as_ref()).unwrap(), b"a");
+        assert!(sys.print_str_stdout("hi").is_err());
+        let sys = sys.allow_all().deny(SysOpClass::Filesystem);
+        assert!(sys.file_read_all("/a.txt".as_ref()).is_err());
+        assert!(sys.print_str_stdout("hi").is_ok());
+    }
+
+    #[test]
+    fn parent_dir_escape() {
+        let sys = mem_policy(
+            MemSys::new()
+                .with_file("/sandbox/in.txt", "in")
+                .with_file("/out.txt", "out"),
+        );
+        assert_eq!(
+            sys.file_read_all("/sandbox/in.txt".as_ref()).unwrap(),
+            b"in"
+        );
+        assert!(sys.file_read_all("/sandbox/../out.txt".as_ref()).is_err());
+        assert!(sys
+            .file_read_all("/sandbox/x/../../out.txt".as_ref())
+            .is_err());
+        assert!(sys.file_write_all("/out.txt".as_ref(), b"x").is_err());
+        assert!(sys.rename("/sandbox/in.txt", "/sandbox/../in.txt").is_err());
+        assert!(sys.file_read_all("/sandbox/x/../in.txt".as_ref()).is_ok());
+    }
+
+    #[cfg(all(unix, feature = "native_sys"))]
+    #[test]
+    fn symlink_escape() {
+        use std::{env, fs, os::unix::fs::symlink};
+        let dir = env::temp_dir().join(format!("uiua-policy-test-{}", std::process::id()));
+        let (root, outside) = (dir.join("root"), dir.join("outside"));
+        fs::create_dir_all(&root).unwrap();
+        fs::create_dir_all(&outside).unwrap();
+        fs::write(root.join("in.txt"), "in").unwrap();
+        fs::write(outside.join("secret.txt"), "secret").unwrap();
+        symlink(&outside, root.join("dir_link")).unwrap();
+        symlink(outside.join("secret.txt"), root.join("file_link")).unwrap();
+        symlink(outside.join("new.txt"), root.join("dangling")).unwrap();
+
+        let sys = PolicySys::new(crate::NativeSys)
+            .allow_all()
+            .with_root(&root);
+        let path = |name: &str| root.join(name);
+        assert!(sys.file_read_all(&path("in.txt")).is_ok());
+        assert!(sys.file_read_all(&path("dir_link/secret.txt")).is_err());
+        assert!(sys.file_read_all(&path("file_link")).is_err());
+        assert!(sys.file_write_all(&path("dangling"), b"x").is_err());
+        assert!(!outside.join("new.txt").exists());
+        let walked = sys.walk_dir(&root.to_string_lossy()).unwrap();
+        assert_eq!(walked, [path("in.txt").to_string_lossy()]);
+        let listed = sys.list_dir(&root.to_string_lossy()).unwrap();
+        assert_eq!(listed, [path("in.txt").to_string_lossy()]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn relative_paths() {
+        let sys = mem_policy(
+            MemSys::new()
+                .with_file("/sandbox/in.txt", "in")
+                .with_file("/out.txt", "out"),
+        );
+        // Relative paths are resolved against the wrapped backend's current directory
+        assert!(sys.file_read_all("in.txt".as_ref()).is_err());
+        assert_eq!(sys.file_read_all("sandbox/in.txt".as_ref()).unwrap(), b"in");
+        sys.change_directory("/sandbox").unwrap();
+        assert_eq!(sys.file_read_all("in.txt".as_ref()).unwrap(), b"in");
+        assert!(sys.file_read_all("../out.txt".as_ref()).is_err());
+        assert!(sys.change_directory("..").is_err());
+    }
+
+    #[test]
+    fn git_modules() {
+        let sys = mem_policy(MemSys::new().with_dir("/sandbox/project"));
+        let err = sys.load_git_module("owner/repo", None).unwrap_err();
+        assert!(err.contains("outside"), "{err}");
+        let project = Path::new("/sandbox/project");
+        let err = (sys.load_project_git_module("owner/repo", None, project)).unwrap_err();
+        assert!(err.contains("outside"), "{err}");
+        sys.change_directory("/sandbox").unwrap();
+        let err = (sys.load_project_git_module("owner/repo", None, "/".as_ref())).unwrap_err();
+        assert!(err.contains("outside"), "{err}");
+        let sys = sys.deny(SysOpClass::Command);
+        let err = sys.load_git_module("owner/repo", None).unwrap_err();
+        assert!(err.contains("Command"), "{err}");
+    }
+
+    #[test]
+    fn output_limit() {
+        let mem = Arc::new(MemSys::new().with_dir("/sandbox"));
+        let sys = PolicySys::new(mem.clone() as Arc<dyn SysBackend>)
+            .allow_all()
+            .with_output_limit(5);
+        sys.print_str_stdout("abc").unwrap();
+        assert!(sys.print_str_stderr("def").is_err());
+        sys.print_str_stderr("de").unwrap();
+        assert!(sys.file_write_all("/sandbox/a.txt".as_ref(), b"x").is_err());
+        assert_eq!(sys.output_len(), 5);
+        assert_eq!(mem.take_stdout(), b"abc");
+        assert_eq!(mem.take_stderr(), b"de");
+        assert_eq!(mem.file("/sandbox/a.txt"), None);
+    }
+
+    #[test]
+    fn read_limit() {
+        let mem = MemSys::new()
+            .with_file("/sandbox/small.txt", "1234")
+            .with_file("/sandbox/big.txt", "123456")
+            .with_stdin("ab\nabcdef\n");
+        let sys = mem_policy(mem).with_read_limit(4);
+        assert_eq!(
+            sys.file_read_all("/sandbox/small.txt".as_ref()).unwrap(),
+            b"1234"
+        );
+        let err = sys.file_read_all("/sandbox/big.txt".as_ref()).unwrap_err();
+        assert!(err.contains("Read limit"), "{err}");
+        let handle = sys.open_file("/sandbox/big.txt".as_ref(), false).unwrap();
+        assert!(sys.read(handle, 6).is_err());
+        assert_eq!(sys.scan_until_stdin(b"\n").unwrap(), b"ab\n");
+        assert!(sys.scan_until_stdin(b"\n").is_err());
+    }
+
+    #[test]
+    fn memory_limit() {
+        use crate::{Uiua, UiuaErrorKind};
+        let mut env = Uiua::with_backend(mem_policy(MemSys::new()).with_memory_limit(1 << 20));
+        env.run_str("⇡1e4").unwrap();
+        let Err(err) = env.run_str("⇡1e6") else {
+            panic!("expected a memory limit error");
+        };
+        assert!(matches!(err.kind, UiuaErrorKind::MemoryLimit(..)), "{err}");
+        // Wrapping does not lose the limit
+        let sys = PolicySys::new(mem_policy(MemSys::new()).with_memory_limit(5));
+        assert_eq!(sys.memory_limit(), Some(5));
+        assert_eq!(mem_policy(MemSys::new()).memory_limit(), None);
+    }
+}
@@ -0,0 +1,265 @@
+//! Calendar conversions for the time system functions
+//!
+//! Timestamps are seconds since the Unix epoch in UTC, like the ones returned by `now`.
+
+use chrono::{
+    format::{Item, StrftimeItems},
+    DateTime, Datelike, NaiveDate, NaiveDateTime, Timelike, Utc,
+};
+use ecow::EcoVec;
+
+use crate::{Array, Boxed, Uiua, UiuaResult, Value};
+
+/// Split timestamps into `[year month day hour minute second]`
+pub(crate) fn to_parts(value: &Value, env: &Uiua) -> UiuaResult<Value> {
+    let times = timestamps(value, env)?;
+    let mut shape = times.shape().clone();
+    shape.push(6);
+    let mut data = EcoVec::with_capacity(times.element_count() * 6);
+    for &time in &times.data {
+        let dt = to_datetime(time, env)?;
+        data.extend([
+            dt.year() as f64,
+            dt.month() as f64,
+            dt.day() as f64,
+            dt.hour() as f64,
+            dt.minute() as f64,
+            dt.second() as f64 + (time - time.floor()),
+        ]);
+    }
+    Ok(Array::new(shape, data).into())
+}
+
+/// Build timestamps from lists of up to 6 parts
+///
+/// Parts that are out of range carry over into the next larger part.
+pub(crate) fn from_parts(value: &Value, env: &Uiua) -> UiuaResult<Value> {
+    let parts = value.as_number_array(
+        env,
+        "Date parts must be finite numbers",
+        |_| true,
+        f64::is_finite,
+        |n| n,
+    )?;
+    let mut shape = parts.shape().clone();
+    let len = if shape.is_empty() {
+        1
+    } else {
+        shape.pop().unwrap()
+    };
+    if !(1..=6).contains(&len) {
+        return Err(env.error(format!(
+            "Date parts must have between 1 and 6 elements, but there are {len}"
+        )));
+    }
+    let mut data = EcoVec::with_capacity(shape.elements());
+    for chunk in parts.data.chunks_exact(len) {
+        let mut full = [0.0, 1.0, 1.0, 0.0, 0.0, 0.0];
+        full[..len].copy_from_slice(chunk);
+        let [year, month, day, hour, minute, second] = full;
+        let month = month.floor() - 1.0;
+        let year = year.floor() + (month / 12.0).floor();
+        let month = month.rem_euclid(12.0) + 1.0;
+        let date = (year.abs() < i32::MAX as f64)
+            .then(|| NaiveDate::from_ymd_opt(year as i32, month as u32, 1))
+            .flatten()
+            .ok_or_else(|| env.error(format!("Year {year} is out of range")))?;
+        let start = date.and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp() as f64;
+        data.push(start + (day - 1.0) * 86400.0 + hour * 3600.0 + minute * 60.0 + second);
+    }
+    Ok(Array::new(shape, data).into())
+}
+
+/// Format timestamps with a strftime-style pattern
+pub(crate) fn format(pattern: &str, value: &Value, env: &Uiua) -> UiuaResult<Value> {
+    let items: Vec<Item> = StrftimeItems::new(pattern).collect();
+    if items.contains(&Item::Error) {
+        return Err(env.error(format!("Invalid date format pattern {pattern:?}")));
+    }
+    let times = timestamps(value, env)?;
+    let mut strings = EcoVec::with_capacity(times.element_count());
+    for &time in &times.data {
+        let dt = to_datetime(time, env)?;
+        strings.push(dt.format_with_items(items.iter()).to_string());
+    }
+    Ok(if times.rank() == 0 {
+        strings.remove(0).into()
+    } else {
+        let boxes: EcoVec<Boxed> = strings.into_iter().map(|s| Boxed(s.into())).collect();
+        Array::new(times.shape().clone(), boxes).into()
+    })
+}
+
+/// Parse strings into timestamps with a strftime-style pattern
+///
+/// If the pattern has no time zone, the time is assumed to be UTC.
+/// If the pattern has no time of day, the time is midnight.
+pub(crate) fn parse(pattern: &str, value: &Value, env: &Uiua) -> UiuaResult<Value> {
+    let parse = |s: &str| -> UiuaResult<f64> {
+        if let Ok(dt) = DateTime::parse_from_str(s, pattern) {
+            return Ok(to_timestamp(dt.with_timezone(&Utc)));
+        }
+        match NaiveDateTime::parse_from_str(s, pattern) {
+            Ok(dt) => Ok(to_timestamp(dt.and_utc())),
+            Err(e) => match NaiveDate::parse_from_str(s, pattern) {
+                Ok(date) => Ok(to_timestamp(date.and_hms_opt(0, 0, 0).unwrap().and_utc())),
+                Err(_) => Err(env.error(format!(
+                    "Failed to parse {s:?} with pattern {pattern:?}: {e}"
+                ))),
+            },
+        }
+    };
+    match value {
+        Value::Char(_) => Ok(parse(&value.as_string(env, "Date must be a string")?)?.into()),
+        Value::Box(boxes) => {
+            let mut data = EcoVec::with_capacity(boxes.element_count());
+            for Boxed(s) in &boxes.data {
+                data.push(parse(&s.as_string(env, "Dates must be strings")?)?);
+            }
+            Ok(Array::new(boxes.shape().clone(), data).into())
+        }
+        value => Err(env.error(format!(
+            "Date must be a string or box array of strings, but it is {}",
+            value.type_name_plural()
+        ))),
+    }
+}
+
+/// Shift timestamps by a time zone offset
+///
+/// The offset is either a number of hours or a string like `+05:30` or `UTC-8`.
+pub(crate) fn offset(offset: &Value, value: &Value, env: &Uiua) -> UiuaResult<Value> {
+    let seconds = match offset {
+        Value::Char(_) => {
+            let offset = offset.as_string(env, "Time zone offset must be a string")?;
+            parse_offset(&offset)
+                .ok_or_else(|| env.error(format!("Invalid time zone offset {offset:?}")))?
+        }
+        offset => {
+            offset.as_num(
+                env,
+                "Time zone offset must be a number of hours or a string",
+            )? * 3600.0
+        }
+    };
+    let mut times = timestamps(value, env)?;
+    for time in times.data.as_mut_slice() {
+        *time += seconds;
+    }
+    Ok(times.into())
+}
+
+fn parse_offset(s: &str) -> Option<f64> {
+    let s = s.trim();
+    let upper = s.to_ascii_uppercase();
+    let rest = if let Some(rest) = upper.strip_prefix("UTC").or(upper.strip_prefix("GMT")) {
+        rest
+    } else if upper == "Z" {
+        ""
+    } else {
+        &upper
+    };
+    if rest.is_empty() {
+        return Some(0.0);
+    }
+    let sign = match rest.as_bytes()[0] {
+        b'+' => 1.0,
+        b'-' => -1.0,
+        _ => return None,
+    };
+    let rest = &rest[1..];
+    if !rest.bytes().all(|b| b.is_ascii_digit() || b == b':') {
+        return None;
+    }
+    let (hours, minutes) = match rest.split_once(':') {
+        Some((hours, minutes)) => (hours, minutes),
+        None if rest.len() > 2 => rest.split_at(rest.len() - 2),
+        None => (rest, "0"),
+    };
+    let hours: u8 = hours.parse().ok()?;
+    let minutes: u8 = minutes.parse().ok()?;
+    if hours > 23 || minutes > 59 {
+        return None;
+    }
+    Some(sign * (hours as f64 * 3600.0 + minutes as f64 * 60.0))
+}
+
+fn timestamps(value: &Value, env: &Uiua) -> UiuaResult<Array<f64>> {
+    value.as_number_array(
+        env,
+        "Timestamps must be finite numbers",
+        |_| true,
+        f64::is_finite,
+        |n| n,
+    )
+}
+
+fn to_datetime(time: f64, env: &Uiua) -> UiuaResult<DateTime<Utc>> {
+    let secs = time.floor();
+    let nanos = ((time - secs) * 1e9).min(999_999_999.0) as u32;
+    (secs.abs() < i64::MAX as f64)
+        .then(|| DateTime::from_timestamp(secs as i64, nanos))
+        .flatten()
+        .ok_or_else(|| env.error(format!("Timestamp {time} is out of range")))
+}
+
+fn to_timestamp(dt: DateTime<Utc>) -> f64 {
+    dt.timestamp() as f64 + dt.timestamp_subsec_nanos() as f64 / 1e9
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn run(code: &str) -> Vec<Value> {
+        let mut env = Uiua::with_safe_sys();
+        env.run_str(code).unwrap_or_else(|e| panic!("{e}"));
+        env.take_stack()
+    }
+
+    #[test]
+    fn parts_round_trip() {
+        let [time, parts] = <[Value; 2]>::try_from(run("⊃∘&dtb &dt 1700000000.25")).unwrap();
+        assert_eq!(time, Value::from(1700000000.25));
+        assert_eq!(
+            parts,
+            Value::from_iter([2023.0, 11.0, 14.0, 22.0, 13.0, 20.25])
+        );
+        // Out of range parts carry over
+        assert_eq!(run("&dtb [2023 14 31]"), run("&dtb [2024 3 2]"));
+        assert_eq!(run("&dt ¯0.5"), run("[1969 12 31 23 59 59.5]"));
+    }
+
+    #[test]
+    fn format_and_parse() {
+        assert_eq!(
+            run(r#"&dtf "%F %T" 1700000000"#),
+            [Value::from("2023-11-14 22:13:20")]
+        );
+        let parsed = run(r#"&dtp "%F %T" "2023-11-14 22:13:20""#);
+        assert_eq!(parsed, [Value::from(1700000000.0)]);
+        let parsed = run(r#"&dtp "%FT%T%z" "2023-11-15T00:13:20+0200""#);
+        assert_eq!(parsed, [Value::from(1700000000.0)]);
+        let parsed = run(r#"&dtp "%F" {"2023-11-14" "1970-01-02"}"#);
+        assert_eq!(parsed, [Value::from_iter([1699920000.0, 86400.0])]);
+        let mut env = Uiua::with_safe_sys();
+        assert!(env.run_str(r#"&dtp "%F" "14/11/2023""#).is_err());
+        assert!(env.run_str(r#"&dtf "%Q" 0"#).is_err());
+    }
+
+    #[test]
+    fn offsets() {
+        assert_eq!(parse_offset("UTC"), Some(0.0));
+        assert_eq!(parse_offset("Z"), Some(0.0));
+        assert_eq!(parse_offset("+05:30"), Some(19800.0));
+        assert_eq!(parse_offset("-0800"), Some(-28800.0));
+        assert_eq!(parse_offset("utc+1"), Some(3600.0));
+        assert_eq!(parse_offset("+24"), None);
+        assert_eq!(parse_offset("5"), None);
+        assert_eq!(run(r#"&dtz "+01:00" 0"#), run("&dtz 1 0"));
+        assert_eq!(parse_offset("+éé1"), None);
+        assert_eq!(parse_offset("++5"), None);
+        let mut env = Uiua::with_safe_sys();
+        assert!(env.run_str(r#"&dtz "+éé1" 0"#).is_err());
+    }
+}
@@ -0,0 +1,601 @@
+//! Structured test results
+
+use std::{fmt::Write, mem::take, ops::Range, path::Path, time::Duration};
+
+use rayon::prelude::*;
+
+use crate::{
+    Assembly, CodeSpan, FuncSlice, InputSrc, Report, Span, Uiua, UiuaError, UiuaErrorKind, Value,
+};
+
+/// A test scope's place in the top-level code
+#[derive(Debug, Clone)]
+pub(crate) struct TestScope {
+    /// The name given by a comment on the opening `---`
+    pub name: Option<String>,
+    /// The span of the whole scope
+    pub span: CodeSpan,
+    /// The indices of the scope's top-level slices
+    pub slices: Range<usize>,
+}
+
+/// Options for [`Uiua::run_tests`]
+#[derive(Debug, Clone, Default)]
+pub struct TestOptions {
+    /// Only run test scopes whose names contain this pattern
+    pub filter: Option<String>,
+    /// Run test scopes in parallel, each on its own runtime
+    ///
+    /// Each scope sees the state left by the code before it,
+    /// but not the effects of other scopes.
+    pub parallel: bool,
+}
+
+impl TestOptions {
+    fn matches(&self, name: &str) -> bool {
+        (self.filter.as_ref()).map_or(true, |filter| name.contains(filter.as_str()))
+    }
+}
+
+/// The kind of a [`TestCase`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TestCaseKind {
+    /// A `---` test scope
+    Scope,
+    /// A single `assert`
+    Assert,
+    /// Code outside of test scopes
+    ///
+    /// This is only reported if it fails.
+    TopLevel,
+}
+
+impl TestCaseKind {
+    /// The name used for the kind in test reports
+    pub fn name(&self) -> &'static str {
+        match self {
+            TestCaseKind::Scope => "scope",
+            TestCaseKind::Assert => "assert",
+            TestCaseKind::TopLevel => "top level",
+        }
+    }
+}
+
+/// The result of running a test scope or an assertion
+#[derive(Debug, Clone)]
+pub struct TestCase {
+    /// The name of the test
+    pub name: String,
+    /// Whether this is a scope or an assertion
+    pub kind: TestCaseKind,
+    /// The span of the test
+    pub span: CodeSpan,
+    /// The error message if the test failed
+    pub error: Option<String>,
+    /// The full error report if the test failed
+    ///
+    /// Failed assertions only have a message.
+    /// Their report is on the scope or top-level case that they ended.
+    pub report: Option<Report>,
+    /// The expected value, if it is known
+    pub expected: Option<Value>,
+    /// The actual value, if it is known
+    pub actual: Option<Value>,
+    /// How long the test took
+    pub duration: Duration,
+}
+
+impl TestCase {
+    /// Check if the test passed
+    pub fn passed(&self) -> bool {
+        self.error.is_none()
+    }
+    /// Create a failed [`TestCaseKind::TopLevel`] case from an error
+    ///
+    /// This can also be used to report errors that happen before the tests run,
+    /// like compilation errors.
+    pub fn top_level(error: &UiuaError, duration: Duration) -> Self {
+        let span = match &error.kind {
+            UiuaErrorKind::Run(message, _) => message.span.clone().code(),
+            UiuaErrorKind::Parse(errors, _) => errors.first().map(|e| e.span.clone()),
+            UiuaErrorKind::Throw(_, span, _)
+            | UiuaErrorKind::Timeout(span, _)
+            | UiuaErrorKind::MemoryLimit(span, _)
+            | UiuaErrorKind::Interrupted(span, _) => span.clone().code(),
+            _ => None,
+        };
+        TestCase {
+            name: "top level".into(),
+            kind: TestCaseKind::TopLevel,
+            span: span.unwrap_or_else(CodeSpan::dummy),
+            error: Some(error.to_string()),
+            report: Some(error.report()),
+            expected: None,
+            actual: None,
+            duration,
+        }
+    }
+}
+
+/// Render test results as a JSON report
+///
+/// `path` is the file that was tested.
+pub fn json_test_report(path: &Path, cases: &[TestCase], duration: Duration) -> String {
+    let cases: Vec<_> = (cases.iter())
+        .map(|case| {
+            serde_json::json!({
+                "name": case.name,
+                "kind": case.kind.name(),
+                "file": test_case_file(path, case),
+                "start": { "line": case.span.start.line, "column": case.span.start.col },
+                "end": { "line": case.span.end.line, "column": case.span.end.col },
+                "passed": case.passed(),
+                "error": case.error,
+                "expected": case.expected.as_ref().map(Value::show),
+                "actual": case.actual.as_ref().map(Value::show),
+                "duration": case.duration.as_secs_f64(),
+            })
+        })
+        .collect();
+    let report = serde_json::json!({
+        "file": path.display().to_string(),
+        "tests": cases.len(),
+        "failures": cases.iter().filter(|case| case["passed"] == false).count(),
+        "duration": duration.as_secs_f64(),
+        "cases": cases,
+    });
+    serde_json::to_string_pretty(&report).unwrap()
+}
+
+/// Render test results as a JUnit XML report
+///
+/// `path` is the file that was tested.
+pub fn junit_test_report(path: &Path, cases: &[TestCase], duration: Duration) -> String {
+    fn escape(s: &str) -> String {
+        s.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('"', "&quot;")
+            .replace('\n', "&#10;")
+    }
+    let mut s = String::new();
+    let file = escape(&path.display().to_string());
+    let failures = cases.iter().filter(|case| !case.passed()).count();
+    let tests = cases.len();
+    let time = duration.as_secs_f64();
+    _ = writeln!(s, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    _ = writeln!(
+        s,
+        r#"<testsuites tests="{tests}" failures="{failures}" time="{time}">"#
+    );
+    _ = writeln!(
+        s,
+        r#"  <testsuite name="{file}" tests="{tests}" failures="{failures}" time="{time}">"#
+    );
+    for case in cases {
+        let name = escape(&case.name);
+        let classname = case.kind.name();
+        let case_file = escape(&test_case_file(path, case));
+        let line = case.span.start.line;
+        let time = case.duration.as_secs_f64();
+        let attrs = format!(
+            r#"name="{name}" classname="{classname}" file="{case_file}" line="{line}" time="{time}""#
+        );
+        let Some(error) = &case.error else {
+            _ = writeln!(s, "    <testcase {attrs}/>");
+            continue;
+        };
+        _ = writeln!(s, "    <testcase {attrs}>");
+        _ = writeln!(s, r#"      <failure message="{}">"#, escape(error));
+        if let Some(expected) = &case.expected {
+            _ = writeln!(s, "expected: {}", escape(&expected.show()));
+        }
+        if let Some(actual) = &case.actual {
+            _ = writeln!(s, "actual: {}", escape(&actual.show()));
+        }
+        _ = writeln!(s, "      </failure>");
+        _ = writeln!(s, "    </testcase>");
+    }
+    _ = writeln!(s, "  </testsuite>");
+    _ = writeln!(s, "</testsuites>");
+    s
+}
+
+fn test_case_file(path: &Path, case: &TestCase) -> String {
+    match &case.span.src {
+        InputSrc::File(file) => file.display().to_string(),
+        _ => path.display().to_string(),
+    }
+}
+
+/// Records assertions while tests run
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TestRecorder {
+    /// Assertions that have run so far
+    asserts: Vec<TestCase>,
+    /// The arguments of the last `match`
+    ///
+    /// `⍤⟜≍: expected actual` asserts that two values match and uses the actual one
+    /// as the message, so this is used to recover the expected value.
+    last_match: Option<(Value, Value)>,
+    /// An assertion that failed, but whose error may still be caught
+    failed: Option<TestCase>,
+    /// The time at which the last assertion ran
+    last_time: f64,
+}
+
+impl Uiua {
+    pub(crate) fn record_match(&mut self, a: &Value, b: &Value) {
+        if let Some(recorder) = &mut self.rt.test_recorder {
+            recorder.last_match = Some((a.clone(), b.clone()));
+        }
+    }
+    pub(crate) fn record_assert(&mut self, message: &Value, passed: bool) {
+        if self.rt.test_recorder.is_none() {
+            return;
+        }
+        let Span::Code(span) = self.span() else {
+            return;
+        };
+        let Some(recorder) = &mut self.rt.test_recorder else {
+            return;
+        };
+        let now = instant::now();
+        let duration = Duration::from_secs_f64((now - recorder.last_time).max(0.0) / 1000.0);
+        recorder.last_time = now;
+        let (expected, actual) = match recorder.last_match.take() {
+            Some((actual, expected)) if actual == *message => (Some(expected), Some(actual)),
+            _ => (None, None),
+        };
+        let name = self.asm.inputs.get_with(&span.src, |input| {
+            let line = input
+                .lines()
+                .nth(span.start.line as usize - 1)
+                .unwrap_or("");
+            line.trim().to_string()
+        });
+        let case = TestCase {
+            name,
+            kind: TestCaseKind::Assert,
+            error: (!passed).then(|| message.to_string()),
+            report: None,
+            span,
+            expected,
+            actual,
+            duration,
+        };
+        if passed {
+            recorder.asserts.push(case);
+        } else {
+            recorder.failed = Some(case);
+        }
+    }
+    /// Run an assembly's test scopes, collecting the results of each scope and assertion
+    ///
+    /// Unlike [`Uiua::run_asm`], a failing test scope does not stop the others from running.
+    /// The assembly should be compiled in [`RunMode::Test`](crate::RunMode::Test).
+    ///
+    /// Assertions outside of test scopes are recorded too.
+    /// An error outside of test scopes stops the run
+    /// and is reported as a failed [`TestCaseKind::TopLevel`] case.
+    pub fn run_tests(&mut self, asm: impl Into<Assembly>, options: &TestOptions) -> Vec<TestCase> {
+        self.asm = asm.into();
+        self.rt.execution_start = instant::now();
+        self.rt.test_recorder = Some(TestRecorder {
+            last_time: instant::now(),
+            ..Default::default()
+        });
+        let top_slices = take(&mut self.asm.top_slices);
+        let scopes = self.asm.test_scopes.clone();
+        let mut cases = Vec::new();
+        let mut parallel = Vec::new();
+        let mut i = 0;
+        let mut scope_number = 0;
+        let mut failed = false;
+        while let Some(&slice) = top_slices.get(i) {
+            let Some(scope) = scopes.iter().find(|scope| scope.slices.start == i) else {
+                // Code outside of test scopes
+                let start = instant::now();
+                let res = self.catching_crash("", |env| env.call_slice(slice));
+                let recorder = self.rt.test_recorder.take().unwrap_or_default();
+                cases.extend(recorder.asserts);
+                if let Err(e) = res.and_then(|res| res) {
+                    let duration =
+                        Duration::from_secs_f64((instant::now() - start).max(0.0) / 1000.0);
+                    let mut case = TestCase::top_level(&e, duration);
+                    let failed_assert = recorder.failed.filter(|failed| {
+                        matches!(&e.kind, UiuaErrorKind::Throw(_, Span::Code(span), _)
+                            if failed.span == *span)
+                    });
+                    if let Some(failed) = failed_assert {
+                        case.expected.clone_from(&failed.expected);
+                        case.actual.clone_from(&failed.actual);
+                        cases.push(case);
+                        cases.push(failed);
+                    } else {
+                        if case.span == CodeSpan::dummy() {
+                            case.span = self.slice_span(slice).unwrap_or(case.span);
+                        }
+                        cases.push(case);
+                    }
+                    self.reset_runtime();
+                    failed = true;
+                    break;
+                }
+                self.rt.test_recorder = Some(TestRecorder {
+                    last_time: instant::now(),
+                    ..Default::default()
+                });
+                i += 1;
+                continue;
+            };
+            scope_number += 1;
+            i = scope.slices.end.max(i + 1);
+            let name = (scope.name.clone()).unwrap_or_else(|| format!("test {scope_number}"));
+            if !options.matches(&name) {
+                continue;
+            }
+            if options.parallel {
+                // Scopes see the stack as it was when they were reached
+                parallel.push((self.rt.stack.clone(), name, scope));
+            } else {
+                cases.extend(self.run_test_scope(name, scope, &top_slices));
+            }
+        }
+        if !failed && !parallel.is_empty() {
+            let env = &*self;
+            let results: Vec<_> = (parallel.into_par_iter())
+                .map(|(stack, name, scope)| {
+                    let mut env = env.clone();
+                    env.rt.stack = stack;
+                    env.run_test_scope(name, scope, &top_slices)
+                })
+                .collect();
+            cases.extend(results.into_iter().flatten());
+        }
+        self.rt.test_recorder = None;
+        self.asm.top_slices = top_slices;
+        cases
+    }
+    /// Get the span of the code in a top-level slice
+    fn slice_span(&self, slice: FuncSlice) -> Option<CodeSpan> {
+        let mut spans = (self.asm.instrs(slice).iter())
+            .filter_map(|instr| instr.span_index())
+            .filter_map(|i| self.asm.spans.get(i)?.clone().code());
+        let first = spans.next()?;
+        let last = spans.rev().find(|span| span.src == first.src);
+        Some(last.map_or(first.clone(), |last| first.merge(last)))
+    }
+    fn run_test_scope(
+        &mut self,
+        name: String,
+        scope: &TestScope,
+        top_slices: &[FuncSlice],
+    ) -> Vec<TestCase> {
+        let start = instant::now();
+        let outer = self.rt.test_recorder.replace(TestRecorder {
+            last_time: start,
+            ..Default::default()
+        });
+        let res = self.catching_crash("", |env| {
+            (top_slices[scope.slices.clone()].iter()).try_for_each(|&slice| env.call_slice(slice))
+        });
+        let res = res.and_then(|res| res);
+        let duration = Duration::from_secs_f64((instant::now() - start).max(0.0) / 1000.0);
+        let recorder = self.rt.test_recorder.take().unwrap_or_default();
+        let mut case = TestCase {
+            name,
+            kind: TestCaseKind::Scope,
+            span: scope.span.clone(),
+            error: None,
+            report: None,
+            expected: None,
+            actual: None,
+            duration,
+        };
+        let mut asserts = recorder.asserts;
+        if let Err(e) = res {
+            case.error = Some(e.to_string());
+            case.report = Some(e.report());
+            // Report the failed assertion if it was what ended the scope
+            if let (UiuaErrorKind::Throw(_, Span::Code(span), _), Some(failed)) =
+                (&e.kind, recorder.failed)
+            {
+                if failed.span == *span {
+                    case.expected.clone_from(&failed.expected);
+                    case.actual.clone_from(&failed.actual);
+                    asserts.push(failed);
+                }
+            }
+            self.reset_runtime();
+        }
+        self.rt.test_recorder = outer;
+        let mut cases = vec![case];
+        cases.extend(asserts);
+        cases
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Compiler, PreEvalMode, RunMode};
+
+    fn run(code: &str, options: &TestOptions) -> Vec<TestCase> {
+        let mut comp = Compiler::new();
+        (comp.mode(RunMode::Test))
+            .pre_eval_mode(PreEvalMode::Lazy)
+            .print_diagnostics(false)
+            .load_str(code)
+            .unwrap();
+        Uiua::with_safe_sys().run_tests(comp.finish(), options)
+    }
+
+    fn summary(cases: &[TestCase]) -> Vec<(TestCaseKind, &str, bool)> {
+        (cases.iter())
+            .map(|case| (case.kind, case.name.as_str(), case.passed()))
+            .collect()
+    }
+
+    const CODE: &str = "\
+X ← 5
+--- # adds
+⍤⟜≍: 6 +1 X
+---
+--- # fails
+⍤⟜≍: 1 2
+⍤\"unreachable\" 0
+---";
+
+    #[test]
+    fn records_scopes_and_asserts() {
+        let cases = run(CODE, &TestOptions::default());
+        use TestCaseKind::*;
+        assert_eq!(
+            summary(&cases),
+            [
+                (Scope, "adds", true),
+                (Assert, "⍤⟜≍: 6 +1 X", true),
+                (Scope, "fails", false),
+                (Assert, "⍤⟜≍: 1 2", false),
+            ]
+        );
+        let failed = &cases[2];
+        assert_eq!(failed.expected, Some(1.into()));
+        assert_eq!(failed.actual, Some(2.into()));
+        assert_eq!(failed.span.start.line, 5);
+        assert_eq!(cases[3].span.start.line, 6);
+    }
+
+    #[test]
+    fn filter_by_name() {
+        let code = "--- # one\n⍤\"1\" 1\n---\n---\n⍤\"2\" 1\n---\n--- # one more\n⍤\"3\" 0\n---";
+        use TestCaseKind::*;
+        assert_eq!(
+            summary(&run(code, &TestOptions::default())),
+            [
+                (Scope, "one", true),
+                (Assert, "⍤\"1\" 1", true),
+                (Scope, "test 2", true),
+                (Assert, "⍤\"2\" 1", true),
+                (Scope, "one more", false),
+                (Assert, "⍤\"3\" 0", false),
+            ]
+        );
+        let options = TestOptions {
+            filter: Some("more".into()),
+            ..Default::default()
+        };
+        assert_eq!(
+            summary(&run(code, &options)),
+            [(Scope, "one more", false), (Assert, "⍤\"3\" 0", false)]
+        );
+        let options = TestOptions {
+            filter: Some("none".into()),
+            ..Default::default()
+        };
+        assert!(run(code, &options).is_empty());
+    }
+
+    #[test]
+    fn parallel_scopes() {
+        let code = "\
+X ← 3
+--- # a
+⍤⟜≍: 3 X
+---
+Y ← +1 X
+--- # b
+⍤⟜≍: 4 Y
+⍤⟜≍: 3 X
+---
+--- # c
+⍤⟜≍: 0 1
+---";
+        let sequential = run(code, &TestOptions::default());
+        let options = TestOptions {
+            parallel: true,
+            ..Default::default()
+        };
+        let parallel = run(code, &options);
+        assert_eq!(summary(&parallel), summary(&sequential));
+        use TestCaseKind::*;
+        assert_eq!(
+            summary(&parallel),
+            [
+                (Scope, "a", true),
+                (Assert, "⍤⟜≍: 3 X", true),
+                (Scope, "b", true),
+                (Assert, "⍤⟜≍: 4 Y", true),
+                (Assert, "⍤⟜≍: 3 X", true),
+                (Scope, "c", false),
+                (Assert, "⍤⟜≍: 0 1", false),
+            ]
+        );
+        assert_eq!(parallel[5].expected, Some(0.into()));
+        assert_eq!(parallel[5].actual, Some(1.into()));
+    }
+
+    #[test]
+    fn top_level_failure() {
+        let cases = run("--- # a\n⍤\"a\" 1\n---\nX ← ⊢[]", &TestOptions::default());
+        use TestCaseKind::*;
+        assert_eq!(
+            summary(&cases),
+            [
+                (Scope, "a", true),
+                (Assert, "⍤\"a\" 1", true),
+                (TopLevel, "top level", false)
+            ]
+        );
+        assert_eq!(cases[2].span.start.line, 4);
+        assert!(cases[2].error.as_ref().unwrap().contains("empty"));
+    }
+
+    #[test]
+    fn reports() {
+        let cases = run(CODE, &TestOptions::default());
+        let path = Path::new("tests.ua");
+        let duration = Duration::from_millis(5);
+
+        let json: serde_json::Value =
+            serde_json::from_str(&json_test_report(path, &cases, duration)).unwrap();
+        assert_eq!(json["file"], "tests.ua");
+        assert_eq!(json["tests"], 4);
+        assert_eq!(json["failures"], 2);
+        assert_eq!(json["duration"], 0.005);
+        let failed = &json["cases"][2];
+        assert_eq!(failed["name"], "fails");
+        assert_eq!(failed["kind"], "scope");
+        assert_eq!(failed["passed"], false);
+        assert_eq!(failed["expected"], "1");
+        assert_eq!(failed["actual"], "2");
+        assert_eq!(failed["start"]["line"], 5);
+        assert_eq!(json["cases"][1]["kind"], "assert");
+        assert_eq!(json["cases"][1]["error"], serde_json::Value::Null);
+
+        let junit = junit_test_report(path, &cases, duration);
+        assert!(junit.starts_with(r#"<?xml version="1.0" encoding="UTF-8"?>"#));
+        assert!(junit.contains(r#"<testsuites tests="4" failures="2" time="0.005">"#));
+        assert!(
+            junit.contains(r#"<testcase name="adds" classname="scope" file="tests.ua" line="2""#)
+        );
+        assert!(junit.contains(r#"name="⍤⟜≍: 6 +1 X" classname="assert""#));
+        assert_eq!(junit.matches("<failure").count(), 2);
+        assert!(junit.contains("expected: 1\nactual: 2\n"));
+        assert!(junit.trim_end().ends_with("</testsuites>"));
+    }
+
+    #[test]
+    fn junit_escapes() {
+        let mut case = TestCase::top_level(
+            &UiuaError::from(UiuaErrorKind::CompilerPanic("<a & \"b\">\nc".into())),
+            Duration::ZERO,
+        );
+        case.name = "<&>".into();
+        let junit = junit_test_report(Path::new("a\"b.ua"), &[case], Duration::ZERO);
+        assert!(junit.contains(r#"name="a&quot;b.ua""#));
+        assert!(junit.contains(r#"name="&lt;&amp;&gt;" classname="top level""#));
+        assert!(junit.contains(r#"message="&lt;a &amp; &quot;b&quot;&gt;&#10;c""#));
+    }
+}